@@ -0,0 +1,42 @@
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use tracseq_rc::{reverse_complement, reverse_complement_in_place, reverse_complement_into};
+
+/// Deterministic pseudo-random ACGT sequence so runs are comparable.
+fn sequence(len: usize) -> String {
+    let mut state: u32 = 0x2545_f491;
+    (0..len)
+        .map(|_| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            b"ACGT"[(state & 3) as usize] as char
+        })
+        .collect()
+}
+
+fn bench_reverse_complement(c: &mut Criterion) {
+    let mut group = c.benchmark_group("reverse_complement");
+    // Index cell, short read, long read / contig
+    for len in [8usize, 150, 100_000] {
+        let seq = sequence(len);
+        group.throughput(Throughput::Bytes(len as u64));
+
+        group.bench_with_input(BenchmarkId::new("str", len), &seq, |b, seq| {
+            b.iter(|| reverse_complement(black_box(seq)))
+        });
+
+        let mut buf = seq.clone().into_bytes();
+        group.bench_with_input(BenchmarkId::new("in_place", len), &len, |b, _| {
+            b.iter(|| reverse_complement_in_place(black_box(&mut buf)))
+        });
+
+        let mut out = vec![0u8; len];
+        group.bench_with_input(BenchmarkId::new("into", len), &seq, |b, seq| {
+            b.iter(|| reverse_complement_into(black_box(seq.as_bytes()), black_box(&mut out)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_reverse_complement);
+criterion_main!(benches);
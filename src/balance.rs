@@ -0,0 +1,75 @@
+//! Colour balance of index cycles on two-colour SBS chemistry (NextSeq,
+//! MiniSeq, NovaSeq). A is seen in both channels, C in red only, T in green
+//! only and G in neither, so a cycle where no sample gives signal in one of
+//! the channels cannot be registered and the lane fails to demultiplex.
+
+/// Whether `base` gives signal in the (red, green) channels. N and anything
+/// else are counted as dark, since they may be read as G.
+pub fn channels(base: u8) -> (bool, bool) {
+    match base.to_ascii_uppercase() {
+        b'A' => (true, true),
+        b'C' => (true, false),
+        b'T' | b'U' => (false, true),
+        _ => (false, false),
+    }
+}
+
+/// The bases of one index cycle across the samples of a lane.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CycleBalance {
+    /// 1-based cycle within the index read.
+    pub cycle: usize,
+    /// Samples with a base at this cycle.
+    pub bases: usize,
+    /// Samples with signal in the red channel (A or C).
+    pub red: usize,
+    /// Samples with signal in the green channel (A or T).
+    pub green: usize,
+}
+
+impl CycleBalance {
+    /// True when one of the channels has no signal at this cycle.
+    pub fn is_at_risk(&self) -> bool {
+        self.bases > 0 && (self.red == 0 || self.green == 0)
+    }
+
+    /// Why the cycle is at risk, or `None` when both channels have signal.
+    pub fn problem(&self) -> Option<&'static str> {
+        if !self.is_at_risk() {
+            None
+        } else if self.red == 0 && self.green == 0 {
+            Some("all G, no signal")
+        } else if self.red == 0 {
+            Some("no red signal, only G/T")
+        } else {
+            Some("no green signal, only G/C")
+        }
+    }
+}
+
+/// Per-cycle channel counts of the index sequences of one read in one lane.
+/// Shorter sequences simply do not contribute to the later cycles.
+pub fn cycle_balance<'a>(sequences: impl IntoIterator<Item = &'a str>) -> Vec<CycleBalance> {
+    let mut cycles: Vec<CycleBalance> = Vec::new();
+    for sequence in sequences {
+        for (i, base) in sequence.trim().bytes().enumerate() {
+            if cycles.len() <= i {
+                cycles.push(CycleBalance {
+                    cycle: i + 1,
+                    ..CycleBalance::default()
+                });
+            }
+            let (red, green) = channels(base);
+            let cycle = &mut cycles[i];
+            cycle.bases += 1;
+            cycle.red += usize::from(red);
+            cycle.green += usize::from(green);
+        }
+    }
+    cycles
+}
+
+/// The cycles of [`cycle_balance`] where a channel has no signal.
+pub fn at_risk_cycles<'a>(sequences: impl IntoIterator<Item = &'a str>) -> Vec<CycleBalance> {
+    cycle_balance(sequences).into_iter().filter(CycleBalance::is_at_risk).collect()
+}
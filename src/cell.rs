@@ -0,0 +1,332 @@
+//! Parsing of composite sheet cells such as `D701-ATTACTCG`, `D701 (ATTACTCG)`
+//! or BCL Convert style `ACGTACGT+TTGGCCAA`, so that only the sequence parts are
+//! reverse complemented and everything else is written back verbatim.
+
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+
+use regex::Regex;
+
+use crate::{IndexSequence, Operation, RcOptions, SequenceError};
+
+/// Shortest sequence part accepted by [`CellParser::detect`].
+const MIN_DETECTED_LEN: usize = 4;
+
+/// Where the sequence sits inside a cell.
+#[derive(Debug, Clone)]
+pub enum CellFormat {
+    /// The whole cell is the sequence: `ATTACTCG`.
+    Sequence,
+    /// A label, a delimiter, then the sequence: `D701-ATTACTCG`, `D701_ATTACTCG`, `D701:ATTACTCG`.
+    Labeled { delimiter: char },
+    /// A label followed by the sequence in parentheses: `D701 (ATTACTCG)`.
+    Parenthesized,
+    /// Every capture group of the regex is a sequence; the rest of the cell is kept as text.
+    Regex(Regex),
+}
+
+impl PartialEq for CellFormat {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (CellFormat::Sequence, CellFormat::Sequence) => true,
+            (CellFormat::Labeled { delimiter: a }, CellFormat::Labeled { delimiter: b }) => a == b,
+            (CellFormat::Parenthesized, CellFormat::Parenthesized) => true,
+            (CellFormat::Regex(a), CellFormat::Regex(b)) => a.as_str() == b.as_str(),
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for CellFormat {
+    type Err = String;
+
+    /// Accepts `sequence`, `label:<char>`, `paren` or `regex:<pattern>`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if let Some(pattern) = s.strip_prefix("regex:") {
+            return Regex::new(pattern)
+                .map(CellFormat::Regex)
+                .map_err(|e| format!("invalid cell regex '{}': {}", pattern, e));
+        }
+        if let Some(delimiter) = s.strip_prefix("label:") {
+            let mut chars = delimiter.chars();
+            return match (chars.next(), chars.next()) {
+                (Some(delimiter), None) => Ok(CellFormat::Labeled { delimiter }),
+                _ => Err(format!("label delimiter must be a single character, got '{}'", delimiter)),
+            };
+        }
+        match s {
+            "sequence" => Ok(CellFormat::Sequence),
+            "paren" => Ok(CellFormat::Parenthesized),
+            other => Err(format!(
+                "unknown cell format '{}' (expected sequence, label:<char>, paren or regex:<pattern>)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for CellFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CellFormat::Sequence => f.write_str("sequence"),
+            CellFormat::Labeled { delimiter } => write!(f, "label:{}", delimiter),
+            CellFormat::Parenthesized => f.write_str("paren"),
+            CellFormat::Regex(regex) => write!(f, "regex:{}", regex.as_str()),
+        }
+    }
+}
+
+/// One piece of a parsed cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CellPart {
+    /// Text kept exactly as it appeared (labels, delimiters, whitespace).
+    Text(String),
+    Sequence(IndexSequence),
+}
+
+/// A cell split into text and sequence parts. Displaying it rebuilds the cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedCell {
+    parts: Vec<CellPart>,
+}
+
+impl ParsedCell {
+    pub fn parts(&self) -> &[CellPart] {
+        &self.parts
+    }
+
+    /// The sequence parts in cell order, e.g. i7 then i5 for `i7+i5` cells.
+    pub fn sequences(&self) -> impl Iterator<Item = &IndexSequence> {
+        self.parts.iter().filter_map(|part| match part {
+            CellPart::Sequence(index) => Some(index),
+            CellPart::Text(_) => None,
+        })
+    }
+
+    /// Text before the first sequence part, e.g. `D701-`.
+    pub fn label(&self) -> Option<&str> {
+        match self.parts.first() {
+            Some(CellPart::Text(text)) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Replaces each sequence part (numbered in cell order) with `f`'s result.
+    pub fn map_sequences(&self, mut f: impl FnMut(usize, &IndexSequence) -> IndexSequence) -> ParsedCell {
+        let mut n = 0;
+        let parts = self
+            .parts
+            .iter()
+            .map(|part| match part {
+                CellPart::Sequence(index) => {
+                    let mapped = f(n, index);
+                    n += 1;
+                    CellPart::Sequence(mapped)
+                }
+                text => text.clone(),
+            })
+            .collect();
+        ParsedCell { parts }
+    }
+
+    /// Reverse complements every sequence part in place, leaving their order unchanged.
+    pub fn reverse_complement(&self, options: &RcOptions) -> ParsedCell {
+        self.apply(Operation::ReverseComplement, options)
+    }
+
+    /// Applies `operation` to every sequence part.
+    pub fn apply(&self, operation: Operation, options: &RcOptions) -> ParsedCell {
+        self.map_sequences(|_, index| index.apply(operation, options))
+    }
+
+    /// Applies `operations[n]` to sequence part `n`, so that the i7 and i5
+    /// halves of an `i7+i5` cell can be treated differently. Parts without an
+    /// operation are left unchanged.
+    pub fn apply_each(&self, operations: &[Operation], options: &RcOptions) -> ParsedCell {
+        self.map_sequences(|n, index| match operations.get(n) {
+            Some(operation) => index.apply(*operation, options),
+            None => index.clone(),
+        })
+    }
+}
+
+impl fmt::Display for ParsedCell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for part in &self.parts {
+            match part {
+                CellPart::Text(text) => f.write_str(text)?,
+                CellPart::Sequence(index) => write!(f, "{}", index)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Splits cells of one [`CellFormat`] into text and sequence parts.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CellParser {
+    format: CellFormat,
+    pair_separator: Option<char>,
+}
+
+impl CellParser {
+    /// A parser for `format` that also splits combined `i7+i5` sequences on `+`.
+    pub fn new(format: CellFormat) -> Self {
+        CellParser {
+            format,
+            pair_separator: Some('+'),
+        }
+    }
+
+    /// Changes (or with `None`, disables) the separator between combined index sequences.
+    pub fn with_pair_separator(mut self, separator: Option<char>) -> Self {
+        self.pair_separator = separator;
+        self
+    }
+
+    pub fn format(&self) -> &CellFormat {
+        &self.format
+    }
+
+    /// Parses `cell`. When the cell does not have the expected label or
+    /// parentheses the whole cell is taken as the sequence. Error offsets are
+    /// relative to the whole cell.
+    pub fn parse(&self, cell: &str) -> Result<ParsedCell, SequenceError> {
+        let mut parts = Vec::new();
+        let mut pos = 0;
+        for region in self.sequence_regions(cell) {
+            if region.start > pos {
+                parts.push(CellPart::Text(cell[pos..region.start].to_string()));
+            }
+            self.push_sequence_region(cell, region.clone(), &mut parts)?;
+            pos = region.end;
+        }
+        if pos < cell.len() {
+            parts.push(CellPart::Text(cell[pos..].to_string()));
+        }
+        Ok(ParsedCell { parts })
+    }
+
+    /// Picks the first built-in format that every non-empty sample parses
+    /// with, each sample having at least one sequence part of 4+ bases.
+    /// Returns `None` when no format fits.
+    pub fn detect<'a>(samples: impl IntoIterator<Item = &'a str>) -> Option<CellParser> {
+        let samples: Vec<&str> = samples.into_iter().filter(|s| !s.trim().is_empty()).collect();
+        if samples.is_empty() {
+            return None;
+        }
+        let candidates = [
+            CellFormat::Sequence,
+            CellFormat::Labeled { delimiter: '-' },
+            CellFormat::Labeled { delimiter: '_' },
+            CellFormat::Labeled { delimiter: ':' },
+            CellFormat::Parenthesized,
+            CellFormat::Labeled { delimiter: ' ' },
+        ];
+        candidates
+            .into_iter()
+            .map(CellParser::new)
+            .find(|parser| samples.iter().all(|sample| parser.fits(sample)))
+    }
+
+    fn fits(&self, sample: &str) -> bool {
+        match self.parse(sample) {
+            Ok(parsed) => {
+                let mut sequences = parsed.sequences().peekable();
+                sequences.peek().is_some() && sequences.all(|index| index.len() >= MIN_DETECTED_LEN)
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Byte ranges of the cell that hold sequences, in order.
+    #[allow(clippy::single_range_in_vec_init)]
+    fn sequence_regions(&self, cell: &str) -> Vec<Range<usize>> {
+        let whole = vec![0..cell.len()];
+        match &self.format {
+            CellFormat::Sequence => whole,
+            CellFormat::Labeled { delimiter } => match cell.find(*delimiter) {
+                Some(i) => vec![i + delimiter.len_utf8()..cell.len()],
+                None => whole,
+            },
+            CellFormat::Parenthesized => {
+                let trimmed_end = cell.trim_end().len();
+                match cell.rfind('(') {
+                    Some(open) if cell[..trimmed_end].ends_with(')') && open + 1 < trimmed_end => {
+                        vec![open + 1..trimmed_end - 1]
+                    }
+                    _ => whole,
+                }
+            }
+            CellFormat::Regex(regex) => match regex.captures(cell) {
+                Some(captures) if captures.len() > 1 => {
+                    captures.iter().skip(1).flatten().map(|m| m.range()).collect()
+                }
+                Some(captures) => vec![captures.get(0).map(|m| m.range()).unwrap_or(0..0)],
+                None => whole,
+            },
+        }
+    }
+
+    /// Splits a sequence region on the pair separator and surrounding
+    /// whitespace, validating each sequence.
+    fn push_sequence_region(
+        &self,
+        cell: &str,
+        region: Range<usize>,
+        parts: &mut Vec<CellPart>,
+    ) -> Result<(), SequenceError> {
+        let text = &cell[region.clone()];
+        let mut pieces: Vec<(usize, &str)> = Vec::new();
+        let mut start = 0;
+        if let Some(separator) = self.pair_separator {
+            for (i, _) in text.match_indices(separator) {
+                pieces.push((start, &text[start..i]));
+                start = i + separator.len_utf8();
+            }
+        }
+        pieces.push((start, &text[start..]));
+
+        for (n, (piece_start, piece)) in pieces.iter().enumerate() {
+            if n > 0 {
+                let separator = self.pair_separator.unwrap_or('+');
+                parts.push(CellPart::Text(separator.to_string()));
+            }
+            let leading = piece.len() - piece.trim_start().len();
+            let sequence = piece.trim();
+            if leading > 0 {
+                parts.push(CellPart::Text(piece[..leading].to_string()));
+            }
+            let byte_offset = region.start + piece_start + leading;
+            let index = IndexSequence::new(sequence)
+                .map_err(|error| error.offset_by(cell[..byte_offset].chars().count()))?;
+            parts.push(CellPart::Sequence(index));
+            let trailing = &piece[leading + sequence.len()..];
+            if !trailing.is_empty() {
+                parts.push(CellPart::Text(trailing.to_string()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for CellParser {
+    fn default() -> Self {
+        CellParser::new(CellFormat::Sequence)
+    }
+}
+
+impl FromStr for CellParser {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.parse().map(CellParser::new)
+    }
+}
+
+impl fmt::Display for CellParser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.format, f)
+    }
+}
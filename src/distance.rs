@@ -0,0 +1,113 @@
+//! Distances between index sequences and a pairwise collision check, so that
+//! samples a demultiplexer could not tell apart are caught before a run.
+
+use crate::reverse_complement;
+
+/// Number of mismatching positions, ignoring case. `None` when the lengths differ.
+pub fn hamming_distance(a: &str, b: &str) -> Option<usize> {
+    if a.len() != b.len() {
+        return None;
+    }
+    Some(
+        a.bytes()
+            .zip(b.bytes())
+            .filter(|(x, y)| !x.eq_ignore_ascii_case(y))
+            .count(),
+    )
+}
+
+/// Levenshtein distance (substitutions, insertions and deletions), ignoring case.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.as_bytes();
+    let b = b.as_bytes();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, x) in a.iter().enumerate() {
+        current[0] = i + 1;
+        for (j, y) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(!x.eq_ignore_ascii_case(y));
+            current[j + 1] = substitution.min(previous[j + 1] + 1).min(current[j] + 1);
+        }
+        std::mem::swap(&mut previous, &mut current);
+    }
+    previous[b.len()]
+}
+
+/// Hamming distance for equal-length indexes, edit distance otherwise.
+pub fn index_distance(a: &str, b: &str) -> usize {
+    hamming_distance(a, b).unwrap_or_else(|| edit_distance(a, b))
+}
+
+/// A sample and its index sequences, one per index column (i7, i5, ...).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedSample {
+    pub sample: String,
+    pub indexes: Vec<String>,
+}
+
+impl IndexedSample {
+    pub fn new(sample: impl Into<String>, indexes: Vec<String>) -> Self {
+        IndexedSample {
+            sample: sample.into(),
+            indexes,
+        }
+    }
+
+    /// Summed distance over the index columns the two samples share.
+    pub fn distance(&self, other: &IndexedSample) -> usize {
+        self.indexes
+            .iter()
+            .zip(&other.indexes)
+            .map(|(a, b)| index_distance(a, b))
+            .sum()
+    }
+
+    /// Distance to `other` once `other`'s indexes are reverse complemented.
+    pub fn rc_distance(&self, other: &IndexedSample) -> usize {
+        self.indexes
+            .iter()
+            .zip(&other.indexes)
+            .map(|(a, b)| index_distance(a, &reverse_complement(b)))
+            .sum()
+    }
+}
+
+/// Two samples whose index combinations are within the allowed mismatches.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    pub first: String,
+    pub second: String,
+    pub distance: usize,
+    /// Distance when one of the two samples has its indexes reverse complemented.
+    pub rc_distance: usize,
+    /// Whether the pair would still collide with one sample reverse
+    /// complemented, i.e. fixing one sample's orientation does not help.
+    pub collides_after_rc: bool,
+}
+
+/// Every pair of samples at most `max_mismatches` apart, in sheet order.
+/// Samples without any index sequence are skipped.
+pub fn find_collisions(samples: &[IndexedSample], max_mismatches: usize) -> Vec<Collision> {
+    let samples: Vec<&IndexedSample> = samples
+        .iter()
+        .filter(|s| s.indexes.iter().any(|index| !index.is_empty()))
+        .collect();
+    let mut collisions = Vec::new();
+    for (i, first) in samples.iter().enumerate() {
+        for second in &samples[i + 1..] {
+            let distance = first.distance(second);
+            if distance > max_mismatches {
+                continue;
+            }
+            let rc_distance = first.rc_distance(second);
+            collisions.push(Collision {
+                first: first.sample.clone(),
+                second: second.sample.clone(),
+                distance,
+                rc_distance,
+                collides_after_rc: rc_distance <= max_mismatches,
+            });
+        }
+    }
+    collisions
+}
@@ -0,0 +1,294 @@
+//! Demultiplexer sample sheets built from processed rows: bcl2fastq v1
+//! `[Data]`, BCL Convert v2 `[BCLConvert_Data]`, the cellranger mkfastq
+//! simple CSV (`Lane,Sample,Index`), the Element AVITI run manifest
+//! (`[SAMPLES]`) and MGI DNBSEQ barcode lists.
+//!
+//! Rows carry i5 as read on the forward strand; each target then writes it
+//! the way its software expects. bcl2fastq wants index2 as the instrument
+//! reads it, so it needs the workflow. BCL Convert v2 sheets get Index2 the
+//! same way when a workflow is given, as the sample sheet processing writes
+//! it, and for the forward strand otherwise. mkfastq takes
+//! 10x set names and works out the orientation itself. Run manifests list
+//! Index2 as the AVITI reads it (see [`Instrument::Aviti`]) unless a workflow
+//! is given, and barcode lists follow the DNBSEQ orientation table of
+//! [`crate::mgi`].
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::instrument::{IndexRole, Instrument, Workflow};
+use crate::mgi::{BarcodeMode, MgiBarcode, write_barcodes};
+use crate::reverse_complement;
+
+/// Lines before the sample table of a bcl2fastq sheet.
+const BCL2FASTQ_HEADER: [&str; 6] = [
+    "[Header]",
+    "IEMFileVersion,4",
+    "Workflow,GenerateFASTQ",
+    "Application,FASTQ Only",
+    "",
+    "[Data]",
+];
+
+/// Lines before the sample table of a BCL Convert sheet.
+const BCL_CONVERT_HEADER: [&str; 7] = [
+    "[Header]",
+    "FileFormatVersion,2",
+    "",
+    "[BCLConvert_Settings]",
+    "CreateFastqForIndexReads,0",
+    "",
+    "[BCLConvert_Data]",
+];
+
+/// Lines before the sample table of an AVITI run manifest.
+const RUN_MANIFEST_HEADER: [&str; 7] = [
+    "[RUNVALUES]",
+    "KeyName,Value",
+    "",
+    "[SETTINGS]",
+    "SettingName,Value",
+    "",
+    "[SAMPLES]",
+];
+
+/// The sample sheet format to export to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ExportTarget {
+    /// bcl2fastq / Illumina Experiment Manager v1 sheet with a `[Data]` section.
+    Bcl2fastq,
+    /// BCL Convert v2 sheet with a `[BCLConvert_Data]` section.
+    BclConvert,
+    /// cellranger mkfastq simple CSV.
+    Mkfastq,
+    /// Element AVITI run manifest with a `[SAMPLES]` section.
+    Aviti,
+    /// MGI DNBSEQ barcode list for single- or dual-barcode splitting.
+    Mgi,
+}
+
+impl ExportTarget {
+    /// The value accepted by `--export`, also used in the output file name.
+    pub fn id(self) -> &'static str {
+        match self {
+            ExportTarget::Bcl2fastq => "bcl2fastq",
+            ExportTarget::BclConvert => "bclconvert",
+            ExportTarget::Mkfastq => "mkfastq",
+            ExportTarget::Aviti => "aviti",
+            ExportTarget::Mgi => "mgi",
+        }
+    }
+
+    /// Extension of the exported file.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ExportTarget::Mgi => "txt",
+            _ => "csv",
+        }
+    }
+}
+
+impl FromStr for ExportTarget {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "bcl2fastq" | "v1" => Ok(ExportTarget::Bcl2fastq),
+            "bclconvert" | "bcl-convert" | "v2" => Ok(ExportTarget::BclConvert),
+            "mkfastq" | "cellranger" | "cellranger-mkfastq" => Ok(ExportTarget::Mkfastq),
+            "aviti" | "runmanifest" | "element" => Ok(ExportTarget::Aviti),
+            "mgi" | "dnbseq" | "splitbarcode" => Ok(ExportTarget::Mgi),
+            other => Err(format!(
+                "unknown export target '{}' (expected bcl2fastq, bclconvert, mkfastq, aviti or mgi)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for ExportTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// One sample of the exported sheet.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExportRow {
+    pub sample: String,
+    /// `None` when the sample runs in every lane.
+    pub lane: Option<String>,
+    /// The i7 sequences; more than one for pooled oligos such as a 10x
+    /// single-index set, which are written one row each.
+    pub i7: Vec<String>,
+    /// i5 as read on the forward strand; empty for single-indexed samples.
+    pub i5: String,
+    /// The 10x index set the row's indexes came from, if any.
+    pub tenx_set: Option<String>,
+}
+
+/// An exported sheet and what could not be carried over.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleSheetExport {
+    pub text: String,
+    pub warnings: Vec<String>,
+}
+
+/// A sample ID demultiplexers accept: letters, digits, `-` and `_`, with
+/// anything else replaced by `_`.
+pub fn sample_id(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+/// Writes `rows` as a `target` sheet. `workflow` is the i5 workflow of the
+/// instrument the sheet is for; bcl2fastq sheets with i5 cannot be written
+/// without it, BCL Convert sheets keep i5 forward, and run manifests use the
+/// AVITI's own when it is `None`.
+/// Rows without a lane are repeated for every lane when other rows have one.
+pub fn export_sample_sheet(
+    target: ExportTarget,
+    rows: &[ExportRow],
+    workflow: Option<Workflow>,
+) -> Result<SampleSheetExport, String> {
+    let has_i5 = rows.iter().any(|row| !row.i5.is_empty());
+    if target == ExportTarget::Bcl2fastq && has_i5 && workflow.is_none() {
+        return Err(
+            "bcl2fastq reads index2 as the instrument reads it; pass --instrument or --workflow to export it"
+                .to_string(),
+        );
+    }
+    let mut lanes: Vec<&str> = Vec::new();
+    for lane in rows.iter().filter_map(|row| row.lane.as_deref()) {
+        if !lanes.contains(&lane) {
+            lanes.push(lane);
+        }
+    }
+    let row_lanes = |row: &ExportRow| -> Vec<Option<String>> {
+        match (&row.lane, lanes.is_empty()) {
+            (Some(lane), _) => vec![Some(lane.clone())],
+            (None, true) => vec![None],
+            (None, false) => lanes.iter().map(|lane| Some(lane.to_string())).collect(),
+        }
+    };
+
+    let mut warnings = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+    match target {
+        ExportTarget::Bcl2fastq | ExportTarget::BclConvert | ExportTarget::Aviti => {
+            // Run manifests name the sample first and put Lane last
+            let (header, sample_name, i7_name, i5_name) = match target {
+                ExportTarget::Bcl2fastq => (&BCL2FASTQ_HEADER[..], "Sample_ID", "index", "index2"),
+                ExportTarget::Aviti => (&RUN_MANIFEST_HEADER[..], "SampleName", "Index1", "Index2"),
+                _ => (&BCL_CONVERT_HEADER[..], "Sample_ID", "Index", "Index2"),
+            };
+            let lane_last = target == ExportTarget::Aviti;
+            lines.extend(header.iter().map(|line| line.to_string()));
+            let mut columns = Vec::new();
+            if !lanes.is_empty() && !lane_last {
+                columns.push("Lane");
+            }
+            columns.extend([sample_name, i7_name]);
+            if has_i5 {
+                columns.push(i5_name);
+            }
+            if !lanes.is_empty() && lane_last {
+                columns.push("Lane");
+            }
+            lines.push(columns.join(","));
+            let i5_workflow = match target {
+                ExportTarget::Bcl2fastq | ExportTarget::BclConvert => workflow,
+                ExportTarget::Aviti => Some(workflow.unwrap_or(Instrument::Aviti.workflow())),
+                _ => None,
+            };
+            for row in rows {
+                let i5 = match i5_workflow {
+                    Some(Workflow::ReverseComplement) => reverse_complement(&row.i5),
+                    _ => row.i5.clone(),
+                };
+                // A sample without i7 still gets its row
+                let i7s: Vec<&str> = match row.i7.as_slice() {
+                    [] => vec![""],
+                    i7 => i7.iter().map(String::as_str).collect(),
+                };
+                for lane in row_lanes(row) {
+                    for i7 in &i7s {
+                        let mut fields: Vec<&str> = Vec::new();
+                        if let Some(lane) = lane.as_deref().filter(|_| !lane_last) {
+                            fields.push(lane);
+                        }
+                        let sample = sample_id(&row.sample);
+                        fields.extend([sample.as_str(), i7]);
+                        if has_i5 {
+                            fields.push(&i5);
+                        }
+                        if let Some(lane) = lane.as_deref().filter(|_| lane_last) {
+                            fields.push(lane);
+                        }
+                        lines.push(fields.join(","));
+                    }
+                }
+            }
+        }
+        ExportTarget::Mkfastq => {
+            lines.push("Lane,Sample,Index".to_string());
+            for row in rows {
+                let indexes: Vec<&str> = match &row.tenx_set {
+                    Some(set) => vec![set],
+                    None => {
+                        if !row.i5.is_empty() {
+                            warnings.push(format!(
+                                "{}: the simple CSV has no i5 column and the indexes are not a 10x set; only i7 was written",
+                                row.sample
+                            ));
+                        }
+                        row.i7.iter().map(String::as_str).collect()
+                    }
+                };
+                for lane in row_lanes(row) {
+                    for index in &indexes {
+                        let lane = lane.as_deref().unwrap_or("*");
+                        lines.push(format!("{},{},{}", lane, sample_id(&row.sample), index));
+                    }
+                }
+            }
+        }
+        ExportTarget::Mgi => return Ok(export_barcodes(rows, has_i5, lanes.len())),
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    Ok(SampleSheetExport { text, warnings })
+}
+
+/// A barcode list for dual-barcode splitting when any row has i5. A list
+/// covers one lane, so a sample in several lanes is listed once.
+fn export_barcodes(rows: &[ExportRow], has_i5: bool, lane_count: usize) -> SampleSheetExport {
+    let mode = if has_i5 { BarcodeMode::Dual } else { BarcodeMode::Single };
+    let mut warnings = Vec::new();
+    if lane_count > 1 {
+        warnings.push("a barcode list covers one lane; the samples of every lane were listed together".to_string());
+    }
+    let mut barcodes: Vec<MgiBarcode> = Vec::new();
+    for row in rows {
+        if row.i7.is_empty() || (mode == BarcodeMode::Dual && row.i5.is_empty()) {
+            warnings.push(format!("{}: a {} list needs every barcode; the sample was left out", row.sample, mode));
+            continue;
+        }
+        for i7 in &row.i7 {
+            let barcode = MgiBarcode {
+                name: sample_id(&row.sample),
+                barcode: mode.barcode(IndexRole::I7, i7).unwrap_or_default(),
+                barcode2: mode.barcode(IndexRole::I5, &row.i5),
+            };
+            if !barcodes.contains(&barcode) {
+                barcodes.push(barcode);
+            }
+        }
+    }
+    SampleSheetExport {
+        text: write_barcodes(&barcodes),
+        warnings,
+    }
+}
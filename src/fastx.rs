@@ -0,0 +1,317 @@
+//! Streaming FASTA/FASTQ reading and writing for whole-file reverse complementing.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+
+use crate::reverse_complement_in_place;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FastxFormat {
+    Fasta,
+    Fastq,
+}
+
+/// One FASTA or FASTQ record.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FastxRecord {
+    /// Header line without the leading `>` or `@`.
+    pub header: String,
+    pub seq: Vec<u8>,
+    /// Quality string; `None` for FASTA records.
+    pub qual: Option<Vec<u8>>,
+    /// Sequence line width of a wrapped FASTA record, so it can be written back the same way.
+    pub line_width: Option<usize>,
+}
+
+impl FastxRecord {
+    /// The record ID: the header up to the first whitespace.
+    pub fn id(&self) -> &str {
+        self.header.split_whitespace().next().unwrap_or("")
+    }
+
+    /// Reverse complements the sequence and reverses the qualities to match.
+    pub fn reverse_complement(&mut self) {
+        reverse_complement_in_place(&mut self.seq);
+        if let Some(qual) = &mut self.qual {
+            qual.reverse();
+        }
+    }
+
+    /// Appends `tag` to the record ID, keeping any header comment after it.
+    pub fn tag(&mut self, tag: &str) {
+        let id_len = self.id().len();
+        let start = self.header.len() - self.header.trim_start().len();
+        self.header.insert_str(start + id_len, tag);
+    }
+
+    pub fn write_to<W: Write>(&self, out: &mut W) -> io::Result<()> {
+        match &self.qual {
+            Some(qual) => {
+                out.write_all(b"@")?;
+                out.write_all(self.header.as_bytes())?;
+                out.write_all(b"\n")?;
+                out.write_all(&self.seq)?;
+                out.write_all(b"\n+\n")?;
+                out.write_all(qual)?;
+                out.write_all(b"\n")
+            }
+            None => {
+                out.write_all(b">")?;
+                out.write_all(self.header.as_bytes())?;
+                out.write_all(b"\n")?;
+                let width = self.line_width.unwrap_or(self.seq.len()).max(1);
+                for line in self.seq.chunks(width) {
+                    out.write_all(line)?;
+                    out.write_all(b"\n")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Iterator over the records of a FASTA or FASTQ stream. The format is taken
+/// from the first record marker (`>` or `@`).
+pub struct FastxReader<R: BufRead> {
+    inner: R,
+    format: Option<FastxFormat>,
+    /// Header line of the next FASTA record, already consumed while reading the previous one.
+    pending_header: Option<String>,
+    line: Vec<u8>,
+    line_number: usize,
+}
+
+impl<R: BufRead> FastxReader<R> {
+    pub fn new(inner: R) -> Self {
+        FastxReader {
+            inner,
+            format: None,
+            pending_header: None,
+            line: Vec::new(),
+            line_number: 0,
+        }
+    }
+
+    /// The detected format, once the first record has been read.
+    pub fn format(&self) -> Option<FastxFormat> {
+        self.format
+    }
+
+    /// Reads the next line into `self.line` without its line ending. Returns false at end of input.
+    fn next_line(&mut self) -> io::Result<bool> {
+        self.line.clear();
+        if self.inner.read_until(b'\n', &mut self.line)? == 0 {
+            return Ok(false);
+        }
+        self.line_number += 1;
+        while matches!(self.line.last(), Some(b'\n' | b'\r')) {
+            self.line.pop();
+        }
+        Ok(true)
+    }
+
+    fn invalid(&self, message: &str) -> io::Error {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("line {}: {}", self.line_number, message),
+        )
+    }
+
+    fn header_text(&self) -> io::Result<String> {
+        String::from_utf8(self.line[1..].to_vec())
+            .map_err(|_| self.invalid("record header is not valid UTF-8"))
+    }
+
+    fn read_record(&mut self) -> io::Result<Option<FastxRecord>> {
+        if self.format.is_none() {
+            // Skip leading blank lines, then detect the format from the first marker
+            loop {
+                if !self.next_line()? {
+                    return Ok(None);
+                }
+                if !self.line.is_empty() {
+                    break;
+                }
+            }
+            self.format = match self.line[0] {
+                b'>' => Some(FastxFormat::Fasta),
+                b'@' => Some(FastxFormat::Fastq),
+                _ => return Err(self.invalid("expected a FASTA ('>') or FASTQ ('@') record")),
+            };
+            let header = self.header_text()?;
+            self.pending_header = Some(header);
+        }
+        match self.format {
+            Some(FastxFormat::Fasta) => self.read_fasta(),
+            _ => self.read_fastq(),
+        }
+    }
+
+    fn read_fasta(&mut self) -> io::Result<Option<FastxRecord>> {
+        let header = match self.pending_header.take() {
+            Some(header) => header,
+            None => return Ok(None),
+        };
+        let mut seq = Vec::new();
+        let mut first_line_len = None;
+        let mut wrapped = false;
+        while self.next_line()? {
+            if self.line.first() == Some(&b'>') {
+                self.pending_header = Some(self.header_text()?);
+                break;
+            }
+            if self.line.is_empty() {
+                continue;
+            }
+            match first_line_len {
+                None => first_line_len = Some(self.line.len()),
+                Some(_) => wrapped = true,
+            }
+            seq.extend_from_slice(&self.line);
+        }
+        Ok(Some(FastxRecord {
+            header,
+            seq,
+            qual: None,
+            line_width: if wrapped { first_line_len } else { None },
+        }))
+    }
+
+    fn read_fastq(&mut self) -> io::Result<Option<FastxRecord>> {
+        let header = match self.pending_header.take() {
+            Some(header) => header,
+            None => {
+                loop {
+                    if !self.next_line()? {
+                        return Ok(None);
+                    }
+                    if !self.line.is_empty() {
+                        break;
+                    }
+                }
+                if self.line.first() != Some(&b'@') {
+                    return Err(self.invalid("expected a FASTQ record starting with '@'"));
+                }
+                self.header_text()?
+            }
+        };
+        if !self.next_line()? {
+            return Err(self.invalid("FASTQ record is missing its sequence line"));
+        }
+        let seq = self.line.clone();
+        if !self.next_line()? || self.line.first() != Some(&b'+') {
+            return Err(self.invalid("FASTQ record is missing its '+' separator line"));
+        }
+        if !self.next_line()? {
+            return Err(self.invalid("FASTQ record is missing its quality line"));
+        }
+        if self.line.len() != seq.len() {
+            return Err(self.invalid("quality length does not match sequence length"));
+        }
+        Ok(Some(FastxRecord {
+            header,
+            seq,
+            qual: Some(self.line.clone()),
+            line_width: None,
+        }))
+    }
+}
+
+impl<R: BufRead> Iterator for FastxReader<R> {
+    type Item = io::Result<FastxRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_record().transpose()
+    }
+}
+
+/// Opens `path` (or stdin for `None` / `-`) for reading, transparently
+/// decompressing gzip input.
+pub fn open_input(path: Option<&Path>) -> io::Result<Box<dyn BufRead>> {
+    let raw: Box<dyn Read> = match path {
+        Some(path) if path != Path::new("-") => Box::new(File::open(path)?),
+        _ => Box::new(io::stdin()),
+    };
+    let mut buffered = BufReader::new(raw);
+    if buffered.fill_buf()?.starts_with(&GZIP_MAGIC) {
+        Ok(Box::new(BufReader::new(MultiGzDecoder::new(buffered))))
+    } else {
+        Ok(Box::new(buffered))
+    }
+}
+
+/// Options for [`reverse_complement_fastx`].
+#[derive(Debug, Clone, Default)]
+pub struct FastxOptions {
+    /// Text appended to every record ID, e.g. `/rc`.
+    pub tag: Option<String>,
+}
+
+/// Totals returned by [`reverse_complement_fastx`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FastxSummary {
+    pub format: Option<FastxFormat>,
+    pub records: usize,
+    pub bases: usize,
+}
+
+/// Streams every record from `reader` to `writer`, reverse complemented.
+pub fn reverse_complement_records<R: BufRead, W: Write>(
+    reader: R,
+    writer: &mut W,
+    options: &FastxOptions,
+) -> io::Result<FastxSummary> {
+    let mut records = FastxReader::new(reader);
+    let mut summary = FastxSummary::default();
+    for record in records.by_ref() {
+        let mut record = record?;
+        record.reverse_complement();
+        if let Some(tag) = &options.tag {
+            record.tag(tag);
+        }
+        record.write_to(writer)?;
+        summary.records += 1;
+        summary.bases += record.seq.len();
+    }
+    summary.format = records.format();
+    Ok(summary)
+}
+
+/// Reverse complements a FASTA/FASTQ file. `None` or `-` means stdin/stdout;
+/// an output path ending in `.gz` is gzip-compressed.
+pub fn reverse_complement_fastx(
+    input: Option<&Path>,
+    output: Option<&Path>,
+    options: &FastxOptions,
+) -> io::Result<FastxSummary> {
+    let reader = open_input(input)?;
+    match output {
+        Some(path) if path != Path::new("-") => {
+            let file = BufWriter::new(File::create(path)?);
+            if path.extension().is_some_and(|ext| ext == "gz") {
+                let mut encoder = GzEncoder::new(file, Compression::default());
+                let summary = reverse_complement_records(reader, &mut encoder, options)?;
+                encoder.finish()?.flush()?;
+                Ok(summary)
+            } else {
+                let mut file = file;
+                let summary = reverse_complement_records(reader, &mut file, options)?;
+                file.flush()?;
+                Ok(summary)
+            }
+        }
+        _ => {
+            let mut stdout = BufWriter::new(io::stdout().lock());
+            let summary = reverse_complement_records(reader, &mut stdout, options)?;
+            stdout.flush()?;
+            Ok(summary)
+        }
+    }
+}
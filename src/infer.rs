@@ -0,0 +1,144 @@
+//! Counting the index sequences a sequencer actually saw, from FASTQ read
+//! headers (`1:N:0:ATTACTCG+TATAGCCT`) or I1/I2 index reads, to tell whether
+//! a sheet's i5 has to be reverse complemented.
+
+use std::collections::HashMap;
+use std::io::{self, BufRead};
+
+use crate::fastx::{FastxReader, FastxRecord};
+use crate::{Orientation, is_nucleotide, reverse_complement};
+
+/// The `i7+i5` barcode at the end of an Illumina read header, e.g.
+/// `ATTACTCG` / `TATAGCCT` from `@M001:1:FC:1:1101:1:1 1:N:0:ATTACTCG+TATAGCCT`.
+/// Single-index runs give an empty i5; a sample number instead of a barcode gives `None`.
+pub fn header_barcode(header: &str) -> Option<(String, String)> {
+    let comment = header.split_whitespace().nth(1)?;
+    let barcode = comment.rsplit(':').next()?;
+    let (i7, i5) = barcode.split_once('+').unwrap_or((barcode, ""));
+    let is_sequence = |s: &str| s.chars().all(is_nucleotide);
+    if i7.is_empty() || !is_sequence(i7) || !is_sequence(i5) {
+        return None;
+    }
+    Some((i7.to_ascii_uppercase(), i5.to_ascii_uppercase()))
+}
+
+/// Observed `(i7, i5)` barcode counts. Either side is empty when it was not read.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BarcodeCounts {
+    pairs: HashMap<(String, String), u64>,
+    i5: HashMap<String, u64>,
+    total: u64,
+    /// Whether any counted barcode has an i7.
+    has_i7: bool,
+}
+
+impl BarcodeCounts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, i7: &str, i5: &str) {
+        let (i7, i5) = (i7.to_ascii_uppercase(), i5.to_ascii_uppercase());
+        self.has_i7 |= !i7.is_empty();
+        *self.i5.entry(i5.clone()).or_default() += 1;
+        *self.pairs.entry((i7, i5)).or_default() += 1;
+        self.total += 1;
+    }
+
+    /// Counts the header barcodes of up to `max_reads` records. Returns the number of records read.
+    pub fn add_headers<R: BufRead>(&mut self, reader: FastxReader<R>, max_reads: usize) -> io::Result<usize> {
+        let mut read = 0;
+        for record in reader.take(max_reads) {
+            let record = record?;
+            read += 1;
+            if let Some((i7, i5)) = header_barcode(&record.header) {
+                self.add(&i7, &i5);
+            }
+        }
+        Ok(read)
+    }
+
+    /// Counts up to `max_reads` index reads: I1 (i7) and I2 (i5) records are
+    /// paired in file order, and either file may be missing.
+    pub fn add_index_reads<R1: BufRead, R2: BufRead>(
+        &mut self,
+        i1: Option<FastxReader<R1>>,
+        i2: Option<FastxReader<R2>>,
+        max_reads: usize,
+    ) -> io::Result<usize> {
+        let mut i1 = i1.map(|reader| reader.take(max_reads));
+        let mut i2 = i2.map(|reader| reader.take(max_reads));
+        let mut read = 0;
+        loop {
+            let i7 = i1.as_mut().and_then(Iterator::next).transpose()?;
+            let i5 = i2.as_mut().and_then(Iterator::next).transpose()?;
+            if i7.is_none() && i5.is_none() {
+                return Ok(read);
+            }
+            let sequence = |record: Option<FastxRecord>| {
+                record
+                    .map(|record| String::from_utf8_lossy(&record.seq).into_owned())
+                    .unwrap_or_default()
+            };
+            self.add(&sequence(i7), &sequence(i5));
+            read += 1;
+        }
+    }
+
+    /// Number of barcodes counted.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Reads with this barcode. When the reads carry no i7 (an I2 file on its
+    /// own), or `i7` is `None`, only i5 is compared.
+    pub fn count(&self, i7: Option<&str>, i5: &str) -> u64 {
+        let i5 = i5.to_ascii_uppercase();
+        match i7 {
+            Some(i7) if self.has_i7 => self
+                .pairs
+                .get(&(i7.to_ascii_uppercase(), i5))
+                .copied()
+                .unwrap_or(0),
+            _ => self.i5.get(&i5).copied().unwrap_or(0),
+        }
+    }
+
+    /// The `n` most frequent barcodes, most frequent first.
+    pub fn top(&self, n: usize) -> Vec<(&str, &str, u64)> {
+        let mut top: Vec<(&str, &str, u64)> = self
+            .pairs
+            .iter()
+            .map(|((i7, i5), count)| (i7.as_str(), i5.as_str(), *count))
+            .collect();
+        top.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| (a.0, a.1).cmp(&(b.0, b.1))));
+        top.truncate(n);
+        top
+    }
+
+    /// How often a sample's i5 was seen as written and as its reverse complement.
+    pub fn i5_evidence(&self, i7: Option<&str>, i5: &str) -> I5Evidence {
+        I5Evidence {
+            as_written: self.count(i7, i5),
+            reverse_complement: self.count(i7, &reverse_complement(i5)),
+        }
+    }
+}
+
+/// Read counts for a sample's i5 in both orientations.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct I5Evidence {
+    pub as_written: u64,
+    pub reverse_complement: u64,
+}
+
+impl I5Evidence {
+    /// The orientation seen more often, or `None` without reads or on a tie.
+    pub fn verdict(&self) -> Option<Orientation> {
+        match self.as_written.cmp(&self.reverse_complement) {
+            std::cmp::Ordering::Greater => Some(Orientation::Forward),
+            std::cmp::Ordering::Less => Some(Orientation::ReverseComplement),
+            std::cmp::Ordering::Equal => None,
+        }
+    }
+}
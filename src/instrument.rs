@@ -0,0 +1,259 @@
+//! Which way round each sequencer reads the i5 index, so that only the
+//! columns that actually need it are reverse complemented.
+//!
+//! Index sequences are assumed to be entered as for the forward strand
+//! workflow, the way kit vendors list them. Instruments on the reverse
+//! complement workflow read i5 on the other strand, so their i5 sequences
+//! have to be flipped; i7 is read the same way everywhere.
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Operation;
+
+/// How an instrument reads the i5 index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Workflow {
+    /// i5 is read as entered (workflow A).
+    Forward,
+    /// i5 is read as its reverse complement (workflow B).
+    ReverseComplement,
+}
+
+impl Workflow {
+    /// What has to be done to forward-strand i5 sequences for this workflow.
+    pub fn i5_operation(self) -> Operation {
+        match self {
+            Workflow::Forward => Operation::Passthrough,
+            Workflow::ReverseComplement => Operation::ReverseComplement,
+        }
+    }
+}
+
+impl FromStr for Workflow {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "forward" | "forward-strand" | "a" => Ok(Workflow::Forward),
+            "reverse-complement" | "rc" | "b" => Ok(Workflow::ReverseComplement),
+            other => Err(format!(
+                "unknown workflow '{}' (expected forward or reverse-complement)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Workflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Workflow::Forward => f.write_str("forward strand"),
+            Workflow::ReverseComplement => f.write_str("reverse complement"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Instrument {
+    MiSeq,
+    HiSeq2000,
+    HiSeq2500,
+    HiSeq3000,
+    HiSeq4000,
+    HiSeqX,
+    ISeq100,
+    MiniSeq,
+    NextSeq500,
+    NextSeq1000,
+    /// NovaSeq 6000 with v1.0 reagent kits.
+    NovaSeq6000V1_0,
+    /// NovaSeq 6000 with v1.5 reagent kits.
+    NovaSeq6000V1_5,
+    NovaSeqX,
+    /// Element AVITI, for Illumina-compatible libraries. Element's run
+    /// manifest documentation (docs.elembio.io, "Run Manifest") has `Index1`
+    /// entered as i7 is listed and `Index2` as the AVITI reads it, which for
+    /// i5 listed on the forward strand is its reverse complement, as on the
+    /// workflow B Illumina instruments. `--workflow` overrides it for kits
+    /// documented otherwise.
+    Aviti,
+}
+
+impl Instrument {
+    pub const ALL: [Instrument; 14] = [
+        Instrument::MiSeq,
+        Instrument::HiSeq2000,
+        Instrument::HiSeq2500,
+        Instrument::HiSeq3000,
+        Instrument::HiSeq4000,
+        Instrument::HiSeqX,
+        Instrument::ISeq100,
+        Instrument::MiniSeq,
+        Instrument::NextSeq500,
+        Instrument::NextSeq1000,
+        Instrument::NovaSeq6000V1_0,
+        Instrument::NovaSeq6000V1_5,
+        Instrument::NovaSeqX,
+        Instrument::Aviti,
+    ];
+
+    /// The built-in orientation table.
+    pub fn workflow(self) -> Workflow {
+        match self {
+            Instrument::MiSeq
+            | Instrument::HiSeq2000
+            | Instrument::HiSeq2500
+            | Instrument::NovaSeq6000V1_0 => Workflow::Forward,
+            Instrument::HiSeq3000
+            | Instrument::HiSeq4000
+            | Instrument::HiSeqX
+            | Instrument::ISeq100
+            | Instrument::MiniSeq
+            | Instrument::NextSeq500
+            | Instrument::NextSeq1000
+            | Instrument::NovaSeq6000V1_5
+            | Instrument::NovaSeqX
+            | Instrument::Aviti => Workflow::ReverseComplement,
+        }
+    }
+
+    /// Whether the instrument uses two-colour chemistry, where G gives no
+    /// signal and index cycles need colour balance.
+    pub fn is_two_colour(self) -> bool {
+        matches!(
+            self,
+            Instrument::MiniSeq
+                | Instrument::NextSeq500
+                | Instrument::NextSeq1000
+                | Instrument::NovaSeq6000V1_0
+                | Instrument::NovaSeq6000V1_5
+                | Instrument::NovaSeqX
+        )
+    }
+
+    /// The value accepted by `--instrument`.
+    pub fn id(self) -> &'static str {
+        match self {
+            Instrument::MiSeq => "miseq",
+            Instrument::HiSeq2000 => "hiseq2000",
+            Instrument::HiSeq2500 => "hiseq2500",
+            Instrument::HiSeq3000 => "hiseq3000",
+            Instrument::HiSeq4000 => "hiseq4000",
+            Instrument::HiSeqX => "hiseqx",
+            Instrument::ISeq100 => "iseq100",
+            Instrument::MiniSeq => "miniseq",
+            Instrument::NextSeq500 => "nextseq500",
+            Instrument::NextSeq1000 => "nextseq1000",
+            Instrument::NovaSeq6000V1_0 => "novaseq6000-v1.0",
+            Instrument::NovaSeq6000V1_5 => "novaseq6000-v1.5",
+            Instrument::NovaSeqX => "novaseqx",
+            Instrument::Aviti => "aviti",
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Instrument::MiSeq => "MiSeq",
+            Instrument::HiSeq2000 => "HiSeq 2000",
+            Instrument::HiSeq2500 => "HiSeq 2500",
+            Instrument::HiSeq3000 => "HiSeq 3000",
+            Instrument::HiSeq4000 => "HiSeq 4000",
+            Instrument::HiSeqX => "HiSeq X",
+            Instrument::ISeq100 => "iSeq 100",
+            Instrument::MiniSeq => "MiniSeq",
+            Instrument::NextSeq500 => "NextSeq 500/550",
+            Instrument::NextSeq1000 => "NextSeq 1000/2000",
+            Instrument::NovaSeq6000V1_0 => "NovaSeq 6000 (v1.0 reagents)",
+            Instrument::NovaSeq6000V1_5 => "NovaSeq 6000 (v1.5 reagents)",
+            Instrument::NovaSeqX => "NovaSeq X",
+            Instrument::Aviti => "Element AVITI",
+        }
+    }
+}
+
+impl FromStr for Instrument {
+    type Err = String;
+
+    /// Accepts the ids listed by [`Instrument::id`], ignoring case, spaces,
+    /// `-` and `_`, plus common aliases such as `nextseq550` or `novaseqxplus`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let key: String = s
+            .chars()
+            .filter(|c| !matches!(c, ' ' | '-' | '_'))
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let instrument = match key.as_str() {
+            "miseq" | "miseqdx" => Instrument::MiSeq,
+            "hiseq2000" => Instrument::HiSeq2000,
+            "hiseq2500" => Instrument::HiSeq2500,
+            "hiseq3000" => Instrument::HiSeq3000,
+            "hiseq4000" => Instrument::HiSeq4000,
+            "hiseqx" => Instrument::HiSeqX,
+            "iseq" | "iseq100" => Instrument::ISeq100,
+            "miniseq" => Instrument::MiniSeq,
+            "nextseq" | "nextseq500" | "nextseq550" => Instrument::NextSeq500,
+            "nextseq1000" | "nextseq2000" => Instrument::NextSeq1000,
+            "novaseq6000v1.0" | "novaseqv1.0" => Instrument::NovaSeq6000V1_0,
+            "novaseq6000v1.5" | "novaseqv1.5" => Instrument::NovaSeq6000V1_5,
+            "novaseqx" | "novaseqxplus" => Instrument::NovaSeqX,
+            "aviti" | "elementaviti" => Instrument::Aviti,
+            "novaseq" | "novaseq6000" => {
+                return Err(
+                    "the NovaSeq 6000 i5 orientation depends on the reagent kit; use novaseq6000-v1.0 or novaseq6000-v1.5"
+                        .to_string(),
+                );
+            }
+            _ => {
+                let known: Vec<&str> = Instrument::ALL.iter().map(|i| i.id()).collect();
+                return Err(format!(
+                    "unknown instrument '{}' (expected one of: {})",
+                    s,
+                    known.join(", ")
+                ));
+            }
+        };
+        Ok(instrument)
+    }
+}
+
+impl fmt::Display for Instrument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// Which index read a sheet column holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IndexRole {
+    I7,
+    I5,
+}
+
+impl IndexRole {
+    /// Classifies a column by its header, e.g. `Index 2`, `index2`,
+    /// `IndexNtSequence2`, `i5` or MGI's `Barcode2` as [`IndexRole::I5`].
+    pub fn from_column(name: &str) -> Option<IndexRole> {
+        let key: String = name
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        match key.as_str() {
+            "index" | "index1" | "indexntsequence" | "i7" | "i7index" | "i7sequence" | "barcode" | "barcode1" => {
+                Some(IndexRole::I7)
+            }
+            "index2" | "indexntsequence2" | "i5" | "i5index" | "i5sequence" | "barcode2" => Some(IndexRole::I5),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for IndexRole {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IndexRole::I7 => f.write_str("i7"),
+            IndexRole::I5 => f.write_str("i5"),
+        }
+    }
+}
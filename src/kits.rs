@@ -0,0 +1,426 @@
+//! Index kit database: known i7/i5 sequences with their kit, well and name,
+//! used to check whether a sheet's indexes are forward, reverse complemented
+//! or unknown.
+//!
+//! Kits are read from CSV with the columns `kit,well,index_name,i7,i5`, one
+//! row per well or index; combinatorial kits leave the well and the other
+//! read empty. An optional `orientation` column says whether a row lists i5
+//! `forward` or `reverse-complemented`, and an optional `adapter` column
+//! names the kit's adapter design (`truseq` or `nextera`). i5 sequences are
+//! always stored forward.
+//!
+//! `#` lines are comments, and some of them are settings: `# version: ...`
+//! sets the version, `# orientation: ...` sets the orientation of rows
+//! without their own (`forward` by default), and `# wells: KIT=N` says that
+//! KIT's plate has N wells, so a table listing only some of them can say so.
+//! The built-in kits are bundled from `data/index_kits.csv`; user kit files
+//! use the same format.
+
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use csv::ReaderBuilder;
+
+use crate::instrument::{IndexRole, Workflow};
+use crate::{Orientation, reverse_complement, validate_sequence};
+
+const BUILTIN_KITS: &str = include_str!("../data/index_kits.csv");
+
+/// One index of a kit, with its sequence as listed by the vendor.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KitIndex {
+    pub kit: String,
+    /// Plate well for unique dual index kits; `None` for combinatorial kits.
+    pub well: Option<String>,
+    pub name: String,
+    pub read: IndexRole,
+    pub sequence: String,
+    /// Adapter design, when the kit file gives it.
+    pub adapter: Option<Adapter>,
+}
+
+/// Illumina adapter design of a kit, which decides what an index read
+/// reads once it runs past the index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Adapter {
+    TruSeq,
+    Nextera,
+}
+
+impl FromStr for Adapter {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "truseq" => Ok(Adapter::TruSeq),
+            "nextera" | "tagmentation" => Ok(Adapter::Nextera),
+            other => Err(format!("unknown adapter '{}' (expected truseq or nextera)", other)),
+        }
+    }
+}
+
+impl fmt::Display for Adapter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Adapter::TruSeq => f.write_str("truseq"),
+            Adapter::Nextera => f.write_str("nextera"),
+        }
+    }
+}
+
+/// The adapter bases an index read continues into after the index, as read.
+/// i7, and i5 on the reverse complement workflow, read into sequence every
+/// Illumina adapter shares; i5 on the forward strand workflow needs the adapter.
+pub fn read_through(adapter: Option<Adapter>, read: IndexRole, workflow: Workflow) -> Option<&'static str> {
+    match (read, workflow, adapter) {
+        (IndexRole::I7, _, _) => Some("ATCTCGTATGCCGTCTTCTGCTTG"),
+        (IndexRole::I5, Workflow::ReverseComplement, _) => Some("GTGTAGATCTCGGTGGTCGCCGTATCATT"),
+        (IndexRole::I5, Workflow::Forward, Some(Adapter::TruSeq)) => Some("ACACTCTTTCCCTACACGACGCTCTTCCGATCT"),
+        (IndexRole::I5, Workflow::Forward, Some(Adapter::Nextera)) => Some("TCGTCGGCAGCGTCAGATGTGTATAAGAGACAG"),
+        (IndexRole::I5, Workflow::Forward, None) => None,
+    }
+}
+
+/// A kit index matching a sheet sequence, and which way round it matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KitMatch<'a> {
+    pub index: &'a KitIndex,
+    pub orientation: Orientation,
+}
+
+impl fmt::Display for KitMatch<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.index.kit, self.index.name, self.index.read)
+    }
+}
+
+/// A kit file that could not be read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KitError {
+    /// 1-based line number in the kit file, when the error is tied to a line.
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+impl fmt::Display for KitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {}", line, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for KitError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KitDatabase {
+    version: Option<String>,
+    /// Kits with a `# wells:` setting and their number of plate wells.
+    plate_sizes: Vec<(String, usize)>,
+    indexes: Vec<KitIndex>,
+}
+
+impl KitDatabase {
+    /// The kits bundled with this build.
+    pub fn builtin() -> KitDatabase {
+        KitDatabase::from_csv(BUILTIN_KITS).expect("bundled index kit table is valid")
+    }
+
+    /// Parses a kit table. Sequences are validated and stored uppercase.
+    pub fn from_csv(text: &str) -> Result<KitDatabase, KitError> {
+        let setting = |name: &str| {
+            text.lines().find_map(|line| {
+                line.trim()
+                    .strip_prefix('#')
+                    .and_then(|comment| comment.trim().strip_prefix(name))
+                    .and_then(|rest| rest.trim_start().strip_prefix(':'))
+                    .map(|value| value.trim().to_string())
+            })
+        };
+        let error = |line: Option<usize>, message: String| KitError { line, message };
+        let parse_orientation = |line: Option<usize>, value: &str| {
+            value.parse::<Orientation>().map_err(|e| error(line, e))
+        };
+        let version = setting("version");
+        let default_orientation = match setting("orientation") {
+            Some(value) => parse_orientation(None, &value)?,
+            None => Orientation::Forward,
+        };
+        let mut plate_sizes: Vec<(String, usize)> = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let Some(value) = line
+                .trim()
+                .strip_prefix('#')
+                .and_then(|comment| comment.trim().strip_prefix("wells"))
+                .and_then(|rest| rest.trim_start().strip_prefix(':'))
+            else {
+                continue;
+            };
+            let size = value
+                .rsplit_once('=')
+                .and_then(|(kit, wells)| Some((kit.trim().to_string(), wells.trim().parse().ok()?)))
+                .filter(|(kit, wells)| !kit.is_empty() && *wells > 0)
+                .ok_or_else(|| error(Some(n + 1), format!("expected '# wells: KIT=N', found '{}'", line.trim())))?;
+            plate_sizes.push(size);
+        }
+
+        let mut reader = ReaderBuilder::new()
+            .comment(Some(b'#'))
+            .trim(csv::Trim::All)
+            .from_reader(text.as_bytes());
+        let headers = reader
+            .headers()
+            .map_err(|e| error(None, e.to_string()))?
+            .clone();
+        let optional_column = |name: &str| headers.iter().position(|h| h.eq_ignore_ascii_case(name));
+        let column = |name: &str| {
+            optional_column(name).ok_or_else(|| error(None, format!("missing column '{}'", name)))
+        };
+        let (kit_col, well_col, name_col, i7_col, i5_col) = (
+            column("kit")?,
+            column("well")?,
+            column("index_name")?,
+            column("i7")?,
+            column("i5")?,
+        );
+        let orientation_col = optional_column("orientation");
+        let adapter_col = optional_column("adapter");
+
+        let mut indexes = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| error(e.position().map(|p| p.line() as usize), e.to_string()))?;
+            let line = record.position().map(|p| p.line() as usize);
+            let field = |col: usize| record.get(col).unwrap_or("").to_string();
+            let orientation = match orientation_col.map(field).filter(|value| !value.is_empty()) {
+                Some(value) => parse_orientation(line, &value)?,
+                None => default_orientation,
+            };
+            let adapter = match adapter_col.map(field).filter(|value| !value.is_empty()) {
+                Some(value) => Some(value.parse::<Adapter>().map_err(|e| error(line, e))?),
+                None => None,
+            };
+            let well = field(well_col);
+            let name = field(name_col);
+            if name.is_empty() {
+                return Err(error(line, "empty index_name".to_string()));
+            }
+            let mut found = false;
+            for (read, col) in [(IndexRole::I7, i7_col), (IndexRole::I5, i5_col)] {
+                let mut sequence = field(col).to_ascii_uppercase();
+                if sequence.is_empty() {
+                    continue;
+                }
+                validate_sequence(&sequence).map_err(|e| error(line, format!("{} {}", read, e)))?;
+                if read == IndexRole::I5 && orientation == Orientation::ReverseComplement {
+                    sequence = reverse_complement(&sequence);
+                }
+                found = true;
+                indexes.push(KitIndex {
+                    kit: field(kit_col),
+                    well: if well.is_empty() { None } else { Some(well.clone()) },
+                    name: name.clone(),
+                    read,
+                    sequence,
+                    adapter,
+                });
+            }
+            if !found {
+                return Err(error(line, format!("index '{}' has neither an i7 nor an i5 sequence", name)));
+            }
+        }
+        Ok(KitDatabase { version, plate_sizes, indexes })
+    }
+
+    /// Reads a kit file; errors name the file.
+    pub fn from_path(path: &Path) -> Result<KitDatabase, KitError> {
+        let text = std::fs::read_to_string(path).map_err(|e| KitError {
+            line: None,
+            message: format!("{}: {}", path.display(), e),
+        })?;
+        KitDatabase::from_csv(&text).map_err(|e| KitError {
+            line: None,
+            message: format!("{}: {}", path.display(), e),
+        })
+    }
+
+    /// Adds the kits of `other`, which are then looked up like the built-in ones.
+    pub fn extend(&mut self, other: KitDatabase) {
+        for (kit, wells) in other.plate_sizes {
+            if self.plate_size(&kit).is_none() {
+                self.plate_sizes.push((kit, wells));
+            }
+        }
+        self.indexes.extend(other.indexes);
+    }
+
+    pub fn version(&self) -> Option<&str> {
+        self.version.as_deref()
+    }
+
+    pub fn indexes(&self) -> &[KitIndex] {
+        &self.indexes
+    }
+
+    /// The number of wells on `kit`'s plate, when a `# wells:` setting gives it.
+    pub fn plate_size(&self, kit: &str) -> Option<usize> {
+        self.plate_sizes
+            .iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(kit.trim()))
+            .map(|(_, wells)| *wells)
+    }
+
+    /// Kits that list fewer wells than their plate has, with the number of
+    /// wells listed and on the plate. Their other indexes look unknown.
+    pub fn partial_kits(&self) -> Vec<(&str, usize, usize)> {
+        self.kits()
+            .into_iter()
+            .filter_map(|kit| {
+                let size = self.plate_size(kit)?;
+                let mut wells: Vec<&str> = Vec::new();
+                for index in self.indexes.iter().filter(|index| index.kit == kit) {
+                    if let Some(well) = index.well.as_deref()
+                        && !wells.contains(&well)
+                    {
+                        wells.push(well);
+                    }
+                }
+                (wells.len() < size).then_some((kit, wells.len(), size))
+            })
+            .collect()
+    }
+
+    /// Kit names in table order, without duplicates.
+    pub fn kits(&self) -> Vec<&str> {
+        let mut kits: Vec<&str> = Vec::new();
+        for index in &self.indexes {
+            if !kits.contains(&index.kit.as_str()) {
+                kits.push(&index.kit);
+            }
+        }
+        kits
+    }
+
+    /// Every kit index equal to `sequence` or to its reverse complement, ignoring case.
+    pub fn lookup(&self, sequence: &str) -> Vec<KitMatch<'_>> {
+        let forward = sequence.trim().to_ascii_uppercase();
+        let reversed = reverse_complement(&forward);
+        self.indexes
+            .iter()
+            .filter_map(|index| {
+                let orientation = if index.sequence == forward {
+                    Orientation::Forward
+                } else if index.sequence == reversed {
+                    Orientation::ReverseComplement
+                } else {
+                    return None;
+                };
+                Some(KitMatch { index, orientation })
+            })
+            .collect()
+    }
+
+    /// The orientation of `sequence` relative to the kit it came from, or
+    /// `None` when no kit has it. A forward match wins over a reverse complement one.
+    pub fn classify(&self, sequence: &str) -> Option<Orientation> {
+        let matches = self.lookup(sequence);
+        matches
+            .iter()
+            .find(|m| m.orientation == Orientation::Forward)
+            .or(matches.first())
+            .map(|m| m.orientation)
+    }
+}
+
+/// A difference between two kit databases, keyed by kit, index name and read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KitChange {
+    Added(KitIndex),
+    Removed(KitIndex),
+    Changed { before: KitIndex, after: KitIndex },
+}
+
+impl fmt::Display for KitChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let describe = |index: &KitIndex| match &index.well {
+            Some(well) => format!("{} {} {} ({}): {}", index.kit, index.name, index.read, well, index.sequence),
+            None => format!("{} {} {}: {}", index.kit, index.name, index.read, index.sequence),
+        };
+        match self {
+            KitChange::Added(index) => write!(f, "+ {}", describe(index)),
+            KitChange::Removed(index) => write!(f, "- {}", describe(index)),
+            KitChange::Changed { before, after } => {
+                write!(f, "~ {} -> {}", describe(before), after.sequence)?;
+                if before.well != after.well {
+                    write!(f, " (well {} -> {})", before.well.as_deref().unwrap_or("-"), after.well.as_deref().unwrap_or("-"))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl KitDatabase {
+    /// Problems that make lookups ambiguous: an index name used twice for the
+    /// same read of a kit, a sequence listed twice within a kit, and reads of
+    /// one kit with different lengths.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        for (i, index) in self.indexes.iter().enumerate() {
+            for other in &self.indexes[..i] {
+                if other.kit != index.kit || other.read != index.read {
+                    continue;
+                }
+                if other.name == index.name {
+                    problems.push(format!("{}: {} {} is listed twice", index.kit, index.name, index.read));
+                } else if other.sequence == index.sequence {
+                    problems.push(format!(
+                        "{}: {} and {} share the {} sequence {}",
+                        index.kit, other.name, index.name, index.read, index.sequence
+                    ));
+                }
+            }
+        }
+        for kit in self.kits() {
+            for read in [IndexRole::I7, IndexRole::I5] {
+                let mut lengths: Vec<usize> = self
+                    .indexes
+                    .iter()
+                    .filter(|index| index.kit == kit && index.read == read)
+                    .map(|index| index.sequence.len())
+                    .collect();
+                lengths.sort_unstable();
+                lengths.dedup();
+                if lengths.len() > 1 {
+                    let lengths: Vec<String> = lengths.iter().map(usize::to_string).collect();
+                    problems.push(format!("{}: {} sequences have mixed lengths ({})", kit, read, lengths.join(", ")));
+                }
+            }
+        }
+        problems
+    }
+
+    /// What changes from `self` to `other`, in `self`'s order followed by additions.
+    pub fn diff(&self, other: &KitDatabase) -> Vec<KitChange> {
+        let key = |index: &KitIndex| (index.kit.clone(), index.name.clone(), index.read);
+        let find = |db: &KitDatabase, wanted: &KitIndex| db.indexes.iter().find(|index| key(index) == key(wanted)).cloned();
+        let mut changes = Vec::new();
+        for before in &self.indexes {
+            match find(other, before) {
+                None => changes.push(KitChange::Removed(before.clone())),
+                Some(after) if after != *before => changes.push(KitChange::Changed {
+                    before: before.clone(),
+                    after,
+                }),
+                Some(_) => {}
+            }
+        }
+        for after in &other.indexes {
+            if find(self, after).is_none() {
+                changes.push(KitChange::Added(after.clone()));
+            }
+        }
+        changes
+    }
+}
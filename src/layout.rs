@@ -0,0 +1,182 @@
+//! Index plate layout errors: an index plate loaded rotated 180°, or shifted
+//! by one row or column, so that every sample got the index of another well.
+//!
+//! Each candidate layout says which index plate well the sample in a given
+//! well actually got. A shifted plate still uses mostly the same indexes, so
+//! layouts are compared by how much of the evidence for the map's indexes
+//! they leave unexplained: a layout that leaves less than half of what the
+//! recorded one does is reported as the likely error.
+
+use std::fmt;
+
+use crate::platemap::{PlateMap, PlateWell, normalize_well};
+
+/// Rows and columns of a plate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PlateSize {
+    pub rows: u8,
+    pub columns: u8,
+}
+
+impl PlateSize {
+    pub const WELLS_96: PlateSize = PlateSize { rows: 8, columns: 12 };
+    pub const WELLS_384: PlateSize = PlateSize { rows: 16, columns: 24 };
+
+    /// A 96-well plate, or a 384-well plate when `map` has wells outside `A1`-`H12`.
+    pub fn of(map: &PlateMap) -> PlateSize {
+        let beyond_96 = map.wells().iter().any(|well| {
+            let (row, column) = well_position(&well.well).unwrap_or((0, 0));
+            row >= 8 || column > 12
+        });
+        if beyond_96 { PlateSize::WELLS_384 } else { PlateSize::WELLS_96 }
+    }
+}
+
+/// 0-based row and 1-based column of a normalized well.
+fn well_position(well: &str) -> Option<(u8, u8)> {
+    let mut chars = well.chars();
+    let row = chars.next()? as u8 - b'A';
+    let column = chars.as_str().parse().ok()?;
+    Some((row, column))
+}
+
+/// How the index plate was loaded relative to the recorded wells.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PlateLayout {
+    Recorded,
+    /// Turned 180°, so `A1` got the index of `H12`.
+    Rotated,
+    /// Each sample got the index this many columns to the right (negative: left).
+    ShiftedColumns(i8),
+    /// Each sample got the index this many rows down (negative: up).
+    ShiftedRows(i8),
+}
+
+impl PlateLayout {
+    pub const CANDIDATES: [PlateLayout; 6] = [
+        PlateLayout::Recorded,
+        PlateLayout::Rotated,
+        PlateLayout::ShiftedColumns(1),
+        PlateLayout::ShiftedColumns(-1),
+        PlateLayout::ShiftedRows(1),
+        PlateLayout::ShiftedRows(-1),
+    ];
+
+    /// The index plate well the sample in `well` got under this layout, or
+    /// `None` when it falls off the plate.
+    pub fn index_well(self, well: &str, size: PlateSize) -> Option<String> {
+        let (row, column) = well_position(&normalize_well(well)?)?;
+        let (row, column) = (i16::from(row), i16::from(column));
+        let (row, column) = match self {
+            PlateLayout::Recorded => (row, column),
+            PlateLayout::Rotated => (i16::from(size.rows) - 1 - row, i16::from(size.columns) + 1 - column),
+            PlateLayout::ShiftedColumns(shift) => (row, column + i16::from(shift)),
+            PlateLayout::ShiftedRows(shift) => (row + i16::from(shift), column),
+        };
+        if !(0..i16::from(size.rows)).contains(&row) || !(1..=i16::from(size.columns)).contains(&column) {
+            return None;
+        }
+        Some(format!("{}{}", (b'A' + row as u8) as char, column))
+    }
+}
+
+impl fmt::Display for PlateLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = |n: i8| if n.abs() == 1 { "" } else { "s" };
+        match *self {
+            PlateLayout::Recorded => f.write_str("as recorded"),
+            PlateLayout::Rotated => f.write_str("rotated 180° (A1 got the index of the last well)"),
+            PlateLayout::ShiftedColumns(n) => write!(
+                f,
+                "shifted by {} column{} (each sample got the index {} to the {})",
+                n.abs(), plural(n), n.abs(), if n > 0 { "right" } else { "left" }
+            ),
+            PlateLayout::ShiftedRows(n) => write!(
+                f,
+                "shifted by {} row{} (each sample got the index {} {})",
+                n.abs(), plural(n), n.abs(), if n > 0 { "down" } else { "up" }
+            ),
+        }
+    }
+}
+
+/// How well the observed indexes fit one layout.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LayoutScore {
+    pub layout: PlateLayout,
+    /// Sum of the evidence for every sample's expected indexes, e.g. reads.
+    pub score: u64,
+    /// Samples with any evidence for their expected indexes.
+    pub matched: usize,
+    /// Samples whose index well under this layout is on the plate but not in
+    /// the map, so the layout could not be checked for them.
+    pub unmapped: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LayoutCheck {
+    /// One score per candidate, in [`PlateLayout::CANDIDATES`] order.
+    pub scores: Vec<LayoutScore>,
+    /// Evidence for every distinct index pair of the map, used by a sample or not.
+    pub observed: u64,
+    /// The layout that fits the observed indexes best, when it is not the
+    /// recorded one, leaves less than half as much evidence unexplained, and
+    /// matches at least half of the samples.
+    pub likely_error: Option<PlateLayout>,
+}
+
+impl LayoutCheck {
+    /// The layouts some samples could not be checked for, because the map
+    /// does not list the wells they would have got.
+    pub fn unchecked(&self) -> Vec<&LayoutScore> {
+        self.scores.iter().filter(|score| score.unmapped > 0).collect()
+    }
+}
+
+/// Scores every candidate layout for samples at `wells` (plate, well).
+/// `evidence` says how strongly the indexes of an index plate well were
+/// observed, e.g. the number of reads with its i7 and i5.
+pub fn check_layout(
+    wells: &[(Option<&str>, &str)],
+    map: &PlateMap,
+    evidence: impl Fn(&PlateWell) -> u64,
+) -> LayoutCheck {
+    let size = PlateSize::of(map);
+    let scores: Vec<LayoutScore> = PlateLayout::CANDIDATES
+        .iter()
+        .map(|&layout| {
+            let mut score = LayoutScore { layout, score: 0, matched: 0, unmapped: 0 };
+            for (plate, well) in wells {
+                let Some(index_well) = layout.index_well(well, size) else {
+                    continue;
+                };
+                let Some(entry) = map.get(*plate, &index_well) else {
+                    score.unmapped += 1;
+                    continue;
+                };
+                let found = evidence(entry);
+                score.score += found;
+                score.matched += usize::from(found > 0);
+            }
+            score
+        })
+        .collect();
+    let mut pairs: Vec<(&str, &str)> = Vec::new();
+    let mut observed = 0;
+    for well in map.wells() {
+        if !pairs.contains(&(well.i7.as_str(), well.i5.as_str())) {
+            pairs.push((well.i7.as_str(), well.i5.as_str()));
+            observed += evidence(well);
+        }
+    }
+    let unexplained = |score: &LayoutScore| observed.saturating_sub(score.score);
+    let recorded = unexplained(&scores[0]);
+    let best = scores.iter().max_by_key(|score| score.score).filter(|best| {
+        best.layout != PlateLayout::Recorded && unexplained(best) * 2 < recorded && best.matched * 2 >= wells.len()
+    });
+    LayoutCheck {
+        likely_error: best.map(|best| best.layout),
+        scores,
+        observed,
+    }
+}
@@ -0,0 +1,77 @@
+//! Bringing the indexes of a column to one length, for lanes that pool 8 and
+//! 10 bp indexes, or single- and dual-indexed libraries.
+//!
+//! Demultiplexers compare index reads from their first cycle, so lengths are
+//! always changed at the end of the sequence as read. That is why lengths are
+//! normalized after any reverse complement: truncating an i5 before reverse
+//! complementing it would drop bases from the other end of the read.
+
+use std::fmt;
+use std::str::FromStr;
+
+/// How mixed index lengths are made equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LengthMode {
+    /// Cut every index to the shortest length.
+    Truncate,
+    /// Extend every index to the longest length with `N`.
+    Pad,
+    /// Extend every index to the longest length with the adapter bases the
+    /// index read continues into, where the kit is known, and `N` otherwise.
+    PadAdapter,
+}
+
+impl LengthMode {
+    /// The common length for indexes between `shortest` and `longest` bases.
+    pub fn target(self, shortest: usize, longest: usize) -> usize {
+        match self {
+            LengthMode::Truncate => shortest,
+            LengthMode::Pad | LengthMode::PadAdapter => longest,
+        }
+    }
+}
+
+impl FromStr for LengthMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "truncate" => Ok(LengthMode::Truncate),
+            "pad" | "pad-n" => Ok(LengthMode::Pad),
+            "pad-adapter" | "adapter" => Ok(LengthMode::PadAdapter),
+            other => Err(format!(
+                "unknown length mode '{}' (expected truncate, pad or pad-adapter)",
+                other
+            )),
+        }
+    }
+}
+
+impl fmt::Display for LengthMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LengthMode::Truncate => f.write_str("truncate"),
+            LengthMode::Pad => f.write_str("pad"),
+            LengthMode::PadAdapter => f.write_str("pad-adapter"),
+        }
+    }
+}
+
+/// The shortest and longest of `lengths` when they differ, `None` when they
+/// all agree. Zero lengths (samples without this index) count, so a column
+/// mixing single- and dual-indexed libraries is mixed.
+pub fn mixed_lengths(lengths: impl IntoIterator<Item = usize>) -> Option<(usize, usize)> {
+    let mut lengths = lengths.into_iter();
+    let first = lengths.next()?;
+    let (shortest, longest) = lengths.fold((first, first), |(min, max), len| (min.min(len), max.max(len)));
+    (shortest != longest).then_some((shortest, longest))
+}
+
+/// `sequence` cut or extended to `length` bases, as read from its first base.
+/// Extra bases come from `padding`, then `N` once it runs out.
+pub fn resize_index(sequence: &str, length: usize, padding: &str) -> String {
+    let mut resized: String = sequence.chars().take(length).collect();
+    let missing = length.saturating_sub(resized.chars().count());
+    resized.extend(padding.chars().chain(std::iter::repeat('N')).take(missing));
+    resized
+}
@@ -1,13 +1,333 @@
+pub mod balance;
+pub mod cell;
+pub mod distance;
+pub mod export;
+pub mod fastx;
+pub mod infer;
+pub mod instrument;
+pub mod kits;
+pub mod layout;
+pub mod length;
+pub mod mgi;
+pub mod platemap;
+pub mod samplesheet;
+pub mod sequence;
+pub mod tenx;
+
+pub use sequence::{DnaSequence, IndexSequence, Orientation};
+
+/// How letter case is handled when complementing a sequence.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CaseMode {
+    /// Keep each base's case, so soft-masked (lowercase) regions stay masked.
+    #[default]
+    Preserve,
+    /// Normalize the output to uppercase.
+    Upper,
+}
+
+/// Nucleic acid alphabet used for the output of [`reverse_complement_with`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Alphabet {
+    /// Output contains `T`.
+    #[default]
+    Dna,
+    /// Output contains `U`.
+    Rna,
+    /// Pick RNA when the input contains `U` and no `T`, otherwise DNA.
+    Auto,
+}
+
+impl Alphabet {
+    /// Resolves [`Alphabet::Auto`] against `sequence`; other variants are returned as-is.
+    pub fn resolve(self, sequence: &str) -> Alphabet {
+        match self {
+            Alphabet::Auto => {
+                let has_u = sequence.contains(['U', 'u']);
+                let has_t = sequence.contains(['T', 't']);
+                if has_u && !has_t {
+                    Alphabet::Rna
+                } else {
+                    Alphabet::Dna
+                }
+            }
+            other => other,
+        }
+    }
+}
+
+impl std::str::FromStr for Alphabet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "dna" => Ok(Alphabet::Dna),
+            "rna" => Ok(Alphabet::Rna),
+            "auto" => Ok(Alphabet::Auto),
+            other => Err(format!(
+                "unknown alphabet '{}' (expected dna, rna or auto)",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Alphabet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Alphabet::Dna => "dna",
+            Alphabet::Rna => "rna",
+            Alphabet::Auto => "auto",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Options for [`reverse_complement_with`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RcOptions {
+    pub case: CaseMode,
+    pub alphabet: Alphabet,
+}
+
+/// Error returned when a sequence contains a character outside the IUPAC
+/// nucleotide alphabet.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SequenceError {
+    /// `base` at character `offset` (0-based) is not a nucleotide code.
+    InvalidBase { base: char, offset: usize },
+    /// The sequence is empty where a sequence is required.
+    Empty,
+}
+
+impl SequenceError {
+    /// Shifts the reported offset by `n` characters, for sequences embedded in a larger cell.
+    pub fn offset_by(self, n: usize) -> SequenceError {
+        match self {
+            SequenceError::InvalidBase { base, offset } => SequenceError::InvalidBase {
+                base,
+                offset: offset + n,
+            },
+            other => other,
+        }
+    }
+}
+
+impl std::fmt::Display for SequenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SequenceError::InvalidBase { base, offset } => {
+                write!(f, "invalid character '{}' at offset {}", base.escape_debug(), offset)
+            }
+            SequenceError::Empty => f.write_str("empty sequence"),
+        }
+    }
+}
+
+impl std::error::Error for SequenceError {}
+
+/// Returns true if `base` is an IUPAC nucleotide code (including `U`), in either case.
+pub fn is_nucleotide(base: char) -> bool {
+    matches!(
+        base.to_ascii_uppercase(),
+        'A' | 'C' | 'G' | 'T' | 'U' | 'R' | 'Y' | 'K' | 'M' | 'S' | 'W' | 'B' | 'D' | 'H' | 'V' | 'N'
+    )
+}
+
+/// Checks that every character of `dna` is a nucleotide code, reporting the first one that is not.
+pub fn validate_sequence(dna: &str) -> Result<(), SequenceError> {
+    match dna.chars().enumerate().find(|(_, c)| !is_nucleotide(*c)) {
+        Some((offset, base)) => Err(SequenceError::InvalidBase { base, offset }),
+        None => Ok(()),
+    }
+}
+
+/// Returns the Watson-Crick complement of a single IUPAC nucleotide code.
+///
+/// Degenerate codes map to the code covering the complementary bases
+/// (e.g. `R` = A/G becomes `Y` = C/T). Self-complementary codes (`S`, `W`,
+/// `N`) and any character outside the IUPAC alphabet are returned unchanged.
+/// Lowercase bases are complemented to lowercase. RNA `U` pairs with `A`; the
+/// complement of `A` is always `T` here, see [`reverse_complement_with`] for
+/// RNA output.
+pub fn complement(base: char) -> char {
+    if base.is_ascii_lowercase() {
+        return complement(base.to_ascii_uppercase()).to_ascii_lowercase();
+    }
+    match base {
+        'A' => 'T',
+        'T' | 'U' => 'A',
+        'G' => 'C',
+        'C' => 'G',
+        'R' => 'Y',
+        'Y' => 'R',
+        'K' => 'M',
+        'M' => 'K',
+        'B' => 'V',
+        'V' => 'B',
+        'D' => 'H',
+        'H' => 'D',
+        'S' => 'S',
+        'W' => 'W',
+        'N' => 'N',
+        _ => base,
+    }
+}
+
+/// Byte-level complement lookup table, equivalent to [`complement`] for ASCII.
+/// Bytes that are not nucleotide codes map to themselves.
+const COMPLEMENT_TABLE: [u8; 256] = {
+    let mut table = [0u8; 256];
+    let mut i = 0;
+    while i < 256 {
+        table[i] = i as u8;
+        i += 1;
+    }
+    let pairs: [(u8, u8); 8] = [
+        (b'A', b'T'),
+        (b'G', b'C'),
+        (b'R', b'Y'),
+        (b'K', b'M'),
+        (b'B', b'V'),
+        (b'D', b'H'),
+        (b'S', b'S'),
+        (b'W', b'W'),
+    ];
+    let mut p = 0;
+    while p < pairs.len() {
+        let (x, y) = pairs[p];
+        table[x as usize] = y;
+        table[y as usize] = x;
+        table[x.to_ascii_lowercase() as usize] = y.to_ascii_lowercase();
+        table[y.to_ascii_lowercase() as usize] = x.to_ascii_lowercase();
+        p += 1;
+    }
+    table[b'U' as usize] = b'A';
+    table[b'u' as usize] = b'a';
+    table
+};
+
+/// Byte-level [`complement`].
+#[inline]
+pub fn complement_byte(base: u8) -> u8 {
+    COMPLEMENT_TABLE[base as usize]
+}
+
+/// Reverse complements an ASCII sequence in place, e.g. a FASTQ record buffer.
+pub fn reverse_complement_in_place(seq: &mut [u8]) {
+    seq.reverse();
+    for base in seq.iter_mut() {
+        *base = COMPLEMENT_TABLE[*base as usize];
+    }
+}
+
+/// Writes the reverse complement of `seq` into `out` without allocating.
+///
+/// # Panics
+///
+/// Panics if `out` is not the same length as `seq`.
+pub fn reverse_complement_into(seq: &[u8], out: &mut [u8]) {
+    assert_eq!(
+        seq.len(),
+        out.len(),
+        "output buffer length must match the sequence length"
+    );
+    for (dst, src) in out.iter_mut().zip(seq.iter().rev()) {
+        *dst = COMPLEMENT_TABLE[*src as usize];
+    }
+}
+
+/// Byte-level [`reverse_complement`] returning a new buffer.
+pub fn reverse_complement_bytes(seq: &[u8]) -> Vec<u8> {
+    let mut out = vec![0u8; seq.len()];
+    reverse_complement_into(seq, &mut out);
+    out
+}
+
 pub fn reverse_complement(dna: &str) -> String {
-    dna.chars()
-        .rev()
+    dna.chars().rev().map(complement).collect()
+}
+
+/// Like [`reverse_complement`], but rejects characters that are not
+/// nucleotide codes (gaps, digits, whitespace) instead of passing them through.
+pub fn try_reverse_complement(dna: &str) -> Result<String, SequenceError> {
+    validate_sequence(dna)?;
+    Ok(reverse_complement(dna))
+}
+
+/// Validating counterpart of [`reverse_complement_with`].
+pub fn try_reverse_complement_with(dna: &str, options: &RcOptions) -> Result<String, SequenceError> {
+    validate_sequence(dna)?;
+    Ok(reverse_complement_with(dna, options))
+}
+
+/// Reverse complements `dna` according to `options`.
+pub fn reverse_complement_with(dna: &str, options: &RcOptions) -> String {
+    apply_operation(dna, Operation::ReverseComplement, options)
+}
+
+/// What is done to the sequences of a column.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Operation {
+    #[default]
+    ReverseComplement,
+    /// Complement without reversing.
+    Complement,
+    /// Reverse without complementing.
+    Reverse,
+    /// Leave the sequence exactly as it is.
+    Passthrough,
+}
+
+impl std::str::FromStr for Operation {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "rc" | "reverse-complement" => Ok(Operation::ReverseComplement),
+            "complement" => Ok(Operation::Complement),
+            "reverse" => Ok(Operation::Reverse),
+            "none" | "passthrough" => Ok(Operation::Passthrough),
+            other => Err(format!(
+                "unknown operation '{}' (expected rc, complement, reverse or none)",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Operation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Operation::ReverseComplement => "reverse-complement",
+            Operation::Complement => "complement",
+            Operation::Reverse => "reverse",
+            Operation::Passthrough => "none",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Applies `operation` to `dna`; case and alphabet `options` apply to every
+/// operation except [`Operation::Passthrough`], which returns `dna` unchanged.
+pub fn apply_operation(dna: &str, operation: Operation, options: &RcOptions) -> String {
+    let transformed: String = match operation {
+        Operation::ReverseComplement => reverse_complement(dna),
+        Operation::Complement => dna.chars().map(complement).collect(),
+        Operation::Reverse => dna.chars().rev().collect(),
+        Operation::Passthrough => return dna.to_string(),
+    };
+    let rna = options.alphabet.resolve(dna) == Alphabet::Rna;
+    transformed
+        .chars()
         .map(|c| match c {
-            'A' => 'T',
-            'T' => 'A',
-            'G' => 'C',
-            'C' => 'G',
-            'N' => 'N',
+            'T' if rna => 'U',
+            't' if rna => 'u',
             _ => c,
         })
+        .map(|c| match options.case {
+            CaseMode::Preserve => c,
+            CaseMode::Upper => c.to_ascii_uppercase(),
+        })
         .collect()
 }
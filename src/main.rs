@@ -1,33 +1,615 @@
 use calamine::{Reader, Xlsx};
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use rust_xlsxwriter::Workbook;
-use std::path::PathBuf;
-use tracseq_rc::reverse_complement;
+use std::path::{Path, PathBuf};
+use tracseq_rc::fastx::{FastxFormat, FastxOptions, FastxReader, open_input, reverse_complement_fastx};
+use tracseq_rc::cell::{CellFormat, CellParser, ParsedCell};
+use tracseq_rc::balance::{CycleBalance, at_risk_cycles};
+use tracseq_rc::distance::{Collision, IndexedSample, find_collisions};
+use tracseq_rc::export::{ExportRow, ExportTarget, export_sample_sheet};
+use tracseq_rc::infer::BarcodeCounts;
+use tracseq_rc::instrument::{IndexRole, Instrument, Workflow};
+use tracseq_rc::kits::{KitDatabase, KitError, read_through};
+use tracseq_rc::length::{LengthMode, mixed_lengths, resize_index};
+use tracseq_rc::mgi::{BarcodeMode, barcode_mode, parse_barcodes, read_barcodes, write_barcodes};
+use tracseq_rc::layout::{PlateLayout, PlateSize, check_layout};
+use tracseq_rc::platemap::{PlateMap, PlateWell, normalize_well};
+use tracseq_rc::samplesheet::{SampleSheet, SheetVersion, TableRow, is_sample_sheet, override_index_cycles};
+use tracseq_rc::tenx::{SETS_PER_TYPE, TenxIndexSet, TenxIndexSets, is_set_name};
+use tracseq_rc::{
+    Alphabet, CaseMode, IndexSequence, Operation, Orientation, RcOptions, SequenceError, apply_operation, reverse_complement,
+    validate_sequence,
+};
 use csv::{ReaderBuilder, WriterBuilder};
 use std::fs::File;
 
 #[derive(Parser)]
 #[command(author, version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
     /// Path to the Excel or CSV file
-    file: PathBuf,
+    #[arg(required = true)]
+    file: Option<PathBuf>,
+
+    #[command(flatten)]
+    options: ProcessOptions,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Reverse complement every record of a FASTA/FASTQ file (plain or gzip)
+    Fastx(FastxArgs),
+    /// List, validate or compare index kit definition files
+    #[command(subcommand)]
+    Kits(KitsCommand),
+    /// Count the index sequences in FASTQ files (e.g. Undetermined reads) and
+    /// check whether the sheet's i5 has to be reverse complemented, or the
+    /// index plate was loaded rotated or shifted
+    Infer(InferArgs),
+}
+
+#[derive(Subcommand)]
+enum KitsCommand {
+    /// List the built-in kits and the kits of the given files
+    List {
+        /// Kit definition files (CSV: kit,well,index_name,i7,i5)
+        files: Vec<PathBuf>,
+    },
+    /// Check kit files for errors, duplicate names and repeated sequences
+    Validate {
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Show the indexes added, removed or changed from OLD to NEW ("builtin" for the built-in kits)
+    Diff {
+        old: PathBuf,
+        new: PathBuf,
+    },
+}
+
+#[derive(clap::Args)]
+struct FastxArgs {
+    /// Input FASTA/FASTQ file; reads stdin when omitted or "-"
+    input: Option<PathBuf>,
+
+    /// Output file; writes stdout when omitted or "-". A ".gz" extension compresses the output
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+
+    /// Text appended to every record ID, e.g. "/rc"
+    #[arg(long)]
+    tag: Option<String>,
+}
+
+#[derive(clap::Args)]
+struct InferArgs {
+    /// Sample sheet, CSV or Excel file with the samples' indexes
+    sheet: PathBuf,
+
+    /// FASTQ files (plain or gzip). Files named *_I1* / *_I2* are read as index
+    /// reads; any others are counted from their read headers
+    #[arg(required = true)]
+    fastq: Vec<PathBuf>,
+
+    /// Number of most frequent barcodes to list
+    #[arg(long, default_value_t = 10)]
+    top: usize,
+
+    /// Reads to count per file
+    #[arg(long, default_value_t = 1_000_000)]
+    max_reads: usize,
+
+    /// Write the _RC file when the reads show the i5 has to be reverse complemented,
+    /// and the corrected sheet when they show a plate layout error
+    #[arg(long)]
+    write: bool,
+
+    /// Plate map to check the sheet's Plate and Well columns against: reports when the
+    /// reads fit an index plate rotated 180° or shifted by a row or column better
+    #[arg(long = "plate-map", value_name = "FILE")]
+    plate_map: Option<PathBuf>,
+
+    /// Index kit whose wells are checked like --plate-map (built-in or from --kits)
+    #[arg(long = "plate-kit", value_name = "KIT", conflicts_with = "plate_map")]
+    plate_kit: Option<String>,
+
+    /// Extra index kit file for --plate-kit (repeatable)
+    #[arg(long = "kits", value_name = "FILE")]
+    kit_files: Vec<PathBuf>,
+}
+
+#[derive(clap::Args, Debug, Clone, Default)]
+struct ProcessOptions {
+    /// Uppercase the reverse complement of this column (repeatable, or comma-separated)
+    #[arg(long = "uppercase", value_name = "COLUMN", value_delimiter = ',')]
+    uppercase_columns: Vec<String>,
+
+    /// Output alphabet: dna, rna (U instead of T), or auto (per cell, from the input)
+    #[arg(long, default_value_t = Alphabet::Dna)]
+    alphabet: Alphabet,
+
+    /// Cell format of a sequence column, as COLUMN=FORMAT where FORMAT is sequence,
+    /// label:<char>, paren or regex:<pattern> (repeatable). Detected from the data otherwise
+    #[arg(long = "cell-format", value_name = "COLUMN=FORMAT", value_parser = parse_cell_format)]
+    cell_formats: Vec<(String, CellParser)>,
+
+    /// Operation for a sequence column, as COLUMN=OP where OP is rc, complement,
+    /// reverse or none (repeatable). Columns default to rc; none leaves the column untouched
+    #[arg(long = "operation", value_name = "COLUMN=OP", value_parser = parse_column_operation)]
+    operations: Vec<(String, Operation)>,
+
+    /// Report pairs of samples whose combined processed indexes are at most
+    /// MISMATCHES apart
+    #[arg(long = "check-collisions", value_name = "MISMATCHES")]
+    check_collisions: Option<usize>,
+
+    /// Sequencer the sheet is for; its i5 orientation decides which columns are flipped
+    /// (e.g. miseq, nextseq500, novaseq6000-v1.5, novaseqx)
+    #[arg(long)]
+    instrument: Option<Instrument>,
+
+    /// i5 workflow the sheet is for: forward or reverse-complement. Overrides --instrument
+    #[arg(long)]
+    workflow: Option<Workflow>,
+
+    /// Look every sequence up in the built-in index kits and report it as forward,
+    /// reverse-complemented or unknown, before and after processing
+    #[arg(long)]
+    verify_kits: bool,
+
+    /// Extra index kit file used alongside the built-in kits (repeatable); implies --verify-kits
+    #[arg(long = "kits", value_name = "FILE")]
+    kit_files: Vec<PathBuf>,
+
+    /// Flag index cycles of each lane with no signal in one channel on two-colour
+    /// instruments (NextSeq, MiniSeq, NovaSeq), as written and reverse complemented
+    #[arg(long)]
+    check_balance: bool,
+
+    /// Bring index columns with mixed lengths to one length: truncate (to the shortest),
+    /// pad (to the longest, with N) or pad-adapter (with the kit's adapter bases). Collisions
+    /// are rechecked afterwards
+    #[arg(long, value_name = "MODE")]
+    normalize_length: Option<LengthMode>,
+
+    /// Also write one output file per value of the Lane column (e.g. batch_RC_L1.csv);
+    /// rows without a lane go to every lane's file
+    #[arg(long)]
+    split_lanes: bool,
+
+    /// 10x Genomics index set table used alongside the bundled sets (repeatable), in the
+    /// CSV layout 10x publishes, e.g. Dual_Index_Kit_TT_Set_A.csv
+    #[arg(long = "tenx-sets", value_name = "FILE")]
+    tenx_set_files: Vec<PathBuf>,
+
+    /// Also write a demultiplexer sample sheet of the processed rows: bcl2fastq (v1 [Data]),
+    /// bclconvert (v2), mkfastq (cellranger simple CSV), aviti (Element RunManifest.csv) or
+    /// mgi (DNBSEQ barcode list), with each one's index orientation
+    #[arg(long, value_name = "TARGET")]
+    export: Option<ExportTarget>,
+
+    /// Plate map (CSV with Well, optional Plate, and i7/i5 columns) that fills in the indexes
+    /// of a sheet with Plate and Well columns; the joined sheet is written as {stem}_joined.csv,
+    /// or {stem}_joined.xlsx for Excel input, and then processed as usual
+    #[arg(long = "plate-map", value_name = "FILE")]
+    plate_map: Option<PathBuf>,
+
+    /// Index kit whose wells fill in the indexes, like --plate-map (built-in or from --kits)
+    #[arg(long = "plate-kit", value_name = "KIT", conflicts_with = "plate_map")]
+    plate_kit: Option<String>,
+}
+
+/// A sequence column detected in the input: where it is, how its cells are
+/// split, and what is done to their sequences.
+#[derive(Debug, Clone)]
+struct ColumnSpec {
+    index: usize,
+    name: String,
+    parser: CellParser,
+    operation: Operation,
+    /// Operations for the i7 and i5 halves of combined `i7+i5` cells, when
+    /// --instrument or --workflow decides them instead of the column name
+    pair_operations: Option<[Operation; 2]>,
+    /// Why the operation was chosen, when it came from --instrument or --workflow
+    reason: Option<String>,
+    /// Length each index of the cell is brought to, from --normalize-length
+    lengths: Vec<Option<usize>>,
+    /// Whether empty cells (samples without this index) are padded to the length
+    fill_empty: bool,
+}
+
+impl ColumnSpec {
+    /// The operation for each sequence part of a cell that has `parts` of
+    /// them: each half of a combined `i7+i5` cell gets its own read's when
+    /// the workflow decides them, otherwise every part gets the column's.
+    fn part_operations(&self, parts: usize) -> Vec<Operation> {
+        match self.pair_operations {
+            Some(pair) if parts == 2 => pair.to_vec(),
+            _ => vec![self.operation; parts],
+        }
+    }
+}
+
+fn parse_cell_format(s: &str) -> Result<(String, CellParser), String> {
+    let (column, format) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected COLUMN=FORMAT, got '{}'", s))?;
+    Ok((column.trim().to_string(), format.parse()?))
+}
+
+fn parse_column_operation(s: &str) -> Result<(String, Operation), String> {
+    let (column, operation) = s
+        .split_once('=')
+        .ok_or_else(|| format!("expected COLUMN=OP, got '{}'", s))?;
+    Ok((column.trim().to_string(), operation.parse()?))
+}
+
+impl ProcessOptions {
+    /// Reverse complement options for the sequence column named `column`.
+    fn rc_options(&self, column: &str) -> RcOptions {
+        let upper = self
+            .uppercase_columns
+            .iter()
+            .any(|c| c.trim() == column.trim());
+        RcOptions {
+            case: if upper { CaseMode::Upper } else { CaseMode::Preserve },
+            alphabet: self.alphabet,
+        }
+    }
+
+    /// Explicit --cell-format for the column named `column`, if any.
+    fn explicit_cell_parser(&self, column: &str) -> Option<&CellParser> {
+        self.cell_formats
+            .iter()
+            .find(|(c, _)| c == column.trim())
+            .map(|(_, parser)| parser)
+    }
+
+    /// The i5 workflow asked for, and how to describe where it came from.
+    fn target_workflow(&self) -> Option<(Workflow, String)> {
+        match (self.workflow, self.instrument) {
+            (Some(workflow), _) => Some((workflow, format!("the {} workflow", workflow))),
+            (None, Some(instrument)) => Some((
+                instrument.workflow(),
+                format!("{} (the {} workflow)", instrument.name(), instrument.workflow()),
+            )),
+            (None, None) => None,
+        }
+    }
+
+    /// Operation for the column named `column`, with the reason when it was
+    /// decided by the instrument or workflow. An explicit --operation wins;
+    /// without an instrument or workflow every column is reverse complemented.
+    fn column_operation(&self, column: &str) -> (Operation, Option<String>) {
+        if let Some(operation) = self.explicit_operation(column) {
+            return (operation, None);
+        }
+        let Some((workflow, source)) = self.target_workflow() else {
+            return (Operation::default(), None);
+        };
+        let reason = match (IndexRole::from_column(column), workflow) {
+            (Some(IndexRole::I5), Workflow::ReverseComplement) => {
+                format!("{} reads i5 as its reverse complement, so i5 is reverse complemented", source)
+            }
+            (Some(IndexRole::I5), Workflow::Forward) => {
+                format!("{} reads i5 as entered, so i5 is left unchanged", source)
+            }
+            (Some(IndexRole::I7), _) => "i7 is read the same way on every instrument, so it is left unchanged".to_string(),
+            (None, _) => "not an i7 or i5 column, so it is left unchanged (use --operation to change it)".to_string(),
+        };
+        let operation = match IndexRole::from_column(column) {
+            Some(IndexRole::I5) => workflow.i5_operation(),
+            _ => Operation::Passthrough,
+        };
+        (operation, Some(reason))
+    }
+
+    /// Explicit --operation for the column named `column`, if any (the last one wins).
+    fn explicit_operation(&self, column: &str) -> Option<Operation> {
+        self.operations
+            .iter()
+            .rev()
+            .find(|(c, _)| c == column.trim())
+            .map(|(_, operation)| *operation)
+    }
+
+    fn column_spec(&self, index: usize, name: &str, parser: CellParser) -> ColumnSpec {
+        let (operation, reason) = self.column_operation(name);
+        // A combined cell holds both reads, whatever the column is called
+        let pair_operations = match (self.explicit_operation(name), self.target_workflow()) {
+            (None, Some((workflow, _))) => Some([Operation::Passthrough, workflow.i5_operation()]),
+            _ => None,
+        };
+        ColumnSpec {
+            index,
+            name: name.to_string(),
+            parser,
+            operation,
+            pair_operations,
+            reason,
+            lengths: Vec::new(),
+            fill_empty: false,
+        }
+    }
+
+    /// Cell parser for a sequence column: an explicit --cell-format wins, then
+    /// the format detected from `samples`, then "Prefix-SEQUENCE" or a plain
+    /// sequence depending on `has_delimiter`.
+    fn cell_parser(&self, column: &str, samples: &[&str], has_delimiter: bool) -> CellParser {
+        if let Some(parser) = self.explicit_cell_parser(column) {
+            return parser.clone();
+        }
+        // 10x set names are replaced by sequences before the cells are parsed
+        let samples = samples.iter().copied().filter(|sample| !is_set_name(sample));
+        CellParser::detect(samples).unwrap_or_else(|| {
+            if has_delimiter {
+                CellParser::new(CellFormat::Labeled { delimiter: '-' })
+            } else {
+                CellParser::default()
+            }
+        })
+    }
+
+    /// Cell parser for a column the DNA scan did not pick up: only composite
+    /// cells (labels, parentheses, combined i7+i5) or an explicit --cell-format qualify.
+    fn composite_cell_parser(&self, column: &str, samples: &[&str]) -> Option<CellParser> {
+        if let Some(parser) = self.explicit_cell_parser(column) {
+            return Some(parser.clone());
+        }
+        CellParser::detect(samples.iter().copied()).filter(|parser| {
+            *parser.format() != CellFormat::Sequence
+                || samples.iter().any(|sample| {
+                    parser.parse(sample).is_ok_and(|cell| cell.sequences().count() > 1)
+                })
+        })
+    }
+
+    /// Index kits to verify sequences against: the built-in kits plus any
+    /// --kits files, when --verify-kits or --kits is given.
+    fn kit_database(&self) -> Result<Option<KitDatabase>, KitError> {
+        if !self.verify_kits && self.kit_files.is_empty() {
+            return Ok(None);
+        }
+        self.all_kits().map(Some)
+    }
+
+    /// Index kits to take adapter bases from, for --normalize-length pad-adapter.
+    fn adapter_kits(&self) -> Result<Option<KitDatabase>, KitError> {
+        if self.normalize_length != Some(LengthMode::PadAdapter) {
+            return Ok(None);
+        }
+        self.all_kits().map(Some)
+    }
+
+    /// The plate map given with --plate-map or --plate-kit, if any.
+    fn plate_map(&self) -> Result<Option<PlateMap>, Box<dyn std::error::Error>> {
+        if let Some(path) = &self.plate_map {
+            return Ok(Some(PlateMap::from_path(path)?));
+        }
+        let Some(kit) = &self.plate_kit else {
+            return Ok(None);
+        };
+        let map = PlateMap::from_kit(&self.all_kits()?, kit)
+            .ok_or_else(|| format!("No kit '{}' with plate wells (see `tracseq_rc kits list`)", kit))?;
+        Ok(Some(map))
+    }
+
+    fn all_kits(&self) -> Result<KitDatabase, KitError> {
+        let mut kits = KitDatabase::builtin();
+        for path in &self.kit_files {
+            kits.extend(KitDatabase::from_path(path)?);
+        }
+        Ok(kits)
+    }
+
+    /// 10x index sets to expand set names with: the bundled sets plus any --tenx-sets files.
+    fn tenx_sets(&self) -> Result<TenxIndexSets, KitError> {
+        let mut sets = TenxIndexSets::builtin();
+        for path in &self.tenx_set_files {
+            sets.extend(TenxIndexSets::from_path(path)?);
+        }
+        Ok(sets)
+    }
+
+    /// Characters accepted when scanning cells for sequence columns.
+    fn sequence_chars(&self) -> &'static str {
+        match self.alphabet {
+            Alphabet::Dna => "ATGCN",
+            Alphabet::Rna | Alphabet::Auto => "ATGCNU",
+        }
+    }
 }
 
 #[derive(Debug)]
 enum FileType {
     Excel,
     Csv,
+    SampleSheet,
+    MgiBarcodes,
 }
 
-fn detect_file_type(path: &PathBuf) -> Result<FileType, Box<dyn std::error::Error>> {
+fn detect_file_type(path: &Path) -> Result<FileType, Box<dyn std::error::Error>> {
     match path.extension().and_then(|ext| ext.to_str()) {
         Some("xlsx") | Some("xls") => Ok(FileType::Excel),
+        // Illumina sample sheets are .csv files that start with a [Header] section
+        Some("csv") if std::fs::read_to_string(path).is_ok_and(|text| is_sample_sheet(&text)) => {
+            Ok(FileType::SampleSheet)
+        }
         Some("csv") => Ok(FileType::Csv),
-        _ => Err("Unsupported file type. Please use .xlsx, .xls, or .csv files.".into()),
+        // MGI barcode lists are whitespace-separated text
+        Some("txt") | Some("barcode") => Ok(FileType::MgiBarcodes),
+        _ => Err("Unsupported file type. Please use .xlsx, .xls, .csv or .txt (MGI barcode list) files.".into()),
+    }
+}
+
+/// The data rows of a CSV or Excel batch, processed the same way for both
+/// formats, with everything the reports need.
+struct BatchRows<'a> {
+    options: &'a ProcessOptions,
+    lane_columns: LaneColumns,
+    collision_threshold: Option<usize>,
+    adapter_kits: Option<KitDatabase>,
+    kits: Option<KitDatabase>,
+    tenx_sets: TenxIndexSets,
+    /// Output fields and lane of every row, in file order
+    output_rows: Vec<Vec<String>>,
+    row_lanes: Vec<Option<String>>,
+    sql_statements: Vec<String>,
+    cell_errors: Vec<CellError>,
+    indexed_samples: Vec<(Option<String>, IndexedSample)>,
+    kit_checks: Vec<KitCheck>,
+    balance_cells: Vec<BalanceCell>,
+    tenx_expansions: Vec<TenxExpansion>,
+    export_rows: Vec<ExportRow>,
+}
+
+impl<'a> BatchRows<'a> {
+    fn new(options: &'a ProcessOptions, lane_columns: LaneColumns, normalized: bool) -> Result<BatchRows<'a>, KitError> {
+        Ok(BatchRows {
+            options,
+            lane_columns,
+            // Changed lengths can make indexes collide, so they are always rechecked
+            collision_threshold: options.check_collisions.or(normalized.then_some(0)),
+            adapter_kits: options.adapter_kits()?,
+            kits: options.kit_database()?,
+            tenx_sets: options.tenx_sets()?,
+            output_rows: Vec::new(),
+            row_lanes: Vec::new(),
+            sql_statements: Vec::new(),
+            cell_errors: Vec::new(),
+            indexed_samples: Vec::new(),
+            kit_checks: Vec::new(),
+            balance_cells: Vec::new(),
+            tenx_expansions: Vec::new(),
+            export_rows: Vec::new(),
+        })
+    }
+
+    /// Processes one data row, given as read; `row_number` is its 1-based line
+    /// or row in the file and `id` its Id cell.
+    fn process_row(&mut self, row_number: usize, lane: Option<String>, id: Option<&str>, input: Vec<String>) {
+        let options = self.options;
+        let sequence_columns = self.lane_columns.get(lane.as_deref());
+        let tenx_columns: Vec<(usize, &str)> = self.lane_columns.all.iter().map(|spec| (spec.index, spec.name.as_str())).collect();
+        let mut fields = input.clone();
+        self.tenx_expansions.extend(expand_tenx_set(row_number, &mut fields, &tenx_columns, &self.tenx_sets));
+        let mut output = fields.clone();
+        // (column name, new value) for every changed sequence cell
+        let mut updates: Vec<(String, String)> = Vec::new();
+        // Output value of each sequence column, for the collision check
+        let mut sequence_values = vec![String::new(); sequence_columns.len()];
+        
+        for (n, spec) in sequence_columns.iter().enumerate() {
+            let Some(field) = fields.get(spec.index) else {
+                continue;
+            };
+            let rc_options = options.rc_options(&spec.name);
+            match process_sequence_cell(field, spec, &rc_options, self.adapter_kits.as_ref()) {
+                Ok(Some(cell)) => {
+                    let new_val = cell.to_string();
+                    updates.push((spec.name.clone(), new_val.clone()));
+                    sequence_values[n] = new_val.clone();
+                    output[spec.index] = new_val;
+                }
+                Ok(None) => {
+                    // A 10x set name replaced by sequences the column leaves as they are
+                    if input.get(spec.index) != Some(field) {
+                        updates.push((spec.name.clone(), field.clone()));
+                    }
+                    sequence_values[n] = field.clone();
+                }
+                Err(error) => self.cell_errors.push(CellError {
+                    row: row_number,
+                    column: spec.name.clone(),
+                    value: field.clone(),
+                    error,
+                }),
+            }
+            if let Some(kits) = &self.kits {
+                let check = KitCheck::new(row_number, spec, field, &sequence_values[n], kits);
+                self.kit_checks.extend(check.map(|check| KitCheck { lane: lane.clone(), ..check }));
+            }
+            if options.check_balance {
+                self.balance_cells.extend(BalanceCell::new(lane.as_deref(), spec, field));
+            }
+        }
+        
+        if options.export.is_some() {
+            let sample = sample_name(id, row_number);
+            let input = |idx: usize| input.get(idx).cloned();
+            let output = |idx: usize| output.get(idx).cloned();
+            self.export_rows.push(export_row(sample, lane.clone(), sequence_columns, input, output));
+        }
+        if self.collision_threshold.is_some() {
+            let sample = sample_name(id, row_number);
+            let indexes = index_sequences(&sequence_values, sequence_columns);
+            self.indexed_samples.push((lane.clone(), IndexedSample::new(sample, indexes)));
+        }
+        // Queue one SQL update statement per changed column if the ID is not empty
+        if let Some(id) = id.filter(|id| !id.trim().is_empty()) {
+            for (col_name, value) in &updates {
+                self.sql_statements.push(sql_update_statement(col_name, value, id));
+            }
+        }
+        self.output_rows.push(output);
+        self.row_lanes.push(lane);
+    }
+
+    /// Fails, listing the invalid cells, when any sequence cell could not be processed.
+    fn check_cells(&self) -> Result<(), Box<dyn std::error::Error>> {
+        if self.cell_errors.is_empty() {
+            return Ok(());
+        }
+        let error = invalid_cells_error(&self.cell_errors);
+        if self.cell_errors.iter().any(|cell_error| is_set_name(&cell_error.value)) {
+            print_tenx_coverage(&self.tenx_sets);
+        }
+        Err(error)
+    }
+
+    /// Prints the SQL updates and every report, and writes the --export sheet.
+    fn report(
+        &self,
+        file_path: &Path,
+        output_path: &Path,
+        lane_files: &[PathBuf],
+        column_count: usize,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let options = self.options;
+        for statement in &self.sql_statements {
+            println!("{}", statement);
+        }
+        
+        println!("File processed successfully!");
+        println!("Output saved to: {}", output_path.display());
+        report_lane_files(options.split_lanes, lane_files);
+        if let Some(target) = options.export {
+            write_export(file_path, target, &self.export_rows, options)?;
+        }
+        println!("\nNumber of data rows: {}", self.output_rows.len());
+        println!("Number of columns: {}", column_count);
+        print_lane_rows(&self.row_lanes);
+        report_tenx_expansions(&self.tenx_expansions);
+        
+        if let Some(kits) = &self.kits {
+            report_kit_checks(&self.kit_checks, kits);
+        }
+        
+        if options.check_balance {
+            report_colour_balance(&self.balance_cells, options.instrument);
+        }
+        
+        if let Some(max_mismatches) = self.collision_threshold {
+            report_collisions(&self.indexed_samples, max_mismatches);
+        }
+        Ok(())
     }
 }
 
-fn process_csv_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+fn process_csv_file(file_path: &Path, options: &ProcessOptions) -> Result<(), Box<dyn std::error::Error>> {
     println!("\nProcessing CSV file...");
     
     // Create output filename
@@ -51,64 +633,76 @@ fn process_csv_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Error
     let index2_col = headers.iter().position(|h| h == "Index 2");
     let index_col = headers.iter().position(|h| h == "Index");
     let id_col = headers.iter().position(|h| h == "Id" || h == "Sample ID");
+    let lane_col = headers.iter().position(|h| h.eq_ignore_ascii_case("Lane"));
     
     // Detect columns containing DNA sequences
     println!("\nScanning for DNA sequence columns...");
-    let mut sequence_columns: Vec<(usize, String, bool)> = Vec::new();
+    let mut sequence_columns: Vec<ColumnSpec> = Vec::new();
+    
+    // Read the first few rows to detect sequence columns and their cell formats
+    let mut sample_reader = ReaderBuilder::new()
+        .has_headers(true)
+        .from_reader(File::open(file_path)?);
+    
+    let sample_records: Vec<_> = sample_reader.records()
+        .take(10)
+        .filter_map(Result::ok)
+        .collect();
+    let column_samples = |col_idx: usize| -> Vec<&str> {
+        sample_records.iter().filter_map(|record| record.get(col_idx)).collect()
+    };
     
     // First check if we have standard columns
     if indexnt_col.is_some() || indexnt2_col.is_some() || index2_col.is_some() || index_col.is_some() {
-        if let Some(idx) = indexnt_col {
-            sequence_columns.push((idx, "IndexNtSequence".to_string(), true));
-        }
-        if let Some(idx) = indexnt2_col {
-            sequence_columns.push((idx, "IndexNtSequence2".to_string(), false));
-        }
-        if let Some(idx) = index2_col {
-            sequence_columns.push((idx, "Index 2".to_string(), false));
-        }
-        if let Some(idx) = index_col {
-            sequence_columns.push((idx, "Index".to_string(), true));
+        for (col, name, has_delimiter) in [
+            (indexnt_col, "IndexNtSequence", true),
+            (indexnt2_col, "IndexNtSequence2", false),
+            (index2_col, "Index 2", false),
+            (index_col, "Index", true),
+        ] {
+            if let Some(idx) = col {
+                let parser = options.cell_parser(name, &column_samples(idx), has_delimiter);
+                sequence_columns.push(options.column_spec(idx, name, parser));
+            }
         }
     } else {
         // No standard columns, scan for DNA patterns in first few rows
-        let mut sample_reader = ReaderBuilder::new()
-            .has_headers(true)
-            .from_reader(File::open(file_path)?);
-        
-        let sample_records: Vec<_> = sample_reader.records()
-            .take(10)
-            .filter_map(Result::ok)
-            .collect();
-        
+        let sequence_chars = options.sequence_chars();
         for col_idx in 0..headers.len() {
             let mut has_sequences = false;
             let mut has_delimiter = false;
             
             for record in &sample_records {
-                if let Some(field) = record.get(col_idx) {
-                    if field.len() >= 4 {
-                        if field.contains('-') {
-                            let parts: Vec<&str> = field.split('-').collect();
-                            if parts.len() == 2 && parts[1].len() >= 4 {
-                                if parts[1].chars().all(|c| "ATGCN".contains(c)) {
-                                    has_sequences = true;
-                                    has_delimiter = true;
-                                    break;
-                                }
-                            }
-                        } else if field.chars().all(|c| "ATGCN".contains(c)) {
+                if let Some(field) = record.get(col_idx)
+                    && field.len() >= 4
+                {
+                    if field.contains('-') {
+                        let parts: Vec<&str> = field.split('-').collect();
+                        if parts.len() == 2
+                            && parts[1].len() >= 4
+                            && parts[1].chars().all(|c| sequence_chars.contains(c))
+                        {
                             has_sequences = true;
-                            has_delimiter = false;
+                            has_delimiter = true;
                             break;
                         }
+                    } else if field.chars().all(|c| sequence_chars.contains(c)) {
+                        has_sequences = true;
+                        has_delimiter = false;
+                        break;
                     }
                 }
             }
             
+            let col_name = headers.get(col_idx).map(|s| s.to_string()).unwrap_or_else(|| format!("Column_{}", col_idx + 1));
+            let samples = column_samples(col_idx);
             if has_sequences {
-                let col_name = headers.get(col_idx).map(|s| s.to_string()).unwrap_or_else(|| format!("Column_{}", col_idx + 1));
-                sequence_columns.push((col_idx, col_name, has_delimiter));
+                let parser = options.cell_parser(&col_name, &samples, has_delimiter);
+                sequence_columns.push(options.column_spec(col_idx, &col_name, parser));
+            } else if let Some(parser) = options.composite_cell_parser(&col_name, &samples) {
+                sequence_columns.push(options.column_spec(col_idx, &col_name, parser));
+            } else if samples.iter().any(|sample| is_set_name(sample)) {
+                sequence_columns.push(options.column_spec(col_idx, &col_name, CellParser::default()));
             }
         }
     }
@@ -125,8 +719,14 @@ fn process_csv_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Error
         }
     } else {
         println!("- Sequence columns found: {}", sequence_columns.len());
-        for (idx, name, delim) in &sequence_columns {
-            println!("  * Column {}: '{}' (delimiter: {})", idx + 1, name, if *delim { "yes" } else { "no" });
+        for spec in &sequence_columns {
+            println!(
+                "  * Column {}: '{}' (format: {}, operation: {})",
+                spec.index + 1, spec.name, spec.parser, spec.operation
+            );
+            if let Some(reason) = &spec.reason {
+                println!("    {}", reason);
+            }
         }
     }
     
@@ -135,106 +735,66 @@ fn process_csv_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Error
         println!("  Column {}: '{}'", idx + 1, header);
     }
     
+    let all_records: Vec<csv::StringRecord> = if options.normalize_length.is_some() {
+        ReaderBuilder::new().has_headers(true).from_path(file_path)?.records().collect::<Result<_, _>>()?
+    } else {
+        Vec::new()
+    };
+    let record_lanes: Vec<Option<String>> = all_records
+        .iter()
+        .map(|record| lane_of(lane_col.and_then(|idx| record.get(idx))))
+        .collect();
+    let (lane_columns, normalized) = plan_index_lengths(options.normalize_length, sequence_columns, &record_lanes, |col_idx| {
+        all_records.iter().map(|record| record.get(col_idx).unwrap_or("").to_string()).collect()
+    });
+    let mut batch = BatchRows::new(options, lane_columns, normalized)?;
+    for result in reader.records() {
+        let record = result?;
+        // Line 1 is the header, so data rows start on line 2
+        let row_number = record
+            .position()
+            .map(|pos| pos.line() as usize)
+            .unwrap_or(batch.output_rows.len() + 2);
+        let lane = lane_of(lane_col.and_then(|idx| record.get(idx)));
+        let id = id_col.and_then(|idx| record.get(idx));
+        batch.process_row(row_number, lane, id, record.iter().map(str::to_string).collect());
+    }
+    
+    // Refuse to write a partially reverse-complemented file
+    batch.check_cells()?;
+    
     // Create output CSV file
     let output_file = File::create(&output_path)?;
     let mut writer = WriterBuilder::new()
         .has_headers(true)
         .from_writer(output_file);
     
-    // Write headers
     writer.write_record(&headers)?;
+    for record in &batch.output_rows {
+        writer.write_record(record)?;
+    }
+    writer.flush()?;
     
-    let mut data_row_count = 0;
-    
-    // Process rows
-    for result in reader.records() {
-        let record = result?;
-        let mut output_record = Vec::new();
-        let mut rc_value: Option<String> = None;
-        let mut id_value: Option<String> = None;
-        let mut processed_col_name: Option<String> = None;
-        
-        // Get ID value if present
-        if let Some(idx) = id_col {
-            if let Some(field) = record.get(idx) {
-                id_value = Some(field.to_string());
-            }
-        }
-        
-        for (col_idx, field) in record.iter().enumerate() {
-            // Check if this column is a sequence column
-            let mut processed = false;
-            for (seq_col_idx, seq_col_name, has_delimiter) in &sequence_columns {
-                if col_idx == *seq_col_idx {
-                    if *has_delimiter {
-                        // Handle delimiter pattern
-                        let parts: Vec<&str> = field.splitn(2, '-').collect();
-                        if parts.len() == 2 {
-                            let rc = reverse_complement(parts[1]);
-                            let new_val = format!("{}-{}", parts[0], rc);
-                            rc_value = Some(new_val.clone());
-                            processed_col_name = Some(seq_col_name.clone());
-                            output_record.push(new_val);
-                        } else {
-                            // No delimiter found, treat as full sequence
-                            let rc = reverse_complement(field);
-                            rc_value = Some(rc.clone());
-                            processed_col_name = Some(seq_col_name.clone());
-                            output_record.push(rc);
-                        }
-                    } else {
-                        // Direct sequence pattern
-                        let rc = reverse_complement(field);
-                        rc_value = Some(rc.clone());
-                        processed_col_name = Some(seq_col_name.clone());
-                        output_record.push(rc);
-                    }
-                    processed = true;
-                    break;
-                }
-            }
-            
-            if !processed {
-                // Copy non-sequence fields as-is
-                output_record.push(field.to_string());
-            }
-        }
-        
-        // Write the output record
-        writer.write_record(&output_record)?;
-        
-        // Print SQL update statement if both values are present and ID is not empty
-        if let (Some(id), Some(rc), Some(col_name)) = (id_value, rc_value, processed_col_name) {
-            if !id.trim().is_empty() {
-                // Generate SQL with proper column name escaping
-                if col_name.contains(' ') || col_name.starts_with("Column_") {
-                    println!(
-                        "UPDATE SampleBatchItems SET [{}] = '{}' WHERE Id = '{}';",
-                        col_name, rc, id
-                    );
-                } else {
-                    println!(
-                        "UPDATE SampleBatchItems SET {} = '{}' WHERE Id = '{}';",
-                        col_name, rc, id
-                    );
+    let mut lane_files: Vec<PathBuf> = Vec::new();
+    if options.split_lanes {
+        for lane in distinct_lanes(&batch.row_lanes) {
+            let lane_path = lane_output_path(&output_path, lane);
+            let mut writer = WriterBuilder::new().has_headers(true).from_path(&lane_path)?;
+            writer.write_record(&headers)?;
+            for (record, row_lane) in batch.output_rows.iter().zip(&batch.row_lanes) {
+                if in_lane(row_lane, lane) {
+                    writer.write_record(record)?;
                 }
             }
+            writer.flush()?;
+            lane_files.push(lane_path);
         }
-        
-        data_row_count += 1;
     }
     
-    writer.flush()?;
-    
-    println!("File processed successfully!");
-    println!("Output saved to: {}", output_path.display());
-    println!("\nNumber of data rows: {}", data_row_count);
-    println!("Number of columns: {}", headers.len());
-    
-    Ok(())
+    batch.report(file_path, &output_path, &lane_files, headers.len())
 }
 
-fn process_excel_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Error>> {
+fn process_excel_file(file_path: &Path, options: &ProcessOptions) -> Result<(), Box<dyn std::error::Error>> {
     println!("\nProcessing Excel file...");
     
     // Open the input Excel file
@@ -259,13 +819,13 @@ fn process_excel_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Err
         
         println!("\nSearching for 'Sample ID' header row...");
         for (idx, row) in all_rows.iter().enumerate() {
-            if let Some(first_cell) = row.get(0) {
-                if first_cell.to_string().trim() == "Sample ID" {
-                    header_row_idx = Some(idx);
-                    header_row = Some(row);
-                    println!("Found 'Sample ID' header at row {}", idx + 1);
-                    break;
-                }
+            if let Some(first_cell) = row.first()
+                && first_cell.to_string().trim() == "Sample ID"
+            {
+                header_row_idx = Some(idx);
+                header_row = Some(row);
+                println!("Found 'Sample ID' header at row {}", idx + 1);
+                break;
             }
         }
         
@@ -285,49 +845,61 @@ fn process_excel_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Err
 
         // Write header row
         for (col, cell) in header_row.iter().enumerate() {
-            sheet.write_string(0, col as u16, &cell.to_string())?;
+            sheet.write_string(0, col as u16, cell.to_string())?;
         }
 
         // Check for IndexNtSequence, IndexNtSequence2, Index 2, or Index
         let indexnt_col = header_row
             .iter()
-            .position(|c| c.to_string() == "IndexNtSequence");
+            .position(|c| *c == "IndexNtSequence");
         let indexnt2_col = header_row
             .iter()
-            .position(|c| c.to_string() == "IndexNtSequence2");
-        let index2_col = header_row.iter().position(|c| c.to_string() == "Index 2");
-        let index_col = header_row.iter().position(|c| c.to_string() == "Index");
+            .position(|c| *c == "IndexNtSequence2");
+        let index2_col = header_row.iter().position(|c| *c == "Index 2");
+        let index_col = header_row.iter().position(|c| *c == "Index");
         // Look for both "Id" and "Sample ID" columns
         let id_col = header_row.iter().position(|c| {
             let col = c.to_string();
             col == "Id" || col == "Sample ID"
         });
+        let lane_col = header_row.iter().position(|c| c.to_string().eq_ignore_ascii_case("Lane"));
 
         // Detect columns containing DNA sequences by scanning data
         println!("\nScanning for DNA sequence columns...");
-        let mut sequence_columns: Vec<(usize, String, bool)> = Vec::new(); // (index, name, has_delimiter)
+        let mut sequence_columns: Vec<ColumnSpec> = Vec::new();
+        
+        // Rows after the header, used to detect sequence columns and their cell formats
+        let sample_rows: Vec<_> = all_rows.iter()
+            .skip(header_row_idx + 1)
+            .take(10)
+            .collect();
+        let sample_values: Vec<Vec<String>> = (0..header_row.len())
+            .map(|col_idx| {
+                sample_rows.iter()
+                    .filter_map(|row| row.get(col_idx).map(|cell| cell.to_string()))
+                    .collect()
+            })
+            .collect();
+        let column_samples = |col_idx: usize| -> Vec<&str> {
+            sample_values[col_idx].iter().map(String::as_str).collect()
+        };
         
         // First check if we have standard columns
         if indexnt_col.is_some() || indexnt2_col.is_some() || index2_col.is_some() || index_col.is_some() {
-            if let Some(idx) = indexnt_col {
-                sequence_columns.push((idx, "IndexNtSequence".to_string(), true));
-            }
-            if let Some(idx) = indexnt2_col {
-                sequence_columns.push((idx, "IndexNtSequence2".to_string(), false));
-            }
-            if let Some(idx) = index2_col {
-                sequence_columns.push((idx, "Index 2".to_string(), false));
-            }
-            if let Some(idx) = index_col {
-                sequence_columns.push((idx, "Index".to_string(), true));
+            for (col, name, has_delimiter) in [
+                (indexnt_col, "IndexNtSequence", true),
+                (indexnt2_col, "IndexNtSequence2", false),
+                (index2_col, "Index 2", false),
+                (index_col, "Index", true),
+            ] {
+                if let Some(idx) = col {
+                    let parser = options.cell_parser(name, &column_samples(idx), has_delimiter);
+                    sequence_columns.push(options.column_spec(idx, name, parser));
+                }
             }
         } else {
             // No standard columns, scan for DNA patterns in rows after the header
-            let sample_rows: Vec<_> = all_rows.iter()
-                .skip(header_row_idx + 1)
-                .take(10)
-                .collect();
-            
+            let sequence_chars = options.sequence_chars();
             for col_idx in 0..header_row.len() {
                 let mut has_sequences = false;
                 let mut has_delimiter = false;
@@ -342,7 +914,7 @@ fn process_excel_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Err
                                 let parts: Vec<&str> = val.split('-').collect();
                                 if parts.len() == 2 && parts[1].len() >= 4 {
                                     // More strict check: at least 80% should be ATGCN
-                                    let dna_chars = parts[1].chars().filter(|c| "ATGCN".contains(*c)).count();
+                                    let dna_chars = parts[1].chars().filter(|c| sequence_chars.contains(*c)).count();
                                     if dna_chars as f32 / parts[1].len() as f32 >= 0.8 {
                                         has_sequences = true;
                                         has_delimiter = true;
@@ -351,7 +923,7 @@ fn process_excel_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Err
                                 }
                             }
                             // Check for direct sequence pattern - must be all DNA chars
-                            else if val.len() >= 6 && val.chars().all(|c| "ATGCN".contains(c)) {
+                            else if val.len() >= 6 && val.chars().all(|c| sequence_chars.contains(c)) {
                                 has_sequences = true;
                                 has_delimiter = false;
                                 break;
@@ -360,13 +932,18 @@ fn process_excel_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Err
                     }
                 }
                 
+                let mut col_name = header_row[col_idx].to_string();
+                if col_name.is_empty() {
+                    col_name = format!("Column_{}", col_idx + 1);
+                }
+                let samples = column_samples(col_idx);
                 if has_sequences {
-                    let col_name = header_row[col_idx].to_string();
-                    if col_name.is_empty() {
-                        sequence_columns.push((col_idx, format!("Column_{}", col_idx + 1), has_delimiter));
-                    } else {
-                        sequence_columns.push((col_idx, col_name, has_delimiter));
-                    }
+                    let parser = options.cell_parser(&col_name, &samples, has_delimiter);
+                    sequence_columns.push(options.column_spec(col_idx, &col_name, parser));
+                } else if let Some(parser) = options.composite_cell_parser(&col_name, &samples) {
+                    sequence_columns.push(options.column_spec(col_idx, &col_name, parser));
+                } else if samples.iter().any(|sample| is_set_name(sample)) {
+                    sequence_columns.push(options.column_spec(col_idx, &col_name, CellParser::default()));
                 }
             }
         }
@@ -404,94 +981,64 @@ fn process_excel_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Err
             }
         } else {
             println!("- Sequence columns found: {}", sequence_columns.len());
-            for (idx, name, delim) in &sequence_columns {
-                println!("  * Column {}: '{}' (delimiter: {})", idx + 1, name, if *delim { "yes" } else { "no" });
+            for spec in &sequence_columns {
+                println!(
+                    "  * Column {}: '{}' (format: {}, operation: {})",
+                    spec.index + 1, spec.name, spec.parser, spec.operation
+                );
+                if let Some(reason) = &spec.reason {
+                    println!("    {}", reason);
+                }
             }
         }
         
-        let mut data_row_count = 0;
+        let lane_cell = |row: &&[calamine::DataType]| lane_of(lane_col.and_then(|idx| row.get(idx)).map(|cell| cell.to_string()).as_deref());
+        let data_lanes: Vec<Option<String>> = all_rows.iter().skip(header_row_idx + 1).map(lane_cell).collect();
+        let (lane_columns, normalized) = plan_index_lengths(options.normalize_length, sequence_columns, &data_lanes, |col_idx| {
+            all_rows
+                .iter()
+                .skip(header_row_idx + 1)
+                .map(|row| row.get(col_idx).map(|cell| cell.to_string()).unwrap_or_default())
+                .collect()
+        });
+        let mut batch = BatchRows::new(options, lane_columns, normalized)?;
         // Only process rows after the header row
         for (idx, row) in all_rows.iter().enumerate().skip(header_row_idx + 1) {
-            let row_idx = idx - header_row_idx - 1; // Adjust for output row index
-            let mut rc_value: Option<String> = None;
-            let mut id_value: Option<String> = None;
-            let mut processed_col_name: Option<String> = None;
-            
-            // Get ID value if present
-            if let Some(idx) = id_col {
-                if let Some(cell) = row.get(idx) {
-                    id_value = Some(cell.to_string());
-                }
-            }
-            
-            // Process each cell in the row
-            for (col_idx, cell) in row.iter().enumerate() {
-                // Check if this column is a sequence column
-                let mut processed = false;
-                for (seq_col_idx, seq_col_name, has_delimiter) in &sequence_columns {
-                    if col_idx == *seq_col_idx {
-                        let val = cell.to_string();
-                        if *has_delimiter {
-                            // Handle delimiter pattern (e.g., "Prefix-SEQUENCE")
-                            let parts = val.splitn(2, '-').collect::<Vec<_>>();
-                            if parts.len() == 2 {
-                                let rc = reverse_complement(parts[1]);
-                                let new_val = format!("{}-{}", parts[0], rc);
-                                rc_value = Some(new_val.clone());
-                                processed_col_name = Some(seq_col_name.clone());
-                                sheet.write_string((row_idx + 1) as u32, col_idx as u16, &new_val)?;
-                            } else {
-                                // No delimiter found, treat as full sequence
-                                let rc = reverse_complement(&val);
-                                rc_value = Some(rc.clone());
-                                processed_col_name = Some(seq_col_name.clone());
-                                sheet.write_string((row_idx + 1) as u32, col_idx as u16, &rc)?;
-                            }
-                        } else {
-                            // Direct sequence pattern
-                            let rc = reverse_complement(&val);
-                            rc_value = Some(rc.clone());
-                            processed_col_name = Some(seq_col_name.clone());
-                            sheet.write_string((row_idx + 1) as u32, col_idx as u16, &rc)?;
-                        }
-                        processed = true;
-                        break;
-                    }
-                }
-                
-                if !processed {
-                    // Copy non-sequence columns as-is
-                    sheet.write_string((row_idx + 1) as u32, col_idx as u16, &cell.to_string())?;
-                }
+            let lane = data_lanes[idx - header_row_idx - 1].clone();
+            let id = id_col.and_then(|idx| row.get(idx)).map(|cell| cell.to_string());
+            batch.process_row(idx + 1, lane, id.as_deref(), row.iter().map(|cell| cell.to_string()).collect());
+        }
+
+        // Refuse to write a partially reverse-complemented workbook
+        batch.check_cells()?;
+
+        // Save the workbook
+        for (row_idx, row) in batch.output_rows.iter().enumerate() {
+            for (col_idx, value) in row.iter().enumerate() {
+                sheet.write_string((row_idx + 1) as u32, col_idx as u16, value)?;
             }
-            
-            // Print SQL update statement if both values are present and ID is not empty
-            if let (Some(id), Some(rc), Some(col_name)) = (id_value, rc_value, processed_col_name) {
-                if !id.trim().is_empty() {
-                    // Generate SQL with proper column name escaping
-                    if col_name.contains(' ') || col_name.starts_with("Column_") {
-                        println!(
-                            "UPDATE SampleBatchItems SET [{}] = '{}' WHERE Id = '{}';",
-                            col_name, rc, id
-                        );
-                    } else {
-                        println!(
-                            "UPDATE SampleBatchItems SET {} = '{}' WHERE Id = '{}';",
-                            col_name, rc, id
-                        );
+        }
+        output_workbook.save(&output_path)?;
+
+        let mut lane_files: Vec<PathBuf> = Vec::new();
+        if options.split_lanes {
+            let headers: Vec<String> = header_row.iter().map(|cell| cell.to_string()).collect();
+            for lane in distinct_lanes(&data_lanes) {
+                let mut lane_workbook = Workbook::new();
+                let lane_sheet = lane_workbook.add_worksheet();
+                let rows = batch.output_rows.iter().zip(&data_lanes).filter(|(_, row_lane)| in_lane(row_lane, lane));
+                for (row_idx, row) in std::iter::once(&headers).chain(rows.map(|(row, _)| row)).enumerate() {
+                    for (col_idx, value) in row.iter().enumerate() {
+                        lane_sheet.write_string(row_idx as u32, col_idx as u16, value)?;
                     }
                 }
+                let lane_path = lane_output_path(&output_path, lane);
+                lane_workbook.save(&lane_path)?;
+                lane_files.push(lane_path);
             }
-            data_row_count += 1;
         }
 
-        // Save the workbook
-        output_workbook.save(&output_path)?;
-
-        println!("File processed successfully!");
-        println!("Output saved to: {}", output_path.display());
-        println!("\nNumber of data rows: {}", data_row_count);
-        println!("Number of columns: {}", header_row.len());
+        batch.report(file_path, &output_path, &lane_files, header_row.len())?;
     } else {
         println!("Error: Could not read the worksheet");
     }
@@ -499,23 +1046,1886 @@ fn process_excel_file(file_path: &PathBuf) -> Result<(), Box<dyn std::error::Err
     Ok(())
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = Args::parse();
-
-    // Detect file type and process accordingly
-    match detect_file_type(&args.file)? {
-        FileType::Excel => process_excel_file(&args.file),
-        FileType::Csv => process_csv_file(&args.file),
+/// Reverse complements the i5 column of an Illumina sample sheet and writes
+/// every other line back unchanged. In v1 sheets `index2` in `[Data]` is always
+/// processed; in v2 sheets `Index2` in `[BCLConvert_Data]` only when asked for
+/// with --operation. AVITI run manifests (`Index2` in `[SAMPLES]`) follow the
+/// AVITI orientation table unless another instrument or workflow is given.
+fn process_sample_sheet(file_path: &Path, options: &ProcessOptions) -> Result<(), Box<dyn std::error::Error>> {
+    println!("\nProcessing Illumina sample sheet...");
+    
+    // Create output filename
+    let output_path = file_path.with_file_name(format!(
+        "{}_RC.csv",
+        file_path.file_stem().unwrap().to_string_lossy()
+    ));
+    
+    let mut sample_sheet = SampleSheet::parse(&std::fs::read_to_string(file_path)?);
+    let version = sample_sheet.version();
+    let (index_name, index2_name) = version.index_columns();
+    let data_name = version.data_section();
+    
+    println!("Sample sheet version: {}", version);
+    // A run manifest is only read by an AVITI, so its orientation table applies
+    let manifest_options;
+    let options = if version == SheetVersion::Aviti && options.target_workflow().is_none() {
+        manifest_options = ProcessOptions {
+            instrument: Some(Instrument::Aviti),
+            ..options.clone()
+        };
+        &manifest_options
+    } else {
+        options
+    };
+    println!("\nSections:");
+    for section in sample_sheet.sections() {
+        println!("  [{}]: {} line(s)", section.name(), section.lines().len());
     }
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs;
-    use tempfile::NamedTempFile;
-
-    #[test]
+    
+    let override_cycles = sample_sheet
+        .section("BCLConvert_Settings")
+        .and_then(|settings| settings.value("OverrideCycles"));
+    let data = sample_sheet
+        .section_mut(data_name)
+        .ok_or_else(|| format!("Sample sheet has no [{}] section", data_name))?;
+    let mut table = data
+        .table()
+        .ok_or_else(|| format!("The [{}] section has no column header", data_name))?;
+    let sample_col = table.column(version.sample_column());
+    let index_col = table.column(index_name);
+    let index2_col = table.column(index2_name);
+    let override_cycles_col = table.column("OverrideCycles");
+    let lane_col = table.column("Lane");
+    
+    println!("\nDetected columns:");
+    println!("- {} column: {}", version.sample_column(), if sample_col.is_some() { "Found" } else { "NOT FOUND" });
+    
+    let mut sequence_columns: Vec<ColumnSpec> = Vec::new();
+    if let Some(idx) = index2_col {
+        let samples: Vec<&str> = table.rows.iter().take(10).filter_map(|row| row.get(idx)).collect();
+        let parser = options.cell_parser(index2_name, &samples, false);
+        let mut spec = options.column_spec(idx, index2_name, parser);
+        // v2 sheets are only changed when asked to, explicitly or by instrument/workflow
+        let asked = options.explicit_operation(index2_name).is_some() || options.target_workflow().is_some();
+        if version == SheetVersion::V2 && !asked {
+            spec.operation = Operation::Passthrough;
+        }
+        println!(
+            "- {} column: Column {} (format: {}, operation: {})",
+            index2_name, idx + 1, spec.parser, spec.operation
+        );
+        if let Some(reason) = &spec.reason {
+            println!("   {}", reason);
+        } else if version == SheetVersion::V2 && !asked {
+            println!("   Left unchanged; pass --operation {}=rc or --instrument to reverse complement it.", index2_name);
+        }
+        sequence_columns.push(spec);
+    } else {
+        println!("- {} column: NOT FOUND", index2_name);
+        println!("\n⚠️  Warning: No {} column in [{}]; the sheet is written back unchanged.", index2_name, data_name);
+    }
+    let kits = options.kit_database()?;
+    let mut kit_checks: Vec<KitCheck> = Vec::new();
+    let mut balance_cells: Vec<BalanceCell> = Vec::new();
+    if (kits.is_some() || options.check_balance || options.normalize_length.is_some() || options.export.is_some())
+        && let Some(idx) = index_col
+    {
+        // i7 is never reverse complemented in a sample sheet, but still checked
+        // against the kits and for balance, normalized in length and exported
+        let mut spec = options.column_spec(idx, index_name, CellParser::default());
+        spec.operation = Operation::Passthrough;
+        spec.reason = None;
+        sequence_columns.push(spec);
+    }
+    
+    let row_lanes: Vec<Option<String>> = table
+        .rows
+        .iter()
+        .map(|row| lane_of(lane_col.and_then(|idx| row.get(idx))))
+        .collect();
+    let (lane_columns, normalized) = plan_index_lengths(options.normalize_length, sequence_columns, &row_lanes, |col_idx| {
+        table.rows.iter().map(|row| row.get(col_idx).unwrap_or("").to_string()).collect()
+    });
+    // Changed lengths can make indexes collide, so they are always rechecked
+    let collision_threshold = options.check_collisions.or(normalized.then_some(0));
+    let adapter_kits = options.adapter_kits()?;
+    
+    let mut cell_errors: Vec<CellError> = Vec::new();
+    let mut cycle_warnings: Vec<String> = Vec::new();
+    let mut indexed_samples: Vec<(Option<String>, IndexedSample)> = Vec::new();
+    let mut changed_counts = vec![0; lane_columns.all.len()];
+    let tenx_sets = options.tenx_sets()?;
+    let tenx_columns: Vec<(usize, &str)> = [(index_col, index_name), (index2_col, index2_name)]
+        .into_iter()
+        .filter_map(|(col, name)| Some((col?, name)))
+        .collect();
+    let mut tenx_expansions: Vec<TenxExpansion> = Vec::new();
+    let mut export_rows: Vec<ExportRow> = Vec::new();
+    // Rows of single-index 10x sets, written once per oligo
+    let mut pooled_rows: Vec<(TableRow, Vec<Vec<String>>)> = Vec::new();
+    
+    for (row, lane) in table.rows.iter_mut().zip(&row_lanes) {
+        let input_fields = row.fields.clone();
+        let expansion = expand_tenx_set(row.line_number(), &mut row.fields, &tenx_columns, &tenx_sets);
+        if expansion.is_none() {
+            // Columns that are processed report unknown set names themselves
+            let columns = lane_columns.get(lane.as_deref());
+            for (idx, name) in tenx_columns.iter().filter(|(idx, _)| columns.iter().all(|spec| spec.index != *idx)) {
+                if let Some(value) = row.get(*idx).filter(|value| is_set_name(value)) {
+                    cell_errors.push(CellError {
+                        row: row.line_number(),
+                        column: name.to_string(),
+                        value: value.to_string(),
+                        error: unknown_set_error(value),
+                    });
+                }
+            }
+        }
+        for (n, spec) in lane_columns.get(lane.as_deref()).iter().enumerate() {
+            let Some(value) = row.get(spec.index).map(str::to_string) else {
+                continue;
+            };
+            let rc_options = options.rc_options(&spec.name);
+            match process_sequence_cell(&value, spec, &rc_options, adapter_kits.as_ref()) {
+                Ok(Some(cell)) => {
+                    // Padding can fill in cells past the end of a short row
+                    if row.fields.len() <= spec.index {
+                        row.fields.resize(spec.index + 1, String::new());
+                    }
+                    row.fields[spec.index] = cell.to_string();
+                    changed_counts[n] += 1;
+                }
+                Ok(None) => {}
+                Err(error) => cell_errors.push(CellError {
+                    row: row.line_number(),
+                    column: spec.name.clone(),
+                    value: value.clone(),
+                    error,
+                }),
+            }
+            if let Some(kits) = &kits {
+                let after = row.get(spec.index).unwrap_or("");
+                let check = KitCheck::new(row.line_number(), spec, &value, after, kits);
+                kit_checks.extend(check.map(|check| KitCheck { lane: lane.clone(), ..check }));
+            }
+            if options.check_balance {
+                balance_cells.extend(BalanceCell::new(lane.as_deref(), spec, &value));
+            }
+        }
+        
+        let indexes: Vec<String> = [index_col, index2_col]
+            .into_iter()
+            .flatten()
+            .map(|idx| row.get(idx).unwrap_or("").trim().to_string())
+            .collect();
+        let sample = sample_name(sample_col.and_then(|idx| row.get(idx)), row.line_number());
+        if options.export.is_some() {
+            let columns = lane_columns.get(lane.as_deref());
+            let input = |idx: usize| input_fields.get(idx).cloned();
+            let output = |idx: usize| row.get(idx).map(str::to_string);
+            export_rows.push(export_row(sample.clone(), lane.clone(), columns, input, output));
+        }
+        
+        // OverrideCycles, per row or from [BCLConvert_Settings], has to match the index lengths
+        let row_cycles = override_cycles_col
+            .and_then(|idx| row.get(idx))
+            .filter(|value| !value.trim().is_empty())
+            .or(override_cycles.as_deref());
+        if let Some(cycles) = row_cycles {
+            match override_index_cycles(cycles) {
+                Ok(index_cycles) => {
+                    for (n, (index, expected)) in indexes.iter().zip(&index_cycles).enumerate() {
+                        if !index.is_empty() && index.len() != *expected {
+                            cycle_warnings.push(format!(
+                                "{}: index {} has {} bases but OverrideCycles '{}' reads {}",
+                                sample, n + 1, index.len(), cycles, expected
+                            ));
+                        }
+                    }
+                }
+                Err(error) => cycle_warnings.push(format!("{}: {}", sample, error)),
+            }
+        }
+        
+        if collision_threshold.is_some() {
+            indexed_samples.push((lane.clone(), IndexedSample::new(sample, indexes)));
+        }
+        data.update_row(row);
+        if let Some(expansion) = expansion {
+            if let (TenxIndexSet::Single { oligos, .. }, Some(idx)) = (&expansion.set, index_col) {
+                pooled_rows.push((row.clone(), oligos.iter().map(|oligo| with_field(&row.fields, idx, oligo)).collect()));
+            }
+            tenx_expansions.push(expansion);
+        }
+    }
+    // Later rows first, as each replacement moves the lines after it
+    for (row, copies) in pooled_rows.iter().rev() {
+        data.replace_row(row, copies);
+    }
+    
+    // Refuse to write a partially reverse-complemented sheet
+    if !cell_errors.is_empty() {
+        let error = invalid_cells_error(&cell_errors);
+        if cell_errors.iter().any(|cell_error| is_set_name(&cell_error.value)) {
+            print_tenx_coverage(&tenx_sets);
+        }
+        return Err(error);
+    }
+    
+    std::fs::write(&output_path, sample_sheet.to_string())?;
+    
+    let mut lane_files: Vec<PathBuf> = Vec::new();
+    if options.split_lanes {
+        for lane in distinct_lanes(&row_lanes) {
+            let mut lane_sheet = sample_sheet.clone();
+            if let Some(data) = lane_sheet.section_mut(data_name) {
+                data.retain_rows(|row| in_lane(&lane_of(lane_col.and_then(|idx| row.get(idx))), lane));
+            }
+            let lane_path = lane_output_path(&output_path, lane);
+            std::fs::write(&lane_path, lane_sheet.to_string())?;
+            lane_files.push(lane_path);
+        }
+    }
+    
+    println!("File processed successfully!");
+    println!("Output saved to: {}", output_path.display());
+    report_lane_files(options.split_lanes, &lane_files);
+    if let Some(target) = options.export {
+        write_export(file_path, target, &export_rows, options)?;
+    }
+    println!("\nNumber of data rows: {}", table.rows.len());
+    print_lane_rows(&row_lanes);
+    for (spec, changed) in lane_columns.all.iter().zip(&changed_counts) {
+        if spec.name == index2_name || *changed > 0 {
+            println!("Changed {} cells: {}", spec.name, changed);
+        }
+    }
+    report_tenx_expansions(&tenx_expansions);
+    
+    if let Some(kits) = &kits {
+        report_kit_checks(&kit_checks, kits);
+    }
+    
+    if !cycle_warnings.is_empty() {
+        println!("\n⚠️  Warning: index lengths do not match OverrideCycles:");
+        for warning in &cycle_warnings {
+            println!("  * {}", warning);
+        }
+    }
+    
+    if options.check_balance {
+        report_colour_balance(&balance_cells, options.instrument);
+    }
+    
+    if let Some(max_mismatches) = collision_threshold {
+        report_collisions(&indexed_samples, max_mismatches);
+    }
+    
+    Ok(())
+}
+
+/// Reverse complements the barcodes of an MGI barcode list, like the columns
+/// of a CSV file: `Barcode` and `Barcode2` follow --operation, --instrument
+/// and --workflow, and names are kept. Nothing is written when a barcode is
+/// invalid. --export is refused, as a list is not a batch of samples.
+fn process_mgi_barcodes(file_path: &Path, options: &ProcessOptions) -> Result<(), Box<dyn std::error::Error>> {
+    println!("\nProcessing MGI barcode list...");
+
+    if let Some(target) = options.export {
+        return Err(format!(
+            "--export {} cannot be used with an MGI barcode list; export from the CSV or Excel batch instead",
+            target.id()
+        )
+        .into());
+    }
+    let output_path = file_path.with_file_name(format!(
+        "{}_RC.txt",
+        file_path.file_stem().unwrap().to_string_lossy()
+    ));
+    let (line_numbers, mut barcodes): (Vec<usize>, Vec<_>) = read_barcodes(&std::fs::read_to_string(file_path)?)
+        .map_err(|e| format!("{}: {}", file_path.display(), e))?
+        .into_iter()
+        .unzip();
+    let mode = barcode_mode(&barcodes);
+    println!("Barcode list: {} ({} barcode(s))", mode, barcodes.len());
+
+    // The DNBSEQ orientation table decides unless --operation does: the same
+    // operations turn forward-strand indexes into a list and a list back
+    let spec = |index: usize, name: &str, role: IndexRole| {
+        let mut spec = options.column_spec(index, name, CellParser::default());
+        if options.explicit_operation(name).is_none()
+            && let Some(operation) = mode.operation(role)
+        {
+            let action = match operation {
+                Operation::ReverseComplement => "as its reverse complement, so it is reverse complemented",
+                _ => "as entered, so it is left unchanged",
+            };
+            spec.operation = operation;
+            spec.reason = Some(format!("In {} splitting the DNBSEQ reads {} ({}) {}", mode, name, role, action));
+        }
+        spec
+    };
+    let barcode_spec = spec(0, "Barcode", IndexRole::I7);
+    let barcode2_spec = spec(1, "Barcode2", IndexRole::I5);
+    println!("\nColumns:");
+    let mut specs = vec![&barcode_spec];
+    if mode == BarcodeMode::Dual {
+        specs.push(&barcode2_spec);
+    }
+    for spec in &specs {
+        println!("- {} (operation: {})", spec.name, spec.operation);
+        if let Some(reason) = &spec.reason {
+            println!("   {}", reason);
+        }
+    }
+
+    let kits = options.kit_database()?;
+    let mut kit_checks: Vec<KitCheck> = Vec::new();
+    let mut balance_cells: Vec<BalanceCell> = Vec::new();
+    let mut cell_errors: Vec<CellError> = Vec::new();
+    let mut process = |line: usize, spec: &ColumnSpec, sequence: &mut String| {
+        if let Err(error) = validate_sequence(sequence) {
+            cell_errors.push(CellError {
+                row: line,
+                column: spec.name.clone(),
+                value: sequence.clone(),
+                error,
+            });
+            return;
+        }
+        let processed = apply_operation(sequence, spec.operation, &options.rc_options(&spec.name));
+        if let Some(kits) = &kits {
+            kit_checks.extend(KitCheck::new(line, spec, sequence, &processed, kits));
+        }
+        if options.check_balance {
+            balance_cells.extend(BalanceCell::new(None, spec, sequence));
+        }
+        *sequence = processed;
+    };
+    for (&line, barcode) in line_numbers.iter().zip(&mut barcodes) {
+        process(line, &barcode_spec, &mut barcode.barcode);
+        if let Some(barcode2) = &mut barcode.barcode2 {
+            process(line, &barcode2_spec, barcode2);
+        }
+    }
+    // Refuse to write a partially processed list
+    if !cell_errors.is_empty() {
+        return Err(invalid_cells_error(&cell_errors));
+    }
+    std::fs::write(&output_path, write_barcodes(&barcodes))?;
+
+    println!("File processed successfully!");
+    println!("Output saved to: {}", output_path.display());
+
+    if let Some(kits) = &kits {
+        report_kit_checks(&kit_checks, kits);
+    }
+
+    if options.check_balance {
+        report_colour_balance(&balance_cells, options.instrument);
+    }
+
+    if let Some(max_mismatches) = options.check_collisions {
+        let samples: Vec<(Option<String>, IndexedSample)> = barcodes
+            .iter()
+            .map(|barcode| {
+                let indexes = std::iter::once(barcode.barcode.clone()).chain(barcode.barcode2.clone()).collect();
+                (None, IndexedSample::new(barcode.name.clone(), indexes))
+            })
+            .collect();
+        report_collisions(&samples, max_mismatches);
+    }
+
+    Ok(())
+}
+
+/// A sequence cell that could not be reverse complemented.
+#[derive(Debug)]
+struct CellError {
+    /// 1-based row number in the input file
+    row: usize,
+    column: String,
+    value: String,
+    error: SequenceError,
+}
+
+impl std::fmt::Display for CellError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if is_set_name(&self.value) {
+            return write!(
+                f,
+                "row {}, column '{}': unknown 10x index set '{}' (add its table with --tenx-sets)",
+                self.row, self.column, self.value.trim()
+            );
+        }
+        write!(f, "row {}, column '{}': {} in '{}'", self.row, self.column, self.error, self.value)
+    }
+}
+
+/// Prints every invalid cell and returns the error that aborts processing.
+fn invalid_cells_error(cell_errors: &[CellError]) -> Box<dyn std::error::Error> {
+    println!("\nInvalid sequence cells:");
+    for cell_error in cell_errors {
+        println!("  * {}", cell_error);
+    }
+    format!(
+        "{} sequence cell(s) contain non-nucleotide characters; no output file was written",
+        cell_errors.len()
+    )
+    .into()
+}
+
+/// A 10x index set name that was replaced by the set's sequences.
+#[derive(Debug)]
+struct TenxExpansion {
+    /// 1-based row number in the input file
+    row: usize,
+    column: String,
+    set: TenxIndexSet,
+    /// The read a dual-index set has no column for, so it was not written
+    missing: Option<IndexRole>,
+}
+
+/// Replaces a 10x index set name in one of the sequence `columns` (column
+/// index and name) of `fields`. A single-index set's four oligos are joined
+/// with `+` in place of the name; a dual-index set's i7 and i5 go to the
+/// row's i7 and i5 columns. i5 is written as listed for the forward strand,
+/// and like every other cell is then processed with its column's operation,
+/// so it follows --instrument or --workflow.
+fn expand_tenx_set(
+    row: usize,
+    fields: &mut Vec<String>,
+    columns: &[(usize, &str)],
+    sets: &TenxIndexSets,
+) -> Option<TenxExpansion> {
+    let (set_col, column, set) = columns
+        .iter()
+        .find_map(|(idx, name)| Some((*idx, *name, sets.get(fields.get(*idx)?)?)))?;
+    let role_column = |role: IndexRole| {
+        columns
+            .iter()
+            .find(|(_, name)| IndexRole::from_column(name) == Some(role))
+            .map(|(idx, _)| *idx)
+    };
+    let mut write = |idx: usize, value: &str| {
+        if fields.len() <= idx {
+            fields.resize(idx + 1, String::new());
+        }
+        fields[idx] = value.to_string();
+    };
+    let mut missing = None;
+    match set {
+        TenxIndexSet::Single { oligos, .. } => write(set_col, &oligos.join("+")),
+        TenxIndexSet::Dual { i7, i5, .. } => {
+            let i7_col = match IndexRole::from_column(column) {
+                Some(IndexRole::I5) => role_column(IndexRole::I7),
+                _ => Some(set_col),
+            };
+            let i5_col = role_column(IndexRole::I5).filter(|idx| Some(*idx) != i7_col);
+            match i7_col {
+                Some(idx) => write(idx, i7),
+                None => missing = Some(IndexRole::I7),
+            }
+            match i5_col {
+                Some(idx) => write(idx, i5),
+                None => missing = Some(IndexRole::I5),
+            }
+        }
+    }
+    Some(TenxExpansion {
+        row,
+        column: column.to_string(),
+        set: set.clone(),
+        missing,
+    })
+}
+
+/// `fields` with the field at `index` set to `value`.
+fn with_field(fields: &[String], index: usize, value: &str) -> Vec<String> {
+    let mut fields = fields.to_vec();
+    if fields.len() <= index {
+        fields.resize(index + 1, String::new());
+    }
+    fields[index] = value.to_string();
+    fields
+}
+
+/// How many sets of each type are known, for reports of unknown set names.
+fn print_tenx_coverage(sets: &TenxIndexSets) {
+    let coverage: Vec<String> = sets
+        .coverage()
+        .iter()
+        .map(|(kind, count)| format!("{} {} of {}", kind, count, SETS_PER_TYPE))
+        .collect();
+    println!("  Known 10x index sets: {}.", coverage.join(", "));
+}
+
+fn report_tenx_expansions(expansions: &[TenxExpansion]) {
+    if expansions.is_empty() {
+        return;
+    }
+    println!("\n10x index sets expanded (i5 as listed for the forward strand, then processed with its column):");
+    for expansion in expansions {
+        let sequences = match &expansion.set {
+            TenxIndexSet::Single { oligos, .. } => format!("single index, oligos {}", oligos.join(", ")),
+            TenxIndexSet::Dual { i7, i5, .. } => format!("dual index, i7 {}, i5 {}", i7, i5),
+        };
+        println!("  * row {}, '{}': {} ({})", expansion.row, expansion.column, expansion.set.name(), sequences);
+    }
+    let missing: Vec<&TenxExpansion> = expansions.iter().filter(|e| e.missing.is_some()).collect();
+    if !missing.is_empty() {
+        println!("\n⚠️  Warning: some dual-index sets have no column for one of their reads:");
+        for expansion in missing {
+            if let Some(read) = expansion.missing {
+                println!("  * row {}: {} has no {} column; its {} was not written", expansion.row, expansion.set.name(), read, read);
+            }
+        }
+    }
+}
+
+/// Applies the column's operation to the sequence parts of a cell. Empty cells
+/// and passthrough columns are left as they are, without validation, but a
+/// 10x set name is an error in any column.
+fn process_sequence_cell(
+    value: &str,
+    spec: &ColumnSpec,
+    rc_options: &RcOptions,
+    adapter_kits: Option<&KitDatabase>,
+) -> Result<Option<ParsedCell>, SequenceError> {
+    let resizes = spec.lengths.iter().any(Option::is_some);
+    if value.trim().is_empty() {
+        // A sample without this index, padded like the others
+        return match spec.lengths.as_slice() {
+            [Some(length)] if spec.fill_empty => {
+                let padding = index_padding(spec, 0, None, adapter_kits);
+                Ok(Some(CellParser::default().parse(&resize_index("", *length, padding))?))
+            }
+            _ => Ok(None),
+        };
+    }
+    if is_set_name(value) {
+        // Known sets were expanded before this, so the name is in none of the loaded tables
+        return Err(unknown_set_error(value));
+    }
+    let passthrough = |operations: &[Operation]| operations.iter().all(|&op| op == Operation::Passthrough);
+    if spec.operation == Operation::Passthrough && passthrough(&spec.part_operations(2)) && !resizes {
+        return Ok(None);
+    }
+    let input = spec.parser.parse(value)?;
+    let operations = spec.part_operations(input.sequences().count());
+    let cell = input.apply_each(&operations, rc_options);
+    if !resizes {
+        return Ok(Some(cell));
+    }
+    // Lengths change after the operation, at the end of the sequence as read
+    let entered: Vec<&IndexSequence> = input.sequences().collect();
+    let cell = cell.map_sequences(|part, index| match spec.lengths.get(part).copied().flatten() {
+        Some(length) if length != index.len() => {
+            let padding = index_padding(spec, part, entered.get(part).copied(), adapter_kits);
+            IndexSequence::with_orientation(resize_index(index.as_str(), length, padding), index.orientation())
+                .expect("resized index keeps valid bases")
+        }
+        _ => index.clone(),
+    });
+    if passthrough(&operations) && cell.to_string() == value {
+        return Ok(None);
+    }
+    Ok(Some(cell))
+}
+
+/// The error for a 10x set name that no table expanded. [`CellError`] reports
+/// it as an unknown set.
+fn unknown_set_error(value: &str) -> SequenceError {
+    CellParser::default().parse(value).err().unwrap_or(SequenceError::Empty)
+}
+
+/// Which index read part `part` of a column's cells holds: i7 then i5 in
+/// combined `i7+i5` cells, otherwise what the column name says (so every
+/// oligo of a pooled 10x i7 set is i7).
+fn index_role(spec: &ColumnSpec, part: usize) -> Option<IndexRole> {
+    if spec.lengths.len() == 2 {
+        [IndexRole::I7, IndexRole::I5].get(part).copied()
+    } else {
+        IndexRole::from_column(&spec.name)
+    }
+}
+
+/// Bases to pad an index with: the adapter bases its read continues into
+/// with --normalize-length pad-adapter, when known, and none (so `N`) otherwise.
+fn index_padding(
+    spec: &ColumnSpec,
+    part: usize,
+    entered: Option<&IndexSequence>,
+    adapter_kits: Option<&KitDatabase>,
+) -> &'static str {
+    let (Some(kits), Some(role)) = (adapter_kits, index_role(spec, part)) else {
+        return "";
+    };
+    // The output is what the sequencer reads, so a flipped i5 is read on the reverse complement workflow
+    let operation = spec.part_operations(spec.lengths.len()).get(part).copied().unwrap_or(spec.operation);
+    let workflow = if operation == Operation::ReverseComplement {
+        Workflow::ReverseComplement
+    } else {
+        Workflow::Forward
+    };
+    let adapter = entered.and_then(|index| kits.lookup(index.as_str()).iter().find_map(|m| m.index.adapter));
+    read_through(adapter, role, workflow).unwrap_or("")
+}
+
+/// The sequence columns used for the rows of each lane. They differ only in
+/// the index lengths --normalize-length planned for the lane.
+struct LaneColumns {
+    /// Columns for rows without a lane, planned over the whole file
+    all: Vec<ColumnSpec>,
+    lanes: Vec<(String, Vec<ColumnSpec>)>,
+}
+
+impl LaneColumns {
+    fn get(&self, lane: Option<&str>) -> &[ColumnSpec] {
+        lane.and_then(|lane| self.lanes.iter().find(|(name, _)| name == lane))
+            .map_or(&self.all, |(_, columns)| columns)
+    }
+}
+
+/// Plans the target lengths of every column whose indexes have mixed lengths,
+/// per lane, and prints what will change. `row_lanes` and `column_values` give
+/// each row's lane and a column's values in row order. Returns the planned
+/// columns and whether any column changes.
+fn plan_index_lengths(
+    mode: Option<LengthMode>,
+    sequence_columns: Vec<ColumnSpec>,
+    row_lanes: &[Option<String>],
+    column_values: impl Fn(usize) -> Vec<String>,
+) -> (LaneColumns, bool) {
+    let lanes = distinct_lanes(row_lanes);
+    let Some(mode) = mode else {
+        return (LaneColumns { all: sequence_columns, lanes: Vec::new() }, false);
+    };
+    println!("\nIndex lengths (--normalize-length {}):", mode);
+    let values: Vec<Vec<String>> = sequence_columns.iter().map(|spec| column_values(spec.index)).collect();
+    let mut all = sequence_columns.clone();
+    let prefix = if lanes.is_empty() { String::new() } else { format!("{}, ", lane_label(None)) };
+    let (mut changed, lines) = plan_lengths(mode, &mut all, &values, |_| true, &prefix);
+    if lanes.is_empty() || row_lanes.iter().any(Option::is_none) {
+        for line in &lines {
+            println!("{}", line);
+        }
+    }
+    let mut lane_columns = Vec::new();
+    for lane in lanes {
+        // Rows without a lane run in every lane, so they count for every lane's plan
+        let mut columns = sequence_columns.clone();
+        let prefix = format!("{}, ", lane_label(Some(lane)));
+        let (lane_changed, lines) =
+            plan_lengths(mode, &mut columns, &values, |row| row_lanes.get(row).is_some_and(|l| in_lane(l, lane)), &prefix);
+        for line in &lines {
+            println!("{}", line);
+        }
+        changed |= lane_changed;
+        lane_columns.push((lane.to_string(), columns));
+    }
+    (LaneColumns { all, lanes: lane_columns }, changed)
+}
+
+/// Sets the target lengths of `sequence_columns` from the rows `in_group`
+/// accepts. Returns whether any column changes, and a report line per column
+/// prefixed with `prefix`.
+fn plan_lengths(
+    mode: LengthMode,
+    sequence_columns: &mut [ColumnSpec],
+    values: &[Vec<String>],
+    in_group: impl Fn(usize) -> bool,
+    prefix: &str,
+) -> (bool, Vec<String>) {
+    let mut changed = false;
+    let mut lines: Vec<String> = Vec::new();
+    for (n, spec) in sequence_columns.iter_mut().enumerate() {
+        // Sequence lengths of each cell, with no entries for empty cells
+        let cells: Vec<Vec<usize>> = values[n]
+            .iter()
+            .enumerate()
+            .filter(|(row, _)| in_group(*row))
+            .map(|(_, value)| match spec.parser.parse(value) {
+                Ok(cell) if !value.trim().is_empty() => cell.sequences().map(IndexSequence::len).collect(),
+                _ => Vec::new(),
+            })
+            .collect();
+        let parts = cells.iter().map(Vec::len).max().unwrap_or(0);
+        spec.lengths = vec![None; parts];
+        for part in 0..parts {
+            let lengths: Vec<usize> = cells.iter().map(|cell| cell.get(part).copied().unwrap_or(0)).collect();
+            let label = if parts > 1 { format!("{}'{}' index {}", prefix, spec.name, part + 1) } else { format!("{}'{}'", prefix, spec.name) };
+            let Some((_, longest)) = mixed_lengths(lengths.iter().copied()) else {
+                lines.push(format!("  * {}: all {} bp", label, lengths.first().copied().unwrap_or(0)));
+                continue;
+            };
+            let shortest = lengths.iter().copied().filter(|&len| len > 0).min().unwrap_or(longest);
+            let missing = lengths.iter().filter(|&&len| len == 0).count();
+            let mut found: Vec<usize> = lengths.iter().copied().filter(|&len| len > 0).collect();
+            found.sort_unstable();
+            found.dedup();
+            let found: Vec<String> = found.iter().map(|len| format!("{} bp", len)).collect();
+            let target = mode.target(shortest, longest);
+            lines.push(format!("  * {}: {} -> {} bp", label, found.join(", "), target));
+            if missing > 0 {
+                match mode {
+                    LengthMode::Truncate => lines.push(format!("    {} sample(s) without this index are left empty; pad to fill them in", missing)),
+                    LengthMode::Pad | LengthMode::PadAdapter => lines.push(format!("    {} sample(s) without this index are filled in", missing)),
+                }
+            }
+            if mode == LengthMode::Truncate && spec.part_operations(parts).get(part) == Some(&Operation::ReverseComplement) {
+                lines.push("    Truncated after reverse complementing: bases are removed from the start of the sequences as entered".to_string());
+            }
+            let fill_empty = missing > 0 && mode != LengthMode::Truncate;
+            if shortest != longest || fill_empty {
+                spec.lengths[part] = Some(target);
+                spec.fill_empty |= fill_empty;
+                changed = true;
+            }
+        }
+    }
+    (changed, lines)
+}
+
+/// Name used for a row in the collision report: its Id, or its row number.
+fn sample_name(id: Option<&str>, row: usize) -> String {
+    match id {
+        Some(id) if !id.trim().is_empty() => id.trim().to_string(),
+        _ => format!("row {}", row),
+    }
+}
+
+/// The sequence parts of each sequence column's output value, labels and
+/// separators dropped, so that only bases are compared.
+fn index_sequences(values: &[String], sequence_columns: &[ColumnSpec]) -> Vec<String> {
+    values
+        .iter()
+        .zip(sequence_columns)
+        .map(|(value, spec)| match spec.parser.parse(value) {
+            Ok(cell) => cell.sequences().map(|index| index.as_str()).collect(),
+            Err(_) => value.trim().to_string(),
+        })
+        .collect()
+}
+
+/// Prints every pair of samples whose indexes are at most `max_mismatches` apart.
+/// Samples are only compared within a lane; samples without a lane are compared with every lane.
+fn report_collisions(samples: &[(Option<String>, IndexedSample)], max_mismatches: usize) {
+    let mut colliding = 0;
+    for (lane, collisions) in lane_collisions(samples, max_mismatches) {
+        let group = lane.map(|lane| format!(" in {}", lane_label(Some(&lane)))).unwrap_or_default();
+        if collisions.is_empty() {
+            println!("\nNo index collisions{} within {} mismatch(es)", group, max_mismatches);
+            continue;
+        }
+        colliding += collisions.len();
+        print_collisions(&group, &collisions, max_mismatches);
+    }
+    if colliding > 0 {
+        println!("\n⚠️  Warning: {} colliding sample pair(s) may not demultiplex.", colliding);
+    }
+}
+
+/// Collisions within each lane, with rows without a lane counted in every
+/// lane. Without any lanes there is a single `None` group.
+fn lane_collisions(samples: &[(Option<String>, IndexedSample)], max_mismatches: usize) -> Vec<(Option<String>, Vec<Collision>)> {
+    let lanes = distinct_lanes(samples.iter().map(|(lane, _)| lane));
+    if lanes.is_empty() {
+        let samples: Vec<IndexedSample> = samples.iter().map(|(_, sample)| sample.clone()).collect();
+        return vec![(None, find_collisions(&samples, max_mismatches))];
+    }
+    lanes
+        .iter()
+        .map(|lane| {
+            let in_lane: Vec<IndexedSample> = samples
+                .iter()
+                .filter(|(l, _)| in_lane(l, lane))
+                .map(|(_, sample)| sample.clone())
+                .collect();
+            (Some(lane.to_string()), find_collisions(&in_lane, max_mismatches))
+        })
+        .collect()
+}
+
+fn print_collisions(group: &str, collisions: &[Collision], max_mismatches: usize) {
+    println!("\nIndex collisions{} (at most {} mismatch(es)):", group, max_mismatches);
+    for collision in collisions {
+        let after_rc = if collision.collides_after_rc {
+            "still collides"
+        } else {
+            "no longer collides"
+        };
+        println!(
+            "  * '{}' and '{}': distance {}; {} with one of them reverse complemented (distance {})",
+            collision.first, collision.second, collision.distance, after_rc, collision.rc_distance
+        );
+    }
+}
+
+/// A row's lane, from the Lane column's cell; `None` when the cell is missing or empty.
+fn lane_of(cell: Option<&str>) -> Option<String> {
+    cell.map(str::trim).filter(|lane| !lane.is_empty()).map(str::to_string)
+}
+
+/// The distinct lanes, in order of first appearance.
+fn distinct_lanes<'a>(lanes: impl IntoIterator<Item = &'a Option<String>>) -> Vec<&'a str> {
+    let mut distinct: Vec<&str> = Vec::new();
+    for lane in lanes.into_iter().flatten() {
+        if !distinct.contains(&lane.as_str()) {
+            distinct.push(lane);
+        }
+    }
+    distinct
+}
+
+fn lane_label(lane: Option<&str>) -> String {
+    match lane {
+        Some(lane) => format!("lane {}", lane),
+        None => "no lane".to_string(),
+    }
+}
+
+/// Prints the number of rows in each lane, when the file has lanes.
+fn print_lane_rows(row_lanes: &[Option<String>]) {
+    let lanes = distinct_lanes(row_lanes);
+    if lanes.is_empty() {
+        return;
+    }
+    println!("Rows per lane:");
+    for lane in lanes {
+        let rows = row_lanes.iter().filter(|l| l.as_deref() == Some(lane)).count();
+        println!("  * {}: {}", lane_label(Some(lane)), rows);
+    }
+    let unlaned = row_lanes.iter().filter(|l| l.is_none()).count();
+    if unlaned > 0 {
+        println!("  * {} (written to every lane): {}", lane_label(None), unlaned);
+    }
+}
+
+/// The output file for one lane, e.g. `batch_RC_L1.csv` next to `batch_RC.csv`.
+fn lane_output_path(output_path: &Path, lane: &str) -> PathBuf {
+    let lane: String = lane.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+    let stem = output_path.file_stem().unwrap_or_default().to_string_lossy();
+    match output_path.extension() {
+        Some(extension) => output_path.with_file_name(format!("{}_L{}.{}", stem, lane, extension.to_string_lossy())),
+        None => output_path.with_file_name(format!("{}_L{}", stem, lane)),
+    }
+}
+
+/// A processed row as an --export sample. i7 comes from the i7 column (or
+/// the first other sequence column), i5 from the i5 column with its column's
+/// operation undone, which gives i5 for the forward strand as entered.
+fn export_row(
+    sample: String,
+    lane: Option<String>,
+    columns: &[ColumnSpec],
+    input: impl Fn(usize) -> Option<String>,
+    output: impl Fn(usize) -> Option<String>,
+) -> ExportRow {
+    let role = |spec: &&ColumnSpec| IndexRole::from_column(&spec.name);
+    let i5_spec = columns.iter().find(|spec| role(spec) == Some(IndexRole::I5));
+    let i7_spec = columns
+        .iter()
+        .find(|spec| role(spec) == Some(IndexRole::I7))
+        .or_else(|| columns.iter().find(|spec| role(spec) != Some(IndexRole::I5)));
+    let sequences = |spec: &ColumnSpec| -> Vec<String> {
+        let value = output(spec.index).unwrap_or_default();
+        let sequences = match spec.parser.parse(&value) {
+            Ok(cell) => cell.sequences().map(|index| index.as_str().to_ascii_uppercase()).collect(),
+            Err(_) => vec![value.trim().to_string()],
+        };
+        sequences.into_iter().filter(|sequence: &String| !sequence.is_empty()).collect()
+    };
+    // Every operation undoes itself
+    let i5 = i5_spec
+        .map(|spec| apply_operation(&sequences(spec).concat(), spec.operation, &RcOptions::default()))
+        .unwrap_or_default();
+    let tenx_set = columns
+        .iter()
+        .filter_map(|spec| input(spec.index))
+        .find(|value| is_set_name(value))
+        .map(|name| name.trim().to_ascii_uppercase());
+    ExportRow {
+        sample,
+        lane,
+        i7: i7_spec.map(sequences).unwrap_or_default(),
+        i5,
+        tenx_set,
+    }
+}
+
+/// Writes the --export sample sheet next to the input file, e.g. batch_bcl2fastq.csv.
+fn write_export(
+    file_path: &Path,
+    target: ExportTarget,
+    rows: &[ExportRow],
+    options: &ProcessOptions,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let workflow = match target {
+        // A run manifest is for the AVITI whatever --instrument the batch was
+        // processed for, so only --workflow overrides its orientation table
+        ExportTarget::Aviti => options.workflow,
+        _ => options.target_workflow().map(|(workflow, _)| workflow),
+    };
+    let export = export_sample_sheet(target, rows, workflow)?;
+    let path = file_path.with_file_name(format!(
+        "{}_{}.{}",
+        file_path.file_stem().unwrap().to_string_lossy(),
+        target.id(),
+        target.extension()
+    ));
+    std::fs::write(&path, &export.text)?;
+    println!("{} sample sheet saved to: {}", target, path.display());
+    if !export.warnings.is_empty() {
+        println!("\n⚠️  Warning: not everything fits the {} sample sheet:", target);
+        for warning in &export.warnings {
+            println!("  * {}", warning);
+        }
+    }
+    Ok(())
+}
+
+/// Lists the per-lane output files, or warns when --split-lanes found no lanes.
+fn report_lane_files(split_lanes: bool, lane_files: &[PathBuf]) {
+    if !split_lanes {
+        return;
+    }
+    if lane_files.is_empty() {
+        println!("\n⚠️  Warning: --split-lanes found no Lane values; no per-lane files were written.");
+        return;
+    }
+    println!("Lane files:");
+    for path in lane_files {
+        println!("  * {}", path.display());
+    }
+}
+
+/// Rows of `lane`: the lane's own rows and rows without a lane.
+fn in_lane(row_lane: &Option<String>, lane: &str) -> bool {
+    row_lane.as_deref().is_none_or(|l| l == lane)
+}
+
+/// How a sequence cell compares with the known index kits, before and after processing.
+#[derive(Debug)]
+struct KitCheck {
+    /// 1-based row number in the input file
+    row: usize,
+    lane: Option<String>,
+    column: String,
+    before: String,
+    after: String,
+    before_orientation: String,
+    after_orientation: String,
+    /// Kit indexes the cell's sequences were found as, e.g. "TruSeq CD Indexes D501 i5"
+    matches: Vec<String>,
+}
+
+impl KitCheck {
+    /// Checks a non-empty cell whose value was `before` and is now `after`.
+    fn new(row: usize, spec: &ColumnSpec, before: &str, after: &str, kits: &KitDatabase) -> Option<KitCheck> {
+        let before_cell = spec.parser.parse(before).ok()?;
+        let after_cell = spec.parser.parse(after).ok()?;
+        let orientations = |cell: &ParsedCell| -> String {
+            cell.sequences()
+                .map(|index| match kits.classify(index.as_str()) {
+                    Some(orientation) => orientation.to_string(),
+                    None => "unknown".to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join("+")
+        };
+        let mut matches: Vec<String> = Vec::new();
+        for index in before_cell.sequences() {
+            for found in kits.lookup(index.as_str()) {
+                let name = found.to_string();
+                if !matches.contains(&name) {
+                    matches.push(name);
+                }
+            }
+        }
+        Some(KitCheck {
+            row,
+            lane: None,
+            column: spec.name.clone(),
+            before: before.to_string(),
+            after: after.to_string(),
+            before_orientation: orientations(&before_cell),
+            after_orientation: orientations(&after_cell),
+            matches,
+        })
+    }
+
+    fn is_unknown(&self) -> bool {
+        self.after_orientation.split('+').any(|o| o == "unknown")
+    }
+}
+
+impl std::fmt::Display for KitCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "row {}, column '{}': {} {} -> {} {}",
+            self.row, self.column, self.before, self.before_orientation, self.after, self.after_orientation
+        )?;
+        if !self.matches.is_empty() {
+            write!(f, " ({})", self.matches.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Prints the kit check of every sequence cell and warns about unknown sequences.
+fn report_kit_checks(checks: &[KitCheck], kits: &KitDatabase) {
+    println!(
+        "\nIndex kit check ({} kit(s), version {}):",
+        kits.kits().len(),
+        kits.version().unwrap_or("unversioned")
+    );
+    for check in checks {
+        println!("  * {}", check);
+    }
+    let lanes = distinct_lanes(checks.iter().map(|check| &check.lane));
+    if !lanes.is_empty() {
+        println!("Orientation after processing, per lane:");
+        for lane in lanes {
+            let orientations: Vec<&str> = checks
+                .iter()
+                .filter(|check| check.lane.as_deref() == Some(lane))
+                .flat_map(|check| check.after_orientation.split('+'))
+                .collect();
+            let count = |orientation: &str| orientations.iter().filter(|o| **o == orientation).count();
+            println!(
+                "  * {}: {} forward, {} reverse-complemented, {} unknown",
+                lane_label(Some(lane)),
+                count("forward"),
+                count("reverse-complemented"),
+                count("unknown")
+            );
+        }
+    }
+    let unknown = checks.iter().filter(|check| check.is_unknown()).count();
+    if unknown > 0 {
+        println!("\n⚠️  Warning: {} cell(s) contain sequences that match no known index.", unknown);
+        for (kit, listed, size) in kits.partial_kits() {
+            println!(
+                "  {} lists only {} of its {} wells; indexes from the other wells show as unknown until its full table is loaded with --kits.",
+                kit, listed, size
+            );
+        }
+    } else {
+        println!("All {} checked cell(s) match a known index.", checks.len());
+    }
+}
+
+/// An index cell's sequences as read, with its lane, for the colour-balance check.
+#[derive(Debug, Clone)]
+struct BalanceCell {
+    lane: Option<String>,
+    column: String,
+    operation: Operation,
+    sequences: Vec<String>,
+}
+
+impl BalanceCell {
+    /// `None` for empty cells and cells the column's parser rejects.
+    fn new(lane: Option<&str>, spec: &ColumnSpec, value: &str) -> Option<BalanceCell> {
+        let cell = spec.parser.parse(value).ok()?;
+        let sequences: Vec<String> = cell.sequences().map(|index| index.as_str().to_string()).collect();
+        if sequences.is_empty() {
+            return None;
+        }
+        Some(BalanceCell {
+            lane: lane.map(str::trim).filter(|lane| !lane.is_empty()).map(str::to_string),
+            column: spec.name.clone(),
+            operation: spec.operation,
+            sequences,
+        })
+    }
+}
+
+/// The colour balance of one index read in one lane.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct BalanceGroup {
+    /// `None` when the sheet has no lanes.
+    lane: Option<String>,
+    column: String,
+    /// 0-based read within the cell, for cells that combine i7 and i5.
+    read: usize,
+    combined: bool,
+    samples: usize,
+    /// The cells' operation, or `None` when cells of the group differ.
+    operation: Option<Operation>,
+    /// At-risk cycles of the sequences as written, reverse complemented and as output.
+    as_written: Vec<CycleBalance>,
+    reverse_complemented: Vec<CycleBalance>,
+    output: Vec<CycleBalance>,
+}
+
+/// Groups `cells` per lane, column and read, in sheet order, and finds their
+/// at-risk cycles. Cells without a lane are in every lane, as in the collision check.
+fn colour_balance(cells: &[BalanceCell]) -> Vec<BalanceGroup> {
+    let lanes: Vec<Option<&str>> = match distinct_lanes(cells.iter().map(|cell| &cell.lane)) {
+        lanes if lanes.is_empty() => vec![None],
+        lanes => lanes.into_iter().map(Some).collect(),
+    };
+    let mut groups: Vec<BalanceGroup> = Vec::new();
+    for lane in lanes {
+        let in_group = |cell: &&BalanceCell| lane.is_none_or(|lane| in_lane(&cell.lane, lane));
+        // (column, read within the cell) in sheet order
+        let mut keys: Vec<(&str, usize)> = Vec::new();
+        for cell in cells.iter().filter(in_group) {
+            for read in 0..cell.sequences.len() {
+                if !keys.contains(&(cell.column.as_str(), read)) {
+                    keys.push((cell.column.as_str(), read));
+                }
+            }
+        }
+        for (column, read) in keys {
+            let group: Vec<(&str, Operation)> = cells
+                .iter()
+                .filter(in_group)
+                .filter(|cell| cell.column == column)
+                .filter_map(|cell| Some((cell.sequences.get(read)?.as_str(), cell.operation)))
+                .collect();
+            let reversed: Vec<String> = group.iter().map(|(sequence, _)| reverse_complement(sequence)).collect();
+            let output: Vec<String> = group
+                .iter()
+                .map(|(sequence, operation)| apply_operation(sequence, *operation, &RcOptions::default()))
+                .collect();
+            let operation = group.first().map(|(_, operation)| *operation);
+            groups.push(BalanceGroup {
+                lane: lane.map(str::to_string),
+                column: column.to_string(),
+                read,
+                combined: cells.iter().any(|cell| cell.column == column && cell.sequences.len() > 1),
+                samples: group.len(),
+                operation: operation.filter(|first| group.iter().all(|(_, operation)| operation == first)),
+                as_written: at_risk_cycles(group.iter().map(|(sequence, _)| *sequence)),
+                reverse_complemented: at_risk_cycles(reversed.iter().map(String::as_str)),
+                output: at_risk_cycles(output.iter().map(String::as_str)),
+            });
+        }
+    }
+    groups
+}
+
+/// Prints the at-risk cycles of every lane and index read, for the sequences
+/// as written and reverse complemented, marking the orientation that is output.
+fn report_colour_balance(cells: &[BalanceCell], instrument: Option<Instrument>) {
+    println!("\nColour balance (two-colour chemistry):");
+    if let Some(instrument) = instrument
+        && !instrument.is_two_colour()
+    {
+        println!("  Note: {} does not use two-colour chemistry; G is not dark there.", instrument.name());
+    }
+    let describe = |label: &str, output: bool, cycles: &[CycleBalance]| {
+        let marker = if output { " (output)" } else { "" };
+        if cycles.is_empty() {
+            format!("{}{}: balanced", label, marker)
+        } else {
+            let cycles: Vec<String> = cycles
+                .iter()
+                .map(|cycle| format!("cycle {} ({})", cycle.cycle, cycle.problem().unwrap_or_default()))
+                .collect();
+            format!("{}{}: {}", label, marker, cycles.join(", "))
+        }
+    };
+    let groups = colour_balance(cells);
+    for group in &groups {
+        let lane = group.lane.as_ref().map_or_else(|| "all lanes".to_string(), |lane| format!("lane {}", lane));
+        let read = if group.combined { format!(" index {}", group.read + 1) } else { String::new() };
+        println!("  * {}, '{}'{} ({} samples):", lane, group.column, read, group.samples);
+        println!("      {}", describe("as written", group.operation == Some(Operation::Passthrough), &group.as_written));
+        println!(
+            "      {}",
+            describe("reverse complemented", group.operation == Some(Operation::ReverseComplement), &group.reverse_complemented)
+        );
+        match group.operation {
+            Some(Operation::Complement) => println!("      {}", describe("complemented", true, &group.output)),
+            Some(Operation::Reverse) => println!("      {}", describe("reversed", true, &group.output)),
+            None => println!("      {}", describe("mixed operations", true, &group.output)),
+            _ => {}
+        }
+    }
+    let at_risk = groups.iter().filter(|group| !group.output.is_empty()).count();
+    if at_risk > 0 {
+        println!("\n⚠️  Warning: {} index read(s) have cycles with no signal in one channel as output.", at_risk);
+    }
+}
+
+/// Builds the SQL UPDATE statement for a processed sequence column.
+fn sql_update_statement(col_name: &str, rc: &str, id: &str) -> String {
+    // Generate SQL with proper column name escaping
+    if col_name.contains(' ') || col_name.starts_with("Column_") {
+        format!(
+            "UPDATE SampleBatchItems SET [{}] = '{}' WHERE Id = '{}';",
+            col_name, rc, id
+        )
+    } else {
+        format!(
+            "UPDATE SampleBatchItems SET {} = '{}' WHERE Id = '{}';",
+            col_name, rc, id
+        )
+    }
+}
+
+fn run_fastx(args: &FastxArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let options = FastxOptions {
+        tag: args.tag.clone(),
+    };
+    let summary = reverse_complement_fastx(args.input.as_deref(), args.output.as_deref(), &options)?;
+
+    // Sequence data may be going to stdout, so report on stderr
+    let format = match summary.format {
+        Some(FastxFormat::Fastq) => "FASTQ",
+        Some(FastxFormat::Fasta) => "FASTA",
+        None => "empty input",
+    };
+    eprintln!(
+        "Reverse complemented {} records ({} bases, {})",
+        summary.records, summary.bases, format
+    );
+    Ok(())
+}
+
+/// Loads a kit file, or the built-in kits for "builtin".
+fn load_kits(path: &Path) -> Result<KitDatabase, KitError> {
+    if path == Path::new("builtin") {
+        Ok(KitDatabase::builtin())
+    } else {
+        KitDatabase::from_path(path)
+    }
+}
+
+fn print_kits(kits: &KitDatabase) {
+    for kit in kits.kits() {
+        let indexes: Vec<_> = kits.indexes().iter().filter(|index| index.kit == kit).collect();
+        let count = |read: IndexRole| indexes.iter().filter(|index| index.read == read).count();
+        let wells = indexes.iter().any(|index| index.well.is_some());
+        let partial = kits.partial_kits().into_iter().find(|(partial, _, _)| *partial == kit);
+        println!(
+            "  * {}: {} i7, {} i5{}",
+            kit,
+            count(IndexRole::I7),
+            count(IndexRole::I5),
+            match partial {
+                Some((_, listed, size)) => format!(" (plate wells, {} of {} listed)", listed, size),
+                None if wells => " (plate wells)".to_string(),
+                None => String::new(),
+            }
+        );
+    }
+}
+
+fn run_kits(command: &KitsCommand) -> Result<(), Box<dyn std::error::Error>> {
+    match command {
+        KitsCommand::List { files } => {
+            let builtin = KitDatabase::builtin();
+            println!("Built-in kits (version {}):", builtin.version().unwrap_or("unversioned"));
+            print_kits(&builtin);
+            for path in files {
+                let kits = KitDatabase::from_path(path)?;
+                println!(
+                    "\n{} (version {}):",
+                    path.display(),
+                    kits.version().unwrap_or("unversioned")
+                );
+                print_kits(&kits);
+            }
+        }
+        KitsCommand::Validate { files } => {
+            let mut invalid = 0;
+            for path in files {
+                match KitDatabase::from_path(path) {
+                    Ok(kits) => {
+                        let problems = kits.validate();
+                        if problems.is_empty() {
+                            println!("{}: OK ({} indexes)", path.display(), kits.indexes().len());
+                        } else {
+                            invalid += 1;
+                            println!("{}:", path.display());
+                            for problem in &problems {
+                                println!("  * {}", problem);
+                            }
+                        }
+                    }
+                    Err(error) => {
+                        invalid += 1;
+                        println!("{}", error);
+                    }
+                }
+            }
+            if invalid > 0 {
+                return Err(format!("{} kit file(s) are not valid", invalid).into());
+            }
+        }
+        KitsCommand::Diff { old, new } => {
+            let changes = load_kits(old)?.diff(&load_kits(new)?);
+            if changes.is_empty() {
+                println!("No differences");
+            }
+            for change in &changes {
+                println!("{}", change);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A sample's indexes as read from a sheet, for `infer`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct SheetIndexes {
+    sample: String,
+    i7: Option<String>,
+    i5: Option<String>,
+}
+
+/// The column names and rows of a sheet's sample table, whatever the file type.
+struct SheetTable {
+    columns: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+fn sheet_table(file_path: &Path) -> Result<SheetTable, Box<dyn std::error::Error>> {
+    match detect_file_type(file_path)? {
+        FileType::SampleSheet => {
+            let sample_sheet = SampleSheet::parse(&std::fs::read_to_string(file_path)?);
+            let data_name = sample_sheet.version().data_section();
+            let table = sample_sheet
+                .section(data_name)
+                .and_then(|data| data.table())
+                .ok_or_else(|| format!("Sample sheet has no [{}] table", data_name))?;
+            Ok(SheetTable {
+                columns: table.columns,
+                rows: table.rows.into_iter().map(|row| row.fields).collect(),
+            })
+        }
+        FileType::MgiBarcodes => {
+            let barcodes = parse_barcodes(&std::fs::read_to_string(file_path)?)
+                .map_err(|e| format!("{}: {}", file_path.display(), e))?;
+            let columns = ["Sample_ID", "Barcode", "Barcode2"].map(str::to_string).to_vec();
+            let rows = barcodes
+                .into_iter()
+                .map(|barcode| vec![barcode.name, barcode.barcode, barcode.barcode2.unwrap_or_default()])
+                .collect();
+            Ok(SheetTable { columns, rows })
+        }
+        FileType::Csv => {
+            let mut reader = ReaderBuilder::new().flexible(true).from_path(file_path)?;
+            let columns = reader.headers()?.iter().map(|c| c.trim().to_string()).collect();
+            let mut rows = Vec::new();
+            for record in reader.records() {
+                rows.push(record?.iter().map(str::to_string).collect());
+            }
+            Ok(SheetTable { columns, rows })
+        }
+        FileType::Excel => {
+            let mut workbook: Xlsx<_> = calamine::open_workbook(file_path)?;
+            let range = workbook
+                .worksheet_range_at(0)
+                .ok_or("The workbook has no sheets")??;
+            let rows: Vec<Vec<String>> = range
+                .rows()
+                .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+                .collect();
+            // The sample table starts at the "Sample ID" row, or at the top
+            let header_idx = rows
+                .iter()
+                .position(|row| row.first().is_some_and(|cell| cell.trim() == "Sample ID"))
+                .unwrap_or(0);
+            let mut rows = rows.into_iter().skip(header_idx);
+            let columns = rows.next().unwrap_or_default().iter().map(|c| c.trim().to_string()).collect();
+            Ok(SheetTable {
+                columns,
+                rows: rows.collect(),
+            })
+        }
+    }
+}
+
+/// Every sample's i7 and i5, from the sheet's index columns. A single
+/// combined column such as `IndexNtSequence` with `i7-i5` cells is split.
+fn read_sheet_indexes(file_path: &Path) -> Result<Vec<SheetIndexes>, Box<dyn std::error::Error>> {
+    let SheetTable { columns, rows } = sheet_table(file_path)?;
+    let find = |names: &[&str]| columns.iter().position(|c| names.iter().any(|name| c.eq_ignore_ascii_case(name)));
+    let sample_col = find(&["Sample_ID", "Sample ID", "SampleName", "Id", "Sample"]);
+    let role_col = |role: IndexRole| columns.iter().position(|c| IndexRole::from_column(c) == Some(role));
+    let (i7_col, i5_col) = (role_col(IndexRole::I7), role_col(IndexRole::I5));
+    if i7_col.is_none() && i5_col.is_none() {
+        return Err(format!("No index columns found in {}", file_path.display()).into());
+    }
+
+    // Parsers are detected per column, so labels such as "D701-ATTACTCG" are dropped
+    let parser = |col: Option<usize>| {
+        col.map(|idx| CellParser::detect(rows.iter().take(10).filter_map(|row| row.get(idx).map(String::as_str))).unwrap_or_default())
+    };
+    let (i7_parser, i5_parser) = (parser(i7_col), parser(i5_col));
+    let sequences = |row: &[String], col: Option<usize>, parser: &Option<CellParser>| -> Vec<String> {
+        match (col.and_then(|idx| row.get(idx)), parser) {
+            (Some(value), Some(parser)) if !value.trim().is_empty() => match parser.parse(value) {
+                Ok(cell) => cell.sequences().map(|index| index.as_str().to_string()).collect(),
+                Err(_) => vec![value.trim().to_string()],
+            },
+            _ => Vec::new(),
+        }
+    };
+
+    let mut samples = Vec::new();
+    for (n, row) in rows.iter().enumerate() {
+        let mut i7 = sequences(row, i7_col, &i7_parser).into_iter();
+        let i7_sequence = i7.next();
+        let i5_sequence = sequences(row, i5_col, &i5_parser).into_iter().next().or_else(|| i7.next());
+        if i7_sequence.is_none() && i5_sequence.is_none() {
+            continue;
+        }
+        samples.push(SheetIndexes {
+            sample: sample_name(sample_col.and_then(|idx| row.get(idx)).map(String::as_str), n + 1),
+            i7: i7_sequence,
+            i5: i5_sequence,
+        });
+    }
+    Ok(samples)
+}
+
+/// Which index read a FASTQ file holds, from the bcl2fastq / BCL Convert
+/// file name, e.g. `Undetermined_S0_L001_I2_001.fastq.gz`.
+fn index_read_kind(path: &Path) -> Option<IndexRole> {
+    let name = path.file_name()?.to_string_lossy().to_ascii_uppercase();
+    if name.contains("_I1_") || name.contains("_I1.") {
+        Some(IndexRole::I7)
+    } else if name.contains("_I2_") || name.contains("_I2.") {
+        Some(IndexRole::I5)
+    } else {
+        None
+    }
+}
+
+/// What an I1 or I2 file is paired on: its path with the read number blanked
+/// out, so the I1 and I2 files of one lane and chunk (`_L002_I1_001`,
+/// `_L002_I2_001`) share it.
+fn index_pair_key(path: &Path) -> PathBuf {
+    let name = path.file_name().map(|name| name.to_string_lossy().to_ascii_uppercase()).unwrap_or_default();
+    let key = ["_I1_", "_I1.", "_I2_", "_I2."]
+        .into_iter()
+        .find(|marker| name.contains(marker))
+        .map_or(name.clone(), |marker| name.replacen(marker, &marker.replace(['1', '2'], "#"), 1));
+    path.with_file_name(key)
+}
+
+/// Counts the barcodes of the FASTQ files: I1/I2 files as index reads,
+/// paired by lane and chunk, others from their read headers.
+fn count_barcodes(files: &[PathBuf], max_reads: usize) -> Result<BarcodeCounts, Box<dyn std::error::Error>> {
+    let mut counts = BarcodeCounts::new();
+    // (pair key, I1 file, I2 file), in the order the files were given
+    let mut pairs: Vec<(PathBuf, Option<&PathBuf>, Option<&PathBuf>)> = Vec::new();
+    for path in files {
+        let Some(role) = index_read_kind(path) else {
+            let reader = FastxReader::new(open_input(Some(path))?);
+            let read = counts.add_headers(reader, max_reads)?;
+            println!("Read {} record headers from {}", read, path.display());
+            continue;
+        };
+        let key = index_pair_key(path);
+        let n = match pairs.iter().position(|(pair, _, _)| *pair == key) {
+            Some(n) => n,
+            None => {
+                pairs.push((key, None, None));
+                pairs.len() - 1
+            }
+        };
+        let slot = if role == IndexRole::I7 { &mut pairs[n].1 } else { &mut pairs[n].2 };
+        if let Some(other) = slot.replace(path) {
+            return Err(format!("{} and {} are the same index read of the same lane and chunk", other.display(), path.display()).into());
+        }
+    }
+    let open = |path: Option<&PathBuf>| path.map(|p| open_input(Some(p)).map(FastxReader::new)).transpose();
+    let paired = pairs.iter().any(|(_, i1, i2)| i1.is_some() && i2.is_some());
+    for (_, i1, i2) in &pairs {
+        let read = counts.add_index_reads(open(*i1)?, open(*i2)?, max_reads)?;
+        let names: Vec<String> = [i1, i2].into_iter().flatten().map(|path| path.display().to_string()).collect();
+        println!("Read {} index reads from {}", read, names.join(" and "));
+        if paired && (i1.is_none() || i2.is_none()) {
+            println!("\n⚠️  Warning: {} has no matching {} file; its reads are counted by one index only", names.join(""), if i1.is_none() { "I1" } else { "I2" });
+        }
+    }
+    Ok(counts)
+}
+
+fn run_infer(args: &InferArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let plate_options = ProcessOptions {
+        plate_map: args.plate_map.clone(),
+        plate_kit: args.plate_kit.clone(),
+        kit_files: args.kit_files.clone(),
+        ..ProcessOptions::default()
+    };
+    let map = plate_options.plate_map()?;
+    let samples = match read_sheet_indexes(&args.sheet) {
+        Ok(samples) => samples,
+        // A sheet with only wells can still be checked against the plate layout
+        Err(_) if map.is_some() => Vec::new(),
+        Err(e) => return Err(e),
+    };
+    println!("Read {} samples from {}", samples.len(), args.sheet.display());
+    let counts = count_barcodes(&args.fastq, args.max_reads)?;
+    if counts.total() == 0 {
+        return Err("No index sequences found in the read headers or index reads".into());
+    }
+
+    println!("\nTop {} barcodes of {} reads:", args.top, counts.total());
+    for (i7, i5, count) in counts.top(args.top) {
+        let barcode = match (i7.is_empty(), i5.is_empty()) {
+            (false, false) => format!("{}+{}", i7, i5),
+            (true, _) => i5.to_string(),
+            (_, true) => i7.to_string(),
+        };
+        println!("  * {}: {} reads", barcode, count);
+    }
+
+    if let Some(map) = &map {
+        report_plate_layout(args, map, &counts)?;
+    }
+    if samples.is_empty() {
+        return Ok(());
+    }
+
+    println!("\ni5 orientation per sample:");
+    let (mut forward, mut reversed) = (0, 0);
+    for sample in &samples {
+        let Some(i5) = &sample.i5 else {
+            println!("  * {}: no i5", sample.sample);
+            continue;
+        };
+        let evidence = counts.i5_evidence(sample.i7.as_deref(), i5);
+        let verdict = match evidence.verdict() {
+            Some(Orientation::Forward) => {
+                forward += 1;
+                "as written"
+            }
+            Some(Orientation::ReverseComplement) => {
+                reversed += 1;
+                "reverse complement"
+            }
+            None => "undecided",
+        };
+        println!(
+            "  * {}: {} as written {} reads, reverse complemented {} reads -> {}",
+            sample.sample, i5, evidence.as_written, evidence.reverse_complement, verdict
+        );
+    }
+
+    println!();
+    if reversed > forward {
+        println!("The reads match the reverse complement of i5 for {} of {} samples.", reversed, samples.len());
+        if args.write {
+            let options = ProcessOptions {
+                workflow: Some(Workflow::ReverseComplement),
+                ..ProcessOptions::default()
+            };
+            process_file(&args.sheet, &options)?;
+        } else {
+            println!("Run with --write (or pass --workflow reverse-complement) to write the corrected _RC file.");
+        }
+    } else if forward > 0 {
+        println!("The reads match i5 as written for {} of {} samples; no _RC file is needed.", forward, samples.len());
+    } else {
+        println!("⚠️  Warning: no sample's i5 was found in the reads in either orientation.");
+    }
+    Ok(())
+}
+
+/// The `Plate` (optional) and `Well` columns of a sheet with wells.
+fn well_columns(columns: &[String]) -> Option<(Option<usize>, usize)> {
+    let find = |names: &[&str]| columns.iter().position(|c| names.iter().any(|name| c.trim().eq_ignore_ascii_case(name)));
+    let well_col = find(&["Well", "Well Position"])?;
+    Some((find(&["Plate", "Plate ID", "Plate Name"]), well_col))
+}
+
+/// The i7 and i5 columns to fill from a plate map; `Index` and `Index 2` are
+/// added when the sheet has none.
+fn index_columns(columns: &mut Vec<String>) -> (usize, usize) {
+    let mut role_col = |role: IndexRole, name: &str| match columns.iter().position(|c| IndexRole::from_column(c) == Some(role)) {
+        Some(idx) => idx,
+        None => {
+            columns.push(name.to_string());
+            columns.len() - 1
+        }
+    };
+    (role_col(IndexRole::I7, "Index"), role_col(IndexRole::I5, "Index 2"))
+}
+
+/// Checks the sheet's wells against `map` under every candidate plate layout
+/// and reports the layout the reads fit best. With --write, a likely layout
+/// error is corrected in `{stem}_layout_fixed.csv`.
+fn report_plate_layout(args: &InferArgs, map: &PlateMap, counts: &BarcodeCounts) -> Result<(), Box<dyn std::error::Error>> {
+    let SheetTable { columns, rows } = sheet_table(&args.sheet)?;
+    let (plate_col, well_col) = well_columns(&columns).ok_or("The sheet has no Well column to check the plate layout")?;
+    fn cell(row: &[String], col: Option<usize>) -> Option<&str> {
+        col.and_then(|idx| row.get(idx)).map(|value| value.trim()).filter(|value| !value.is_empty())
+    }
+    let wells: Vec<(Option<&str>, &str)> = rows
+        .iter()
+        .filter_map(|row| Some((cell(row, plate_col), cell(row, Some(well_col))?)))
+        .collect();
+    // The i5 of the reads can be either way round, depending on the workflow
+    let evidence = |entry: &PlateWell| {
+        let i7 = Some(entry.i7.as_str()).filter(|i7| !i7.is_empty());
+        if entry.i5.is_empty() {
+            counts.count(i7, "")
+        } else {
+            let evidence = counts.i5_evidence(i7, &entry.i5);
+            evidence.as_written + evidence.reverse_complement
+        }
+    };
+    let check = check_layout(&wells, map, evidence);
+
+    println!("\nPlate layout ({} samples with a well):", wells.len());
+    for score in &check.scores {
+        println!(
+            "  * {}: {} reads, {} of {} samples found",
+            score.layout, score.score, score.matched, wells.len()
+        );
+    }
+    // A map of part of the plate cannot show the layouts that move samples off it
+    let size = PlateSize::of(map);
+    let report_unchecked = || {
+        println!(
+            "The plate map lists only {} of the {} wells, so not every layout could be checked:",
+            map.well_count(),
+            usize::from(size.rows) * usize::from(size.columns)
+        );
+        for score in check.unchecked() {
+            println!("  * {}: {} of {} samples got a well the map does not list", score.layout, score.unmapped, wells.len());
+        }
+        println!("Pass a plate map or kit file with the full plate to check them.");
+    };
+    if check.observed == 0 {
+        println!("\n⚠️  Warning: none of the plate map's indexes were found in the reads.");
+        if !check.unchecked().is_empty() {
+            report_unchecked();
+        }
+        return Ok(());
+    }
+    let Some(layout) = check.likely_error else {
+        if check.unchecked().is_empty() {
+            println!("The reads fit the recorded wells best.");
+        } else {
+            report_unchecked();
+        }
+        return Ok(());
+    };
+    println!("\n⚠️  Warning: likely plate layout error: the index plate was {}.", layout);
+    if !args.write {
+        println!("Run with --write to write the sheet with the indexes of the wells the samples actually got.");
+        return Ok(());
+    }
+    let fixed = write_layout_fix(&args.sheet, SheetTable { columns, rows }, map, layout)?;
+    println!("Corrected sheet saved to: {} (forward strand indexes; process it with --instrument or --workflow)", fixed.display());
+    Ok(())
+}
+
+/// Writes `table` with its i7 and i5 columns (added when missing) set to the
+/// indexes of the wells each sample got under `layout`.
+fn write_layout_fix(file_path: &Path, table: SheetTable, map: &PlateMap, layout: PlateLayout) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    let SheetTable { mut columns, mut rows } = table;
+    let (plate_col, well_col) = well_columns(&columns).ok_or("The sheet has no Well column")?;
+    let (i7_col, i5_col) = index_columns(&mut columns);
+    let size = PlateSize::of(map);
+    for row in &mut rows {
+        row.resize(columns.len().max(row.len()), String::new());
+        let plate = plate_col.map(|idx| row[idx].trim().to_string()).filter(|plate| !plate.is_empty());
+        let entry = layout
+            .index_well(&row[well_col], size)
+            .and_then(|index_well| map.get(plate.as_deref(), &index_well));
+        // A sample whose well falls off the plate is left without indexes
+        row[i7_col] = entry.map(|entry| entry.i7.clone()).unwrap_or_default();
+        row[i5_col] = entry.map(|entry| entry.i5.clone()).unwrap_or_default();
+    }
+
+    let fixed_path = file_path.with_file_name(format!(
+        "{}_layout_fixed.csv",
+        file_path.file_stem().unwrap().to_string_lossy()
+    ));
+    let mut writer = WriterBuilder::new().flexible(true).from_path(&fixed_path)?;
+    writer.write_record(&columns)?;
+    for row in &rows {
+        writer.write_record(row)?;
+    }
+    writer.flush()?;
+    Ok(fixed_path)
+}
+
+/// What joining a sheet to a plate map did, for the report.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct PlateJoin {
+    filled: usize,
+    /// Rows left without indexes, and why.
+    unmatched: Vec<String>,
+    /// Samples in a well that an earlier sample is in.
+    shared_wells: Vec<String>,
+    /// Wells listed more than once in the map; the first one is used.
+    duplicates: Vec<String>,
+}
+
+/// Fills in the empty i7 and i5 cells of a sheet with `Plate` and `Well`
+/// columns from `map`, adding `Index` / `Index 2` columns when it has none.
+fn fill_from_plate_map(columns: &mut Vec<String>, rows: &mut [Vec<String>], map: &PlateMap) -> Result<PlateJoin, String> {
+    let (plate_col, well_col) = well_columns(columns).ok_or("The sheet has no Well column")?;
+    let id_col = columns.iter().position(|c| ["Id", "Sample ID", "Sample_ID"].iter().any(|name| c.trim().eq_ignore_ascii_case(name)));
+    let (i7_col, i5_col) = index_columns(columns);
+
+    let mut join = PlateJoin {
+        duplicates: map.duplicates(),
+        ..PlateJoin::default()
+    };
+    let mut sheet_wells: Vec<(String, String)> = Vec::new();
+    for (n, row) in rows.iter_mut().enumerate() {
+        row.resize(columns.len().max(row.len()), String::new());
+        let sample = sample_name(id_col.map(|idx| row[idx].as_str()), n + 1);
+        let plate = plate_col.map(|idx| row[idx].trim().to_string()).filter(|plate| !plate.is_empty());
+        let well = row[well_col].trim().to_string();
+        let Some(normalized) = normalize_well(&well) else {
+            join.unmatched.push(if well.is_empty() {
+                format!("{}: no well", sample)
+            } else {
+                format!("{}: '{}' is not a plate well", sample, well)
+            });
+            continue;
+        };
+        let label = match &plate {
+            Some(plate) => format!("{} {}", plate, normalized),
+            None => normalized.clone(),
+        };
+        match sheet_wells.iter().find(|(known, _)| known.eq_ignore_ascii_case(&label)) {
+            Some((_, first)) => join.shared_wells.push(format!("{} and {} are both in {}", first, sample, label)),
+            None => sheet_wells.push((label.clone(), sample.clone())),
+        }
+        let Some(entry) = map.get(plate.as_deref(), &normalized) else {
+            join.unmatched.push(format!("{}: {} is not in the plate map", sample, label));
+            continue;
+        };
+        for (idx, sequence) in [(i7_col, &entry.i7), (i5_col, &entry.i5)] {
+            if row[idx].trim().is_empty() {
+                row[idx] = sequence.clone();
+            }
+        }
+        join.filled += 1;
+    }
+    Ok(join)
+}
+
+/// Fills in the indexes of a sheet with `Plate` and `Well` columns from `map`
+/// and writes the joined sheet in the input's format, as `{stem}_joined.csv`
+/// or `{stem}_joined.xlsx`, for the usual processing to pick up. Cells that
+/// already hold a sequence are kept; rows whose well is not in the map are
+/// kept without indexes and reported.
+fn join_plate_map(file_path: &Path, map: &PlateMap, options: &ProcessOptions) -> Result<PathBuf, Box<dyn std::error::Error>> {
+    println!("\nJoining the sheet to the plate map by well...");
+    let file_type = detect_file_type(file_path)?;
+    if !matches!(file_type, FileType::Csv | FileType::Excel) {
+        return Err("--plate-map and --plate-kit take a CSV or Excel export with Plate and Well columns".into());
+    }
+    // Plate maps list i5 for the forward strand, so every column being
+    // reverse complemented would be wrong for i7
+    if options.target_workflow().is_none() && options.operations.is_empty() {
+        return Err("Plate map sequences are forward strand; pass --instrument or --workflow so only the right column is flipped".into());
+    }
+
+    let SheetTable { mut columns, mut rows } = sheet_table(file_path)?;
+    let join = fill_from_plate_map(&mut columns, &mut rows, map)?;
+
+    let stem = file_path.file_stem().unwrap().to_string_lossy();
+    let joined_path = if matches!(file_type, FileType::Excel) {
+        let path = file_path.with_file_name(format!("{}_joined.xlsx", stem));
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        for (row_idx, row) in std::iter::once(&columns).chain(&rows).enumerate() {
+            for (col_idx, value) in row.iter().enumerate() {
+                sheet.write_string(row_idx as u32, col_idx as u16, value)?;
+            }
+        }
+        workbook.save(&path)?;
+        path
+    } else {
+        let path = file_path.with_file_name(format!("{}_joined.csv", stem));
+        let mut writer = WriterBuilder::new().flexible(true).from_path(&path)?;
+        writer.write_record(&columns)?;
+        for row in &rows {
+            writer.write_record(row)?;
+        }
+        writer.flush()?;
+        path
+    };
+    println!("Filled in the indexes of {} of {} row(s)", join.filled, rows.len());
+    println!("Joined sheet saved to: {}", joined_path.display());
+
+    if !join.duplicates.is_empty() {
+        println!("\n⚠️  Warning: wells listed more than once in the plate map (the first one was used):");
+        for label in &join.duplicates {
+            println!("  * {}", label);
+        }
+    }
+    if !join.shared_wells.is_empty() {
+        println!("\n⚠️  Warning: samples sharing a well, and so their indexes:");
+        for shared in &join.shared_wells {
+            println!("  * {}", shared);
+        }
+    }
+    if !join.unmatched.is_empty() {
+        println!("\n⚠️  Warning: rows left without indexes:");
+        for row in &join.unmatched {
+            println!("  * {}", row);
+        }
+    }
+    Ok(joined_path)
+}
+
+/// Detects the file type and processes the file accordingly.
+fn process_file(file: &Path, options: &ProcessOptions) -> Result<(), Box<dyn std::error::Error>> {
+    if let Some(map) = options.plate_map()? {
+        let joined = join_plate_map(file, &map, options)?;
+        return match detect_file_type(&joined)? {
+            FileType::Excel => process_excel_file(&joined, options),
+            _ => process_csv_file(&joined, options),
+        };
+    }
+    match detect_file_type(file)? {
+        FileType::Excel => process_excel_file(file, options),
+        FileType::Csv => process_csv_file(file, options),
+        FileType::SampleSheet => process_sample_sheet(file, options),
+        FileType::MgiBarcodes => process_mgi_barcodes(file, options),
+    }
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args = Args::parse();
+
+    match &args.command {
+        Some(Command::Fastx(fastx_args)) => return run_fastx(fastx_args),
+        Some(Command::Kits(command)) => return run_kits(command),
+        Some(Command::Infer(infer_args)) => return run_infer(infer_args),
+        None => {}
+    }
+    let file = args.file.as_deref().ok_or("No input file given")?;
+
+    if let (Some(workflow), Some(instrument)) = (args.options.workflow, args.options.instrument)
+        && workflow != instrument.workflow()
+    {
+        println!(
+            "Note: --workflow {} overrides the {} workflow of {}",
+            workflow, instrument.workflow(), instrument.name()
+        );
+    }
+
+    process_file(file, &args.options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::NamedTempFile;
+    use tracseq_rc::{reverse_complement, reverse_complement_with};
+
+    #[test]
     fn test_reverse_complement() {
         assert_eq!(reverse_complement("ATGC"), "GCAT");
         assert_eq!(reverse_complement("ATGC-N"), "N-GCAT");
@@ -524,11 +2934,856 @@ mod tests {
         assert_eq!(reverse_complement("ATGCATGC"), "GCATGCAT");
     }
 
+    #[test]
+    fn test_uppercase_option_applies_per_column() {
+        let options = ProcessOptions {
+            uppercase_columns: vec!["Index 2".to_string()],
+            ..ProcessOptions::default()
+        };
+        let upper = options.rc_options("Index 2");
+        let preserve = options.rc_options("IndexNtSequence");
+        assert_eq!(reverse_complement_with("acgTN", &upper), "NACGT");
+        assert_eq!(reverse_complement_with("acgTN", &preserve), "NAcgt");
+    }
+
+    #[test]
+    fn test_alphabet_option_is_parsed() {
+        let args = Args::try_parse_from(["tracseq_rc", "oligos.csv", "--alphabet", "rna"]).unwrap();
+        assert_eq!(args.options.alphabet, Alphabet::Rna);
+        assert_eq!(
+            reverse_complement_with("ACGU", &args.options.rc_options("Index")),
+            "ACGU"
+        );
+        assert_eq!(args.options.sequence_chars(), "ATGCNU");
+
+        let args = Args::try_parse_from(["tracseq_rc", "oligos.csv"]).unwrap();
+        assert_eq!(args.options.alphabet, Alphabet::Dna);
+        assert!(Args::try_parse_from(["tracseq_rc", "oligos.csv", "--alphabet", "xna"]).is_err());
+    }
+
+    #[test]
+    fn test_fastx_subcommand_is_parsed() {
+        let args = Args::try_parse_from(["tracseq_rc", "fastx", "reads.fq.gz", "-o", "rc.fq.gz", "--tag", "/rc"]).unwrap();
+        assert!(args.file.is_none());
+        match args.command {
+            Some(Command::Fastx(fastx)) => {
+                assert_eq!(fastx.input, Some(PathBuf::from("reads.fq.gz")));
+                assert_eq!(fastx.output, Some(PathBuf::from("rc.fq.gz")));
+                assert_eq!(fastx.tag.as_deref(), Some("/rc"));
+            }
+            _ => panic!("expected the fastx subcommand"),
+        }
+
+        // A sheet path is still required without a subcommand
+        assert!(Args::try_parse_from(["tracseq_rc"]).is_err());
+    }
+
+    fn processed(value: &str, format: &str) -> Result<String, SequenceError> {
+        let spec = ColumnSpec {
+            index: 0,
+            name: "Index".to_string(),
+            parser: format.parse().unwrap(),
+            operation: Operation::ReverseComplement,
+            pair_operations: None,
+            reason: None,
+            lengths: Vec::new(),
+            fill_empty: false,
+        };
+        process_sequence_cell(value, &spec, &RcOptions::default(), None)
+            .map(|cell| cell.map(|c| c.to_string()).unwrap_or_default())
+    }
+
+    #[test]
+    fn test_process_sequence_cell_reports_cell_offset() {
+        assert_eq!(processed("Prefix-ATGC", "label:-").unwrap(), "Prefix-GCAT");
+        assert_eq!(processed("GCAT", "label:-").unwrap(), "ATGC");
+        assert_eq!(processed("", "label:-").unwrap(), "");
+        assert_eq!(
+            processed("Prefix-AT1C", "label:-"),
+            Err(SequenceError::InvalidBase { base: '1', offset: 9 })
+        );
+        assert_eq!(
+            processed("ATGC-N", "sequence"),
+            Err(SequenceError::InvalidBase { base: '-', offset: 4 })
+        );
+        assert_eq!(processed("D701-", "label:-"), Err(SequenceError::Empty));
+    }
+
+    #[test]
+    fn test_cell_format_option() {
+        let args = Args::try_parse_from([
+            "tracseq_rc",
+            "batch.csv",
+            "--cell-format",
+            "Index=paren",
+            "--cell-format",
+            "Index 2=label:_",
+        ])
+        .unwrap();
+        let options = &args.options;
+        // Explicit formats win over detection
+        assert_eq!(options.cell_parser("Index", &["D701-ATTACTCG"], true).to_string(), "paren");
+        assert_eq!(options.cell_parser("Index 2", &[], false).to_string(), "label:_");
+        // Otherwise the format is detected from the samples
+        assert_eq!(options.cell_parser("IndexNtSequence", &["D701:ATTACTCG"], true).to_string(), "label::");
+        assert_eq!(options.cell_parser("IndexNtSequence", &[], true).to_string(), "label:-");
+        assert_eq!(options.cell_parser("IndexNtSequence2", &[], false).to_string(), "sequence");
+
+        // Plain sequences are left to the DNA scan; composite cells are picked up
+        assert!(options.composite_cell_parser("Barcode", &["ACGTACGT"]).is_none());
+        assert!(options.composite_cell_parser("Barcode", &["ACGTACGT+TTGGCCAA"]).is_some());
+        assert_eq!(
+            options.composite_cell_parser("Barcode", &["D701 (ATTACTCG)"]).unwrap().to_string(),
+            "paren"
+        );
+        assert!(Args::try_parse_from(["tracseq_rc", "batch.csv", "--cell-format", "Index"]).is_err());
+    }
+
+    #[test]
+    fn test_csv_with_invalid_cell_writes_no_output() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("batch.csv");
+        std::fs::write(&input, "Id,Index 2\n1,ACGT\n2,AC GT\n")?;
+
+        let err = process_csv_file(&input, &ProcessOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("1 sequence cell(s)"));
+        assert!(!dir.path().join("batch_RC.csv").exists());
+
+        std::fs::write(&input, "Id,Index 2\n1,ACGT\n2,AACC\n")?;
+        process_csv_file(&input, &ProcessOptions::default())?;
+        let output = std::fs::read_to_string(dir.path().join("batch_RC.csv"))?;
+        assert_eq!(output, "Id,Index 2\n1,ACGT\n2,GGTT\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_operation_option_selects_per_column() -> Result<(), Box<dyn std::error::Error>> {
+        let args = Args::try_parse_from([
+            "tracseq_rc",
+            "batch.csv",
+            "--operation",
+            "Index=none",
+            "--operation",
+            "Index 2=complement",
+        ])
+        .unwrap();
+        let options = &args.options;
+        assert_eq!(options.column_operation("Index").0, Operation::Passthrough);
+        assert_eq!(options.column_operation("Index 2").0, Operation::Complement);
+        assert_eq!(options.column_operation("IndexNtSequence"), (Operation::ReverseComplement, None));
+        assert!(Args::try_parse_from(["tracseq_rc", "batch.csv", "--operation", "Index=flip"]).is_err());
+
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("batch.csv");
+        // The passthrough column is not validated, so its odd cell is kept
+        std::fs::write(&input, "Id,Index,Index 2\n1,ATTACTCG,TATAGCCT\n2,n/a,AACC\n")?;
+        process_csv_file(&input, options)?;
+        let output = std::fs::read_to_string(dir.path().join("batch_RC.csv"))?;
+        assert_eq!(output, "Id,Index,Index 2\n1,ATTACTCG,ATATCGGA\n2,n/a,TTGG\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_index_sequences_drop_labels() {
+        let specs = [
+            ProcessOptions::default().column_spec(1, "Index", "label:-".parse().unwrap()),
+            ProcessOptions::default().column_spec(2, "Index 2", CellParser::default()),
+        ];
+        let values = ["D701-ATTACTCG".to_string(), "TATAGCCT+ACGT".to_string()];
+        assert_eq!(index_sequences(&values, &specs), ["ATTACTCG", "TATAGCCTACGT"]);
+        assert_eq!(index_sequences(&["n/a".to_string(), String::new()], &specs), ["n/a", ""]);
+
+        assert_eq!(sample_name(Some(" S1 "), 2), "S1");
+        assert_eq!(sample_name(Some(""), 2), "row 2");
+
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--check-collisions", "1"]).unwrap();
+        assert_eq!(args.options.check_collisions, Some(1));
+    }
+
+    #[test]
+    fn test_sample_sheet_reverse_complements_only_index2() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("SampleSheet.csv");
+        let sheet = "[Header]\nIEMFileVersion,4\n\n[Reads]\n151\n\n[Data]\nSample_ID,index,index2\nS1,ATTACTCG,TATAGCCT\nS2,TCCGGAGA,\n";
+        std::fs::write(&input, sheet)?;
+        assert!(matches!(detect_file_type(&input)?, FileType::SampleSheet));
+
+        process_sample_sheet(&input, &ProcessOptions::default())?;
+        let output = std::fs::read_to_string(dir.path().join("SampleSheet_RC.csv"))?;
+        assert_eq!(output, sheet.replace("TATAGCCT", "AGGCTATA"));
+
+        // --uppercase applies to the column it names
+        std::fs::write(&input, sheet.replace("TATAGCCT", "tatagcct"))?;
+        let args = Args::try_parse_from(["tracseq_rc", "SampleSheet.csv", "--uppercase", "index2"]).unwrap();
+        process_sample_sheet(&input, &args.options)?;
+        let output = std::fs::read_to_string(dir.path().join("SampleSheet_RC.csv"))?;
+        assert_eq!(output, sheet.replace("TATAGCCT", "AGGCTATA"));
+
+        std::fs::write(&input, sheet.replace("TATAGCCT", "TATA-CCT"))?;
+        let err = process_sample_sheet(&input, &ProcessOptions::default()).unwrap_err();
+        assert!(err.to_string().contains("1 sequence cell(s)"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_v2_sample_sheet_index2_only_when_asked() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("SampleSheet.csv");
+        let sheet = "[Header]\nFileFormatVersion,2\n\n[BCLConvert_Settings]\nOverrideCycles,Y151;I8;I8;Y151\n\n[BCLConvert_Data]\nSample_ID,Index,Index2\nS1,ATTACTCG,TATAGCCT\n\n[Cloud_Data]\nSample_ID,ProjectName\nS1,Liver\n";
+        std::fs::write(&input, sheet)?;
+        let output_path = dir.path().join("SampleSheet_RC.csv");
+
+        process_sample_sheet(&input, &ProcessOptions::default())?;
+        assert_eq!(std::fs::read_to_string(&output_path)?, sheet);
+
+        let args = Args::try_parse_from(["tracseq_rc", "SampleSheet.csv", "--operation", "Index2=rc"]).unwrap();
+        process_sample_sheet(&input, &args.options)?;
+        assert_eq!(std::fs::read_to_string(&output_path)?, sheet.replace("TATAGCCT", "AGGCTATA"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_instrument_decides_which_columns_to_flip() -> Result<(), Box<dyn std::error::Error>> {
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--instrument", "nextseq550"]).unwrap();
+        let options = &args.options;
+        assert_eq!(options.column_operation("Index 2").0, Operation::ReverseComplement);
+        assert_eq!(options.column_operation("Index").0, Operation::Passthrough);
+        assert_eq!(options.column_operation("Barcode").0, Operation::Passthrough);
+        let (_, reason) = options.column_operation("Index 2");
+        assert!(reason.unwrap().starts_with("NextSeq 500/550 (the reverse complement workflow)"));
+
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("batch.csv");
+        std::fs::write(&input, "Id,Index,Index 2\n1,ATTACTCG,TATAGCCT\n")?;
+        process_csv_file(&input, options)?;
+        let output = std::fs::read_to_string(dir.path().join("batch_RC.csv"))?;
+        assert_eq!(output, "Id,Index,Index 2\n1,ATTACTCG,AGGCTATA\n");
+
+        // Forward strand instruments leave i5 alone; --workflow and --operation override
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--instrument", "miseq"]).unwrap();
+        assert_eq!(args.options.column_operation("Index 2").0, Operation::Passthrough);
+        let args = Args::try_parse_from([
+            "tracseq_rc", "batch.csv", "--instrument", "miseq", "--workflow", "reverse-complement",
+        ])
+        .unwrap();
+        assert_eq!(args.options.column_operation("Index 2").0, Operation::ReverseComplement);
+        let args = Args::try_parse_from([
+            "tracseq_rc", "batch.csv", "--instrument", "miseq", "--operation", "Index=rc",
+        ])
+        .unwrap();
+        assert_eq!(args.options.column_operation("Index"), (Operation::ReverseComplement, None));
+
+        assert!(Args::try_parse_from(["tracseq_rc", "batch.csv", "--instrument", "novaseq"]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_combined_cells_flip_only_i5() -> Result<(), Box<dyn std::error::Error>> {
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--instrument", "novaseqx"]).unwrap();
+        let rc_options = RcOptions::default();
+        for column in ["Index", "Index2"] {
+            let spec = args.options.column_spec(1, column, CellParser::default());
+            let cell = process_sequence_cell("AAAACCCC+GGGGTTTA", &spec, &rc_options, None)?;
+            assert_eq!(cell.map(|c| c.to_string()).as_deref(), Some("AAAACCCC+TAAACCCC"), "column {}", column);
+        }
+
+        // Forward strand instruments leave the whole cell alone; --operation applies to both halves
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--instrument", "miseq"]).unwrap();
+        let spec = args.options.column_spec(1, "Index2", CellParser::default());
+        assert_eq!(process_sequence_cell("AAAACCCC+GGGGTTTA", &spec, &rc_options, None)?, None);
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--instrument", "novaseqx", "--operation", "Index=rc"]).unwrap();
+        let spec = args.options.column_spec(1, "Index", CellParser::default());
+        let cell = process_sequence_cell("AAAACCCC+GGGGTTTA", &spec, &rc_options, None)?;
+        assert_eq!(cell.map(|c| c.to_string()).as_deref(), Some("GGGGTTTT+TAAACCCC"));
+
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("batch.csv");
+        std::fs::write(&input, "Id,Index\n1,AAAACCCC+GGGGTTTA\n")?;
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--instrument", "novaseqx"]).unwrap();
+        process_csv_file(&input, &args.options)?;
+        let output = std::fs::read_to_string(dir.path().join("batch_RC.csv"))?;
+        assert_eq!(output, "Id,Index\n1,AAAACCCC+TAAACCCC\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_kit_check_reports_orientation_before_and_after() {
+        let kits = KitDatabase::builtin();
+        let options = ProcessOptions::default();
+        let spec = options.column_spec(1, "Index 2", CellParser::default());
+        let check = KitCheck::new(2, &spec, "TATAGCCT", "AGGCTATA", &kits).unwrap();
+        assert_eq!(
+            check.to_string(),
+            "row 2, column 'Index 2': TATAGCCT forward -> AGGCTATA reverse-complemented (TruSeq CD Indexes D501 i5)"
+        );
+        assert!(!check.is_unknown());
+
+        let combined = KitCheck::new(3, &spec, "ATTACTCG+ACGTACGT", "ATTACTCG+ACGTACGT", &kits).unwrap();
+        assert_eq!(combined.after_orientation, "forward+unknown");
+        assert!(combined.is_unknown());
+        assert!(KitCheck::new(4, &spec, "", "", &kits).is_none());
+
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--verify-kits"]).unwrap();
+        assert!(args.options.kit_database().unwrap().is_some());
+        assert!(options.kit_database().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_user_kit_files() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let kit_file = dir.path().join("core_plate.csv");
+        std::fs::write(&kit_file, "# version: 2026-01\nkit,well,index_name,i7,i5\nCore Plate 1,A1,CP001,ACGTACGT,TTGGCCAA\n")?;
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--kits", kit_file.to_str().unwrap()]).unwrap();
+        let kits = args.options.kit_database()?.unwrap();
+        assert!(kits.kits().contains(&"Core Plate 1"));
+        assert!(kits.kits().contains(&"TruSeq CD Indexes"));
+
+        let spec = args.options.column_spec(1, "Index 2", CellParser::default());
+        let check = KitCheck::new(2, &spec, "TTGGCCAA", "TTGGCCAA", &kits).unwrap();
+        assert_eq!(check.after_orientation, "forward");
+
+        let args = Args::try_parse_from(["tracseq_rc", "kits", "diff", "builtin", kit_file.to_str().unwrap()]).unwrap();
+        assert!(matches!(args.command, Some(Command::Kits(KitsCommand::Diff { .. }))));
+        assert!(Args::try_parse_from(["tracseq_rc", "kits", "validate"]).is_err());
+
+        let missing = dir.path().join("missing.csv");
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--kits", missing.to_str().unwrap()]).unwrap();
+        assert!(args.options.kit_database().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_infer_writes_rc_sheet() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let sheet = dir.path().join("SampleSheet.csv");
+        std::fs::write(
+            &sheet,
+            "[Header]\nIEMFileVersion,4\n\n[Data]\nSample_ID,index,index2\nS1,ATTACTCG,TATAGCCT\nS2,TCCGGAGA,ATAGAGGC\n",
+        )?;
+        let samples = read_sheet_indexes(&sheet)?;
+        assert_eq!(samples[0], SheetIndexes {
+            sample: "S1".to_string(),
+            i7: Some("ATTACTCG".to_string()),
+            i5: Some("TATAGCCT".to_string()),
+        });
+
+        // The sequencer read i5 reverse complemented
+        let fastq = dir.path().join("Undetermined_S0_L001_R1_001.fastq");
+        let mut reads = String::new();
+        for (n, barcode) in ["ATTACTCG+AGGCTATA", "ATTACTCG+AGGCTATA", "TCCGGAGA+GCCTCTAT", "GGGGGGGG+AGATCTCG"].iter().enumerate() {
+            reads.push_str(&format!("@r{} 1:N:0:{}\nACGT\n+\nIIII\n", n, barcode));
+        }
+        std::fs::write(&fastq, reads)?;
+        assert_eq!(index_read_kind(&fastq), None);
+        assert_eq!(index_read_kind(Path::new("Undetermined_S0_L001_I2_001.fastq.gz")), Some(IndexRole::I5));
+
+        let args = Args::try_parse_from(["tracseq_rc", "infer", sheet.to_str().unwrap(), fastq.to_str().unwrap(), "--write"]).unwrap();
+        let Some(Command::Infer(infer_args)) = &args.command else {
+            panic!("expected the infer subcommand");
+        };
+        assert_eq!(infer_args.top, 10);
+        run_infer(infer_args)?;
+        let written = std::fs::read_to_string(dir.path().join("SampleSheet_RC.csv"))?;
+        assert!(written.contains("S1,ATTACTCG,AGGCTATA"));
+        assert!(written.contains("S2,TCCGGAGA,GCCTCTAT"));
+        assert!(Args::try_parse_from(["tracseq_rc", "infer", sheet.to_str().unwrap()]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_count_barcodes_pairs_index_reads_by_lane() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let fastq = |name: &str, sequences: &[&str]| -> std::io::Result<PathBuf> {
+            let path = dir.path().join(name);
+            let reads: String = sequences.iter().enumerate().map(|(n, seq)| format!("@r{}\n{}\n+\nIIIIIIII\n", n, seq)).collect();
+            std::fs::write(&path, reads)?;
+            Ok(path)
+        };
+        // I2 of lane 1 comes last, so files are paired by name rather than order
+        let files = [
+            fastq("Undetermined_S0_L001_I1_001.fastq", &["ATTACTCG", "ATTACTCG"])?,
+            fastq("Undetermined_S0_L002_I1_001.fastq", &["TCCGGAGA"])?,
+            fastq("Undetermined_S0_L002_I2_001.fastq", &["GCCTCTAT"])?,
+            fastq("Undetermined_S0_L001_I2_001.fastq", &["AGGCTATA", "AGGCTATA"])?,
+        ];
+        assert_eq!(index_pair_key(&files[0]), index_pair_key(&files[3]));
+        assert_ne!(index_pair_key(&files[0]), index_pair_key(&files[1]));
+        assert_ne!(
+            index_pair_key(Path::new("Undetermined_S0_L001_I1_001.fastq.gz")),
+            index_pair_key(Path::new("Undetermined_S0_L001_I2_002.fastq.gz"))
+        );
+
+        let counts = count_barcodes(&files, 100)?;
+        assert_eq!(counts.total(), 3);
+        assert_eq!(counts.count(Some("ATTACTCG"), "AGGCTATA"), 2);
+        assert_eq!(counts.count(Some("TCCGGAGA"), "GCCTCTAT"), 1);
+
+        let twice = [files[0].clone(), files[0].clone()];
+        assert!(count_barcodes(&twice, 100).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_infer_plate_layout() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let sheet = dir.path().join("batch.csv");
+        std::fs::write(&sheet, "Id,Well\nS1,A1\nS2,A2\n")?;
+        let map = dir.path().join("plate.csv");
+        std::fs::write(&map, "Well,i7,i5\nA1,ATTACTCG,TATAGCCT\nA2,TCCGGAGA,ATAGAGGC\nH11,CGCTCATT,CCTATCCT\nH12,GAGATTCC,GGCTCTGA\n")?;
+
+        // The index plate went on rotated: S1 got H12 and S2 got H11
+        let fastq = dir.path().join("Undetermined_S0_L001_R1_001.fastq");
+        let mut reads = String::new();
+        for (n, barcode) in ["GAGATTCC+TCAGAGCC", "GAGATTCC+TCAGAGCC", "CGCTCATT+AGGATAGG", "ATTACTCG+AGGCTATA"].iter().enumerate() {
+            reads.push_str(&format!("@r{} 1:N:0:{}\nACGT\n+\nIIII\n", n, barcode));
+        }
+        std::fs::write(&fastq, reads)?;
+
+        let args = Args::try_parse_from([
+            "tracseq_rc", "infer", sheet.to_str().unwrap(), fastq.to_str().unwrap(), "--plate-map", map.to_str().unwrap(), "--write",
+        ])
+        .unwrap();
+        let Some(Command::Infer(infer_args)) = &args.command else {
+            panic!("expected the infer subcommand");
+        };
+        run_infer(infer_args)?;
+        let fixed = std::fs::read_to_string(dir.path().join("batch_layout_fixed.csv"))?;
+        assert_eq!(fixed, "Id,Well,Index,Index 2\nS1,A1,GAGATTCC,GGCTCTGA\nS2,A2,CGCTCATT,CCTATCCT\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_colour_balance_cells() {
+        let args = Args::try_parse_from(["tracseq_rc", "SampleSheet.csv", "--check-balance"]).unwrap();
+        assert!(args.options.check_balance);
+        let spec = args.options.column_spec(2, "IndexNtSequence", CellParser::new(CellFormat::Labeled { delimiter: '-' }));
+        let cell = BalanceCell::new(Some(" 1 "), &spec, "D701-ATTACTCG").unwrap();
+        assert_eq!(cell.lane.as_deref(), Some("1"));
+        assert_eq!(cell.sequences, ["ATTACTCG"]);
+        assert!(BalanceCell::new(Some(""), &spec, "").is_none());
+
+        // Lane 1 has an all-G i7; the row without a lane is in both lanes
+        let mut i7 = args.options.column_spec(0, "index", CellParser::default());
+        i7.operation = Operation::Passthrough;
+        let cells: Vec<BalanceCell> = [(Some("1"), "GGGGGGGG"), (Some("1"), "GGGGGGGG"), (Some("2"), "AAAAAAAA"), (None, "ACACACAC")]
+            .into_iter()
+            .filter_map(|(lane, value)| BalanceCell::new(lane, &i7, value))
+            .collect();
+        let groups = colour_balance(&cells);
+        let lanes: Vec<(Option<&str>, usize, bool)> = groups
+            .iter()
+            .map(|group| (group.lane.as_deref(), group.samples, !group.output.is_empty()))
+            .collect();
+        assert_eq!(lanes, [(Some("1"), 3, true), (Some("2"), 2, false)]);
+        assert_eq!(groups[0].output[0].problem(), Some("no green signal, only G/C"));
+        assert_eq!(groups[1].operation, Some(Operation::Passthrough));
+
+        // What is output is checked, whatever the operation
+        let mut i5 = args.options.column_spec(1, "index2", CellParser::default());
+        i5.operation = Operation::Complement;
+        let cells: Vec<BalanceCell> = BalanceCell::new(None, &i5, "AAAAAAAA").into_iter().collect();
+        let groups = colour_balance(&cells);
+        assert!(groups[0].as_written.is_empty());
+        assert_eq!(groups[0].output.len(), 8);
+        assert_eq!(groups[0].output[0].problem(), Some("no red signal, only G/T"));
+    }
+
+    #[test]
+    fn test_normalize_index_lengths() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let sheet = dir.path().join("SampleSheet.csv");
+        std::fs::write(
+            &sheet,
+            "[Data]\nSample_ID,index,index2\nS1,ATTACTCG,TATAGCCT\nS2,TCCGGAGAAT,ATAGAGGCAC\nS3,CGCTCATT,\n",
+        )?;
+        let output = dir.path().join("SampleSheet_RC.csv");
+
+        // Truncation happens after the reverse complement, at the end as read
+        let args = Args::try_parse_from(["tracseq_rc", "SampleSheet.csv", "--normalize-length", "truncate"]).unwrap();
+        process_sample_sheet(&sheet, &args.options)?;
+        let written = std::fs::read_to_string(&output)?;
+        assert!(written.contains("S1,ATTACTCG,AGGCTATA\n"));
+        assert!(written.contains("S2,TCCGGAGA,GTGCCTCT\n"));
+        assert!(written.contains("S3,CGCTCATT,\n"));
+
+        let args = Args::try_parse_from(["tracseq_rc", "SampleSheet.csv", "--normalize-length", "pad"]).unwrap();
+        process_sample_sheet(&sheet, &args.options)?;
+        let written = std::fs::read_to_string(&output)?;
+        assert!(written.contains("S1,ATTACTCGNN,AGGCTATANN\n"));
+        assert!(written.contains("S3,CGCTCATTNN,NNNNNNNNNN\n"));
+
+        // D501 is a TruSeq i5; read on the reverse complement workflow it continues into GT
+        let args = Args::try_parse_from(["tracseq_rc", "SampleSheet.csv", "--normalize-length", "pad-adapter"]).unwrap();
+        process_sample_sheet(&sheet, &args.options)?;
+        let written = std::fs::read_to_string(&output)?;
+        assert!(written.contains("S1,ATTACTCGAT,AGGCTATAGT\n"));
+        assert!(written.contains("S3,CGCTCATTAT,GTGTAGATCT\n"));
+
+        // Only the second half of an i7+i5 pair is i5; the four oligos of a 10x i7 set are all i7
+        let mut spec = args.options.column_spec(1, "Index", CellParser::default());
+        spec.lengths = vec![None; 2];
+        assert_eq!(index_role(&spec, 1), Some(IndexRole::I5));
+        spec.lengths = vec![None; 4];
+        let roles: Vec<Option<IndexRole>> = (0..4).map(|part| index_role(&spec, part)).collect();
+        assert_eq!(roles, [Some(IndexRole::I7); 4]);
+        let kits = args.options.adapter_kits()?;
+        assert_eq!(index_padding(&spec, 3, None, kits.as_ref()), "ATCTCGTATGCCGTCTTCTGCTTG");
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_lanes() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("batch.csv");
+        std::fs::write(
+            &input,
+            "Lane,Sample,Index2\n1,S1,TATAGCCT\n2,S2,TATAGCCT\n2,S3,ATAGAGGC\n,Spike,CCTATCCT\n",
+        )?;
+
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--split-lanes", "--check-collisions", "0"]).unwrap();
+        process_csv_file(&input, &args.options)?;
+        let lane1 = std::fs::read_to_string(dir.path().join("batch_RC_L1.csv"))?;
+        let lane2 = std::fs::read_to_string(dir.path().join("batch_RC_L2.csv"))?;
+        // Rows without a lane go to every lane's file
+        assert_eq!(lane1, "Lane,Sample,Index2\n1,S1,AGGCTATA\n,Spike,AGGATAGG\n");
+        assert_eq!(lane2, "Lane,Sample,Index2\n2,S2,AGGCTATA\n2,S3,GCCTCTAT\n,Spike,AGGATAGG\n");
+        assert!(dir.path().join("batch_RC.csv").exists());
+        Ok(())
+    }
+
+    #[test]
+    fn test_collisions_are_grouped_by_lane() {
+        let samples = vec![
+            (Some("1".to_string()), IndexedSample::new("S1", vec!["ACGTACGT".to_string()])),
+            (Some("2".to_string()), IndexedSample::new("S2", vec!["ACGTACGT".to_string()])),
+            (None, IndexedSample::new("Spike", vec!["TTTTGGGG".to_string()])),
+        ];
+        // The same index in different lanes does not collide
+        assert_eq!(lane_collisions(&samples, 0).iter().map(|(_, c)| c.len()).sum::<usize>(), 0);
+
+        let samples = vec![
+            (Some("1".to_string()), IndexedSample::new("S1", vec!["TTTTGGGG".to_string()])),
+            (Some("2".to_string()), IndexedSample::new("S2", vec!["ACGTACGT".to_string()])),
+            (None, IndexedSample::new("Spike", vec!["TTTTGGGG".to_string()])),
+        ];
+        let collisions = lane_collisions(&samples, 0);
+        let found: Vec<(Option<&str>, usize)> = collisions.iter().map(|(lane, c)| (lane.as_deref(), c.len())).collect();
+        assert_eq!(found, [(Some("1"), 1), (Some("2"), 0)]);
+    }
+
+    #[test]
+    fn test_tenx_set_expansion() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("batch.csv");
+        std::fs::write(&input, "Id,Index,Index 2\n1,SI-TT-A1,\n2,SI-GA-A1,\n")?;
+
+        // i5 follows the workflow: forward on a MiSeq, reverse complemented on a NovaSeq X
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--instrument", "miseq"]).unwrap();
+        process_csv_file(&input, &args.options)?;
+        let written = std::fs::read_to_string(dir.path().join("batch_RC.csv"))?;
+        assert!(written.contains("1,GTAACATGCG,AGTGTTACCT\n"));
+        assert!(written.contains("2,GGTTTACT+CTAAACGG+TCGGCGTC+AACCGTAA,\n"));
+
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--instrument", "novaseqx"]).unwrap();
+        process_csv_file(&input, &args.options)?;
+        let written = std::fs::read_to_string(dir.path().join("batch_RC.csv"))?;
+        assert!(written.contains("1,GTAACATGCG,AGGTAACACT\n"));
+
+        // Single-index sets become one sample sheet row per oligo
+        let sheet = dir.path().join("SampleSheet.csv");
+        std::fs::write(&sheet, "[Data]\nSample_ID,index,index2\nS1,SI-GA-A1,\nS2,SI-TT-A1,\n")?;
+        let args = Args::try_parse_from(["tracseq_rc", "SampleSheet.csv", "--instrument", "nextseq500"]).unwrap();
+        process_sample_sheet(&sheet, &args.options)?;
+        let written = std::fs::read_to_string(dir.path().join("SampleSheet_RC.csv"))?;
+        assert_eq!(
+            written,
+            "[Data]\nSample_ID,index,index2\nS1,GGTTTACT,\nS1,CTAAACGG,\nS1,TCGGCGTC,\nS1,AACCGTAA,\nS2,GTAACATGCG,AGGTAACACT\n"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_unknown_tenx_sets_are_errors() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("tenx.csv");
+        std::fs::write(&input, "Id,Index\nS1,SI-TT-B3\n")?;
+        let output = dir.path().join("tenx_RC.csv");
+
+        // i7 is left as it is on a NovaSeq X, but the name still has to be known
+        let args = Args::try_parse_from(["tracseq_rc", "tenx.csv", "--instrument", "novaseqx"]).unwrap();
+        assert_eq!(args.options.column_operation("Index").0, Operation::Passthrough);
+        assert!(process_csv_file(&input, &args.options).is_err());
+        assert!(!output.exists());
+        let sheet = dir.path().join("SampleSheet.csv");
+        std::fs::write(&sheet, "[Data]\nSample_ID,index,index2\nS1,SI-TT-B3,\n")?;
+        assert!(process_sample_sheet(&sheet, &args.options).is_err());
+        assert!(!dir.path().join("SampleSheet_RC.csv").exists());
+
+        // With its table loaded the set is expanded, and its i5 follows the workflow
+        let table = dir.path().join("dual_index.csv");
+        std::fs::write(
+            &table,
+            "index_name,index(i7),index2_workflow_a(i5),index2_workflow_b(i5)\nSI-TT-B3,ACGTACGTAC,GGTTTTCCCC,GGGGAAAACC\n",
+        )?;
+        std::fs::write(&input, "Id,Index,Index 2\nS1,SI-TT-B3,\n")?;
+        let args = Args::try_parse_from([
+            "tracseq_rc", "tenx.csv", "--instrument", "novaseqx", "--tenx-sets", table.to_str().unwrap(),
+        ])
+        .unwrap();
+        process_csv_file(&input, &args.options)?;
+        assert_eq!(std::fs::read_to_string(&output)?, "Id,Index,Index 2\nS1,ACGTACGTAC,GGGGAAAACC\n");
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_tenx_set_columns() {
+        let sets = TenxIndexSets::builtin();
+        let columns = [(1, "Index 2")];
+        let mut fields = vec!["S1".to_string(), "SI-TT-A1".to_string()];
+        let expansion = expand_tenx_set(2, &mut fields, &columns, &sets).unwrap();
+        // Without an i7 column only i5 is written
+        assert_eq!(fields, ["S1", "AGTGTTACCT"]);
+        assert_eq!(expansion.missing, Some(IndexRole::I7));
+
+        let mut fields = vec!["S1".to_string(), "ACGT".to_string()];
+        assert!(expand_tenx_set(2, &mut fields, &columns, &sets).is_none());
+    }
+
+    #[test]
+    fn test_export_sample_sheet() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("batch.csv");
+        std::fs::write(&input, "Id,Index,Index 2\nS1,D701-ATTACTCG,TATAGCCT\n")?;
+
+        // i5 is reverse complemented for the NextSeq, in the batch and the BCL Convert sheet alike
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--instrument", "nextseq500", "--export", "bclconvert"]).unwrap();
+        process_csv_file(&input, &args.options)?;
+        assert!(std::fs::read_to_string(dir.path().join("batch_RC.csv"))?.contains("S1,D701-ATTACTCG,AGGCTATA\n"));
+        let written = std::fs::read_to_string(dir.path().join("batch_bclconvert.csv"))?;
+        assert!(written.ends_with("Sample_ID,Index,Index2\nS1,ATTACTCG,AGGCTATA\n"));
+        // Without a workflow Index2 is written for the forward strand
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--export", "bclconvert"]).unwrap();
+        process_csv_file(&input, &args.options)?;
+        let written = std::fs::read_to_string(dir.path().join("batch_bclconvert.csv"))?;
+        assert!(written.ends_with("Sample_ID,Index,Index2\nS1,CGAGTAAT,TATAGCCT\n"));
+
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--instrument", "nextseq500", "--export", "bcl2fastq"]).unwrap();
+        process_csv_file(&input, &args.options)?;
+        let written = std::fs::read_to_string(dir.path().join("batch_bcl2fastq.csv"))?;
+        assert!(written.ends_with("Sample_ID,index,index2\nS1,ATTACTCG,AGGCTATA\n"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_aviti_run_manifest() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("RunManifest.csv");
+        std::fs::write(
+            &input,
+            "[RUNVALUES]\nKeyName,Value\nRunName,Run 7\n\n[SETTINGS]\nSettingName,Value\n\n[SAMPLES]\nSampleName,Index1,Index2,Lane\nS1,ATTACTCG,TATAGCCT,1\n",
+        )?;
+
+        // Only Index2 is flipped, following the AVITI orientation table
+        let args = Args::try_parse_from(["tracseq_rc", "RunManifest.csv"]).unwrap();
+        process_sample_sheet(&input, &args.options)?;
+        let written = std::fs::read_to_string(dir.path().join("RunManifest_RC.csv"))?;
+        assert!(written.starts_with("[RUNVALUES]\nKeyName,Value\nRunName,Run 7\n"));
+        assert!(written.ends_with("[SAMPLES]\nSampleName,Index1,Index2,Lane\nS1,ATTACTCG,AGGCTATA,1\n"));
+
+        // An explicit forward strand workflow wins over the manifest default
+        let args = Args::try_parse_from(["tracseq_rc", "RunManifest.csv", "--workflow", "forward"]).unwrap();
+        process_sample_sheet(&input, &args.options)?;
+        let written = std::fs::read_to_string(dir.path().join("RunManifest_RC.csv"))?;
+        assert!(written.ends_with("S1,ATTACTCG,TATAGCCT,1\n"));
+
+        // The exported manifest follows --workflow too, but not --instrument
+        let batch = dir.path().join("batch.csv");
+        std::fs::write(&batch, "Id,Index,Index 2\nS1,ATTACTCG,TATAGCCT\n")?;
+        let export = dir.path().join("batch_aviti.csv");
+        for (flags, index2) in [
+            (["--instrument", "miseq"], "AGGCTATA"),
+            (["--workflow", "forward"], "TATAGCCT"),
+            (["--workflow", "rc"], "AGGCTATA"),
+        ] {
+            let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--export", "aviti", flags[0], flags[1]]).unwrap();
+            process_csv_file(&batch, &args.options)?;
+            let written = std::fs::read_to_string(&export)?;
+            assert!(written.ends_with(&format!("SampleName,Index1,Index2\nS1,ATTACTCG,{}\n", index2)), "{:?}", flags);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_mgi_barcodes() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("batch.csv");
+        std::fs::write(&input, "Id,Index,Index 2\nS1,ATTACTCG,TATAGCCT\n")?;
+
+        // The LIMS export is forward strand; the barcode list follows the DNBSEQ table
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--instrument", "miseq", "--export", "mgi"]).unwrap();
+        process_csv_file(&input, &args.options)?;
+        assert_eq!(std::fs::read_to_string(dir.path().join("batch_mgi.txt"))?, "S1\tCGAGTAAT\tTATAGCCT\n");
+
+        // A barcode list follows the DNBSEQ table back, whatever the instrument
+        let list = dir.path().join("barcodes.txt");
+        let output = dir.path().join("barcodes_RC.txt");
+        std::fs::write(&list, "1\tCGAGTAAT\tTATAGCCT\n")?;
+        for flags in [&["tracseq_rc", "barcodes.txt"][..], &["tracseq_rc", "barcodes.txt", "--instrument", "miseq"]] {
+            let args = Args::try_parse_from(flags).unwrap();
+            process_file(&list, &args.options)?;
+            assert_eq!(std::fs::read_to_string(&output)?, "1\tATTACTCG\tTATAGCCT\n", "{:?}", flags);
+        }
+
+        // --operation still wins, per column
+        let args = Args::try_parse_from(["tracseq_rc", "barcodes.txt", "--operation", "Barcode2=rc", "--operation", "Barcode=none"]).unwrap();
+        process_file(&list, &args.options)?;
+        assert_eq!(std::fs::read_to_string(&output)?, "1\tCGAGTAAT\tAGGCTATA\n");
+
+        // Single-barcode lists only have barcode 1
+        std::fs::write(&list, "1\tCGAGTAAT\n")?;
+        process_file(&list, &ProcessOptions::default())?;
+        assert_eq!(std::fs::read_to_string(&output)?, "1\tATTACTCG\n");
+
+        // Invalid barcodes are errors even where the table leaves them as entered, and nothing is written
+        std::fs::remove_file(&output)?;
+        std::fs::write(&list, "# run 12\n1\tCGAGTAAT\tTATAGCXT\n2\tTCTCCGGA\tATAGAGGC\n")?;
+        assert!(process_file(&list, &ProcessOptions::default()).is_err());
+        assert!(!output.exists());
+
+        // Kits and colour balance are checked like a batch's; an export needs the batch
+        std::fs::write(&list, "1\tCGAGTAAT\tTATAGCCT\n")?;
+        let args = Args::try_parse_from(["tracseq_rc", "barcodes.txt", "--verify-kits", "--check-balance"]).unwrap();
+        process_file(&list, &args.options)?;
+        let args = Args::try_parse_from(["tracseq_rc", "barcodes.txt", "--export", "bcl2fastq"]).unwrap();
+        let err = process_file(&list, &args.options).unwrap_err();
+        assert!(err.to_string().contains("--export bcl2fastq"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_plate_map_join() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let input = dir.path().join("batch.csv");
+        std::fs::write(&input, "Id,Plate,Well\n1,P1,A1\n2,P1,B01\n3,P1,H12\n4,P1,A1\n")?;
+        let map = dir.path().join("plate.csv");
+        std::fs::write(&map, "Plate,Well,i7,i5\nP1,A1,ATTACTCG,TATAGCCT\nP1,B1,TCCGGAGA,ATAGAGGC\n")?;
+
+        // Sequences come in forward strand, so the join wants an instrument
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--plate-map", map.to_str().unwrap()]).unwrap();
+        let err = process_file(&input, &args.options).unwrap_err();
+        assert!(err.to_string().contains("--instrument"));
+
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--plate-map", map.to_str().unwrap(), "--instrument", "nextseq500"]).unwrap();
+        process_file(&input, &args.options)?;
+        let joined = std::fs::read_to_string(dir.path().join("batch_joined.csv"))?;
+        assert!(joined.starts_with("Id,Plate,Well,Index,Index 2\n1,P1,A1,ATTACTCG,TATAGCCT\n"));
+        assert!(joined.contains("3,P1,H12,,\n"));
+        let written = std::fs::read_to_string(dir.path().join("batch_joined_RC.csv"))?;
+        assert!(written.contains("2,P1,B01,TCCGGAGA,GCCTCTAT\n"));
+
+        // A kit with wells stands in for a plate map
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--plate-kit", "IDT for Illumina UD Indexes", "--workflow", "forward"]).unwrap();
+        process_file(&input, &args.options)?;
+        let written = std::fs::read_to_string(dir.path().join("batch_joined_RC.csv"))?;
+        assert!(written.contains("1,P1,A1,CCGCGGTT,AGCGCTAG\n"));
+
+        // An Excel export stays an Excel workbook
+        let excel = dir.path().join("batch.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        for (row_idx, row) in [["Sample ID", "Plate", "Well"], ["1", "P1", "A1"]].iter().enumerate() {
+            for (col_idx, value) in row.iter().enumerate() {
+                sheet.write_string(row_idx as u32, col_idx as u16, *value)?;
+            }
+        }
+        workbook.save(&excel)?;
+        let args = Args::try_parse_from(["tracseq_rc", "batch.xlsx", "--plate-map", map.to_str().unwrap(), "--instrument", "nextseq500"]).unwrap();
+        process_file(&excel, &args.options)?;
+        let mut output: Xlsx<_> = calamine::open_workbook(dir.path().join("batch_joined_RC.xlsx"))?;
+        let range = output.worksheet_range_at(0).unwrap()?;
+        let rows: Vec<Vec<String>> = range.rows().map(|row| row.iter().map(|cell| cell.to_string()).collect()).collect();
+        assert_eq!(rows[1], ["1", "P1", "A1", "ATTACTCG", "AGGCTATA"]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_plate_map_duplicate_wells() {
+        let map = PlateMap::from_csv("Plate,Well,i7,i5\nP1,A1,ATTACTCG,TATAGCCT\nP1,B1,TCCGGAGA,ATAGAGGC\nP1,a01,CGCTCATT,CCTATCCT\n").unwrap();
+        let mut columns = ["Id", "Plate", "Well"].map(str::to_string).to_vec();
+        let mut rows: Vec<Vec<String>> = [["1", "P1", "A1"], ["2", "P1", "B1"], ["3", "P1", "H12"], ["4", "p1", "A01"]]
+            .iter()
+            .map(|row| row.map(str::to_string).to_vec())
+            .collect();
+        let join = fill_from_plate_map(&mut columns, &mut rows, &map).unwrap();
+        assert_eq!(
+            join,
+            PlateJoin {
+                filled: 3,
+                unmatched: vec!["3: P1 H12 is not in the plate map".to_string()],
+                shared_wells: vec!["1 and 4 are both in p1 A1".to_string()],
+                duplicates: vec!["P1 A1".to_string()],
+            }
+        );
+        // The first of the duplicate map wells is used, for both samples in it
+        assert_eq!(columns, ["Id", "Plate", "Well", "Index", "Index 2"]);
+        assert_eq!(rows[0][3..], ["ATTACTCG", "TATAGCCT"]);
+        assert_eq!(rows[3][3..], ["ATTACTCG", "TATAGCCT"]);
+
+        let mut columns = vec!["Id".to_string()];
+        assert_eq!(fill_from_plate_map(&mut columns, &mut [], &map).unwrap_err(), "The sheet has no Well column");
+    }
+
+    #[test]
+    fn test_csv_and_excel_rows_match() -> Result<(), Box<dyn std::error::Error>> {
+        let dir = tempfile::tempdir()?;
+        let rows = [
+            ["Sample ID", "Lane", "Index", "Index 2"],
+            ["S1", "1", "ATTACTCG", "TATAGCCT"],
+            ["S2", "2", "TCCGGAGA", "tatagcct"],
+            ["S3", "", "CGCTCATT", ""],
+        ];
+        let csv_path = dir.path().join("batch.csv");
+        let text: Vec<String> = rows.iter().map(|row| row.join(",")).collect();
+        std::fs::write(&csv_path, text.join("\n") + "\n")?;
+        let excel_path = dir.path().join("batch.xlsx");
+        let mut workbook = Workbook::new();
+        let sheet = workbook.add_worksheet();
+        for (row_idx, row) in rows.iter().enumerate() {
+            for (col_idx, value) in row.iter().enumerate() {
+                sheet.write_string(row_idx as u32, col_idx as u16, *value)?;
+            }
+        }
+        workbook.save(&excel_path)?;
+
+        let args = Args::try_parse_from(["tracseq_rc", "batch.csv", "--uppercase", "Index 2", "--split-lanes"]).unwrap();
+        process_csv_file(&csv_path, &args.options)?;
+        process_excel_file(&excel_path, &args.options)?;
+        for (csv_name, excel_name) in [("batch_RC.csv", "batch_RC.xlsx"), ("batch_RC_L1.csv", "batch_RC_L1.xlsx")] {
+            let from_csv: Vec<Vec<String>> = ReaderBuilder::new()
+                .has_headers(false)
+                .from_path(dir.path().join(csv_name))?
+                .records()
+                .map(|record| record.map(|record| record.iter().map(str::to_string).collect()))
+                .collect::<Result<_, _>>()?;
+            let mut output: Xlsx<_> = calamine::open_workbook(dir.path().join(excel_name))?;
+            let range = output.worksheet_range_at(0).unwrap()?;
+            let from_excel: Vec<Vec<String>> = range
+                .rows()
+                .map(|row| row.iter().map(|cell| cell.to_string()).collect())
+                .collect();
+            assert_eq!(from_csv, from_excel, "{}", csv_name);
+        }
+        let written = std::fs::read_to_string(dir.path().join("batch_RC.csv"))?;
+        assert!(written.contains("S1,1,CGAGTAAT,AGGCTATA\n"));
+        assert!(written.contains("S2,2,TCTCCGGA,AGGCTATA\n"));
+        Ok(())
+    }
+
     #[test]
     fn test_excel_processing() -> Result<(), Box<dyn std::error::Error>> {
         // Create a temporary Excel file with test data
         let mut workbook = Workbook::new();
-        let mut sheet = workbook.add_worksheet();
+        let sheet = workbook.add_worksheet();
 
         // Write headers
         sheet.write_string(0, 0, "Id")?;
@@ -550,13 +3805,15 @@ mod tests {
 
         // Process the file
         let args = Args {
-            file: temp_file.path().to_path_buf(),
+            file: Some(temp_file.path().to_path_buf()),
+            options: ProcessOptions::default(),
+            command: None,
         };
 
         // Run the main processing logic
-        let mut input_workbook: Xlsx<_> = calamine::open_workbook(&args.file)?;
+        let mut input_workbook: Xlsx<_> = calamine::open_workbook(args.file.as_ref().unwrap())?;
         let mut output_workbook = Workbook::new();
-        let mut output_sheet = output_workbook.add_worksheet();
+        let output_sheet = output_workbook.add_worksheet();
 
         if let Some(Ok(range)) = input_workbook.worksheet_range_at(0) {
             let mut rows = range.rows();
@@ -564,7 +3821,7 @@ mod tests {
 
             // Write header row
             for (col, cell) in header_row.iter().enumerate() {
-                output_sheet.write_string(0, col as u16, &cell.to_string())?;
+                output_sheet.write_string(0, col as u16, cell.to_string())?;
             }
 
             // Process rows
@@ -573,7 +3830,7 @@ mod tests {
                     if col_idx == 1 {
                         // IndexNtSequence column
                         let val = cell.to_string();
-                        let mut parts = val.splitn(2, '-').collect::<Vec<_>>();
+                        let parts = val.splitn(2, '-').collect::<Vec<_>>();
                         if parts.len() == 2 {
                             let rc = reverse_complement(parts[1]);
                             let new_val = format!("{}-{}", parts[0], rc);
@@ -590,7 +3847,7 @@ mod tests {
                         output_sheet.write_string(
                             (row_idx + 1) as u32,
                             col_idx as u16,
-                            &cell.to_string(),
+                            cell.to_string(),
                         )?;
                     }
                 }
@@ -0,0 +1,137 @@
+//! MGI DNBSEQ barcode lists, as taken by splitBarcode and the instrument's
+//! barcode splitting: one barcode per line, `name barcode` for single-barcode
+//! runs or `name barcode barcode2` for dual-barcode runs, separated by tabs
+//! or spaces. `#` lines are comments.
+//!
+//! DNBSEQ reads barcodes from the other strand than Illumina-style listings
+//! give them, so the orientation table is per barcode and per splitting mode
+//! rather than per i5 workflow.
+
+use std::fmt;
+
+use crate::instrument::IndexRole;
+use crate::kits::KitError;
+use crate::{Operation, reverse_complement, validate_sequence};
+
+/// Whether a run splits on one barcode or on two.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BarcodeMode {
+    Single,
+    Dual,
+}
+
+impl BarcodeMode {
+    /// The orientation table: what has to be done to a forward-strand index
+    /// of `role` for the DNBSEQ to find it, or `None` when the mode does not
+    /// read that index. Barcode 1 (i7) is always read as its reverse
+    /// complement; barcode 2 (i5) is only read in dual-barcode splitting, and
+    /// then as entered.
+    pub fn operation(self, role: IndexRole) -> Option<Operation> {
+        match (self, role) {
+            (_, IndexRole::I7) => Some(Operation::ReverseComplement),
+            (BarcodeMode::Dual, IndexRole::I5) => Some(Operation::Passthrough),
+            (BarcodeMode::Single, IndexRole::I5) => None,
+        }
+    }
+
+    /// A forward-strand index of `role` as it goes in the barcode list.
+    pub fn barcode(self, role: IndexRole, forward: &str) -> Option<String> {
+        self.operation(role).map(|operation| match operation {
+            Operation::ReverseComplement => reverse_complement(forward),
+            _ => forward.to_string(),
+        })
+    }
+}
+
+impl fmt::Display for BarcodeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BarcodeMode::Single => f.write_str("single barcode"),
+            BarcodeMode::Dual => f.write_str("dual barcode"),
+        }
+    }
+}
+
+/// One line of a barcode list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MgiBarcode {
+    pub name: String,
+    pub barcode: String,
+    /// Only set in dual-barcode lists.
+    pub barcode2: Option<String>,
+}
+
+/// The splitting mode of a barcode list: dual when its lines have a second barcode.
+pub fn barcode_mode(barcodes: &[MgiBarcode]) -> BarcodeMode {
+    if barcodes.iter().any(|barcode| barcode.barcode2.is_some()) {
+        BarcodeMode::Dual
+    } else {
+        BarcodeMode::Single
+    }
+}
+
+/// Parses a barcode list. Sequences are validated and stored uppercase;
+/// single and dual lines cannot be mixed.
+pub fn parse_barcodes(text: &str) -> Result<Vec<MgiBarcode>, KitError> {
+    let mut barcodes: Vec<MgiBarcode> = Vec::new();
+    for (line, barcode) in read_barcodes(text)? {
+        let error = |e| KitError { line: Some(line), message: format!("barcode {}", e) };
+        validate_sequence(&barcode.barcode).map_err(error)?;
+        if let Some(barcode2) = &barcode.barcode2 {
+            validate_sequence(barcode2).map_err(error)?;
+        }
+        barcodes.push(barcode);
+    }
+    Ok(barcodes)
+}
+
+/// Like [`parse_barcodes`], but leaves the barcodes' bases unchecked and
+/// gives each barcode's 1-based line number, so that every invalid barcode
+/// can be reported with its line.
+pub fn read_barcodes(text: &str) -> Result<Vec<(usize, MgiBarcode)>, KitError> {
+    let mut barcodes: Vec<(usize, MgiBarcode)> = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let line = line.trim_start_matches('\u{feff}').trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let error = |message: String| KitError { line: Some(n + 1), message };
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let barcode = match fields.as_slice() {
+            [name, barcode] => MgiBarcode {
+                name: name.to_string(),
+                barcode: barcode.to_ascii_uppercase(),
+                barcode2: None,
+            },
+            [name, barcode, barcode2] => MgiBarcode {
+                name: name.to_string(),
+                barcode: barcode.to_ascii_uppercase(),
+                barcode2: Some(barcode2.to_ascii_uppercase()),
+            },
+            _ => return Err(error(format!("expected 'name barcode [barcode2]', found '{}'", line))),
+        };
+        if let Some((_, first)) = barcodes.first()
+            && first.barcode2.is_some() != barcode.barcode2.is_some()
+        {
+            return Err(error("single and dual barcode lines are mixed".to_string()));
+        }
+        barcodes.push((n + 1, barcode));
+    }
+    Ok(barcodes)
+}
+
+/// Writes a barcode list with tab-separated fields.
+pub fn write_barcodes(barcodes: &[MgiBarcode]) -> String {
+    let mut text = String::new();
+    for barcode in barcodes {
+        text.push_str(&barcode.name);
+        text.push('\t');
+        text.push_str(&barcode.barcode);
+        if let Some(barcode2) = &barcode.barcode2 {
+            text.push('\t');
+            text.push_str(barcode2);
+        }
+        text.push('\n');
+    }
+    text
+}
@@ -0,0 +1,206 @@
+//! Plate maps: the index sequences in each well of an index plate, used to
+//! fill in the indexes of LIMS exports that only carry `Plate` and `Well`.
+//!
+//! A plate map is a CSV with a `Well` column, an optional `Plate` column, an
+//! optional `index_name` (or `Name`) column and i7 / i5 columns named as in
+//! sample sheets (`index` / `index2`, `i7` / `i5`, ...). As in kit files, i5
+//! is listed for the forward strand unless `# orientation: reverse-complemented`
+//! says otherwise, and is always stored forward. A kit with wells can stand in
+//! for a plate map; its wells match on every plate.
+
+use std::path::Path;
+
+use csv::ReaderBuilder;
+
+use crate::instrument::IndexRole;
+use crate::kits::{KitDatabase, KitError};
+use crate::{Orientation, reverse_complement, validate_sequence};
+
+/// A well such as `A1`, `a01` or `H 12` as `A1`, for plates of up to 16 rows
+/// (`A`-`P`) and 24 columns.
+pub fn normalize_well(value: &str) -> Option<String> {
+    let value: String = value.chars().filter(|c| !c.is_whitespace()).collect();
+    let mut chars = value.chars();
+    let row = chars.next()?.to_ascii_uppercase();
+    let column: u8 = chars.as_str().parse().ok()?;
+    if ('A'..='P').contains(&row) && (1..=24).contains(&column) {
+        Some(format!("{}{}", row, column))
+    } else {
+        None
+    }
+}
+
+/// The indexes in one well. i5 is stored forward; either read may be empty.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlateWell {
+    /// `None` when the well matches on every plate.
+    pub plate: Option<String>,
+    pub well: String,
+    pub name: Option<String>,
+    pub i7: String,
+    pub i5: String,
+}
+
+impl PlateWell {
+    /// The well with its plate, e.g. `Plate1 A1`, for reports.
+    pub fn label(&self) -> String {
+        match &self.plate {
+            Some(plate) => format!("{} {}", plate, self.well),
+            None => self.well.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PlateMap {
+    wells: Vec<PlateWell>,
+}
+
+impl PlateMap {
+    /// Parses a plate map. Wells are normalized and sequences validated and
+    /// stored uppercase.
+    pub fn from_csv(text: &str) -> Result<PlateMap, KitError> {
+        let error = |line: Option<usize>, message: String| KitError { line, message };
+        let orientation = text
+            .lines()
+            .find_map(|line| {
+                line.trim()
+                    .strip_prefix('#')
+                    .and_then(|comment| comment.trim().strip_prefix("orientation"))
+                    .and_then(|rest| rest.trim_start().strip_prefix(':'))
+            })
+            .map(|value| value.trim().parse::<Orientation>().map_err(|e| error(None, e)))
+            .transpose()?
+            .unwrap_or(Orientation::Forward);
+
+        let mut reader = ReaderBuilder::new()
+            .comment(Some(b'#'))
+            .trim(csv::Trim::All)
+            .from_reader(text.as_bytes());
+        let headers = reader.headers().map_err(|e| error(None, e.to_string()))?.clone();
+        let column = |names: &[&str]| headers.iter().position(|h| names.iter().any(|name| h.eq_ignore_ascii_case(name)));
+        let role_column = |role: IndexRole| headers.iter().position(|h| IndexRole::from_column(h) == Some(role));
+        let well_col = column(&["well"]).ok_or_else(|| error(None, "missing column 'well'".to_string()))?;
+        let plate_col = column(&["plate"]);
+        let name_col = column(&["index_name", "name"]);
+        let (i7_col, i5_col) = (role_column(IndexRole::I7), role_column(IndexRole::I5));
+        if i7_col.is_none() && i5_col.is_none() {
+            return Err(error(None, "missing an i7 or i5 column".to_string()));
+        }
+
+        let mut wells = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| error(e.position().map(|p| p.line() as usize), e.to_string()))?;
+            let line = record.position().map(|p| p.line() as usize);
+            let field = |col: Option<usize>| col.and_then(|col| record.get(col)).unwrap_or("").to_string();
+            let well = normalize_well(&field(Some(well_col)))
+                .ok_or_else(|| error(line, format!("'{}' is not a plate well", field(Some(well_col)))))?;
+            let sequence = |read: IndexRole, col: Option<usize>| {
+                let sequence = field(col).to_ascii_uppercase();
+                validate_sequence(&sequence).map_err(|e| error(line, format!("{} {}", read, e)))?;
+                Ok(sequence)
+            };
+            let i7 = sequence(IndexRole::I7, i7_col)?;
+            let mut i5 = sequence(IndexRole::I5, i5_col)?;
+            if orientation == Orientation::ReverseComplement {
+                i5 = reverse_complement(&i5);
+            }
+            if i7.is_empty() && i5.is_empty() {
+                return Err(error(line, format!("well {} has neither an i7 nor an i5 sequence", well)));
+            }
+            let optional = |col: Option<usize>| Some(field(col)).filter(|value| !value.is_empty());
+            wells.push(PlateWell {
+                plate: optional(plate_col),
+                well,
+                name: optional(name_col),
+                i7,
+                i5,
+            });
+        }
+        Ok(PlateMap { wells })
+    }
+
+    /// Reads a plate map file; errors name the file.
+    pub fn from_path(path: &Path) -> Result<PlateMap, KitError> {
+        let text = std::fs::read_to_string(path).map_err(|e| KitError {
+            line: None,
+            message: format!("{}: {}", path.display(), e),
+        })?;
+        PlateMap::from_csv(&text).map_err(|e| KitError {
+            line: None,
+            message: format!("{}: {}", path.display(), e),
+        })
+    }
+
+    /// The wells of `kit` (matched ignoring ASCII case), or `None` when the
+    /// kit is unknown or has no wells.
+    pub fn from_kit(kits: &KitDatabase, kit: &str) -> Option<PlateMap> {
+        let mut wells: Vec<PlateWell> = Vec::new();
+        for index in kits.indexes().iter().filter(|index| index.kit.eq_ignore_ascii_case(kit.trim())) {
+            let Some(well) = index.well.as_deref().and_then(normalize_well) else {
+                continue;
+            };
+            let position = match wells.iter().position(|known| known.well == well) {
+                Some(position) => position,
+                None => {
+                    wells.push(PlateWell {
+                        plate: None,
+                        well,
+                        name: Some(index.name.clone()),
+                        i7: String::new(),
+                        i5: String::new(),
+                    });
+                    wells.len() - 1
+                }
+            };
+            match index.read {
+                IndexRole::I7 => wells[position].i7 = index.sequence.clone(),
+                IndexRole::I5 => wells[position].i5 = index.sequence.clone(),
+            }
+        }
+        if wells.is_empty() { None } else { Some(PlateMap { wells }) }
+    }
+
+    pub fn wells(&self) -> &[PlateWell] {
+        &self.wells
+    }
+
+    /// The number of distinct well positions listed, on any plate.
+    pub fn well_count(&self) -> usize {
+        let mut wells: Vec<&str> = Vec::new();
+        for well in &self.wells {
+            if !wells.contains(&well.well.as_str()) {
+                wells.push(&well.well);
+            }
+        }
+        wells.len()
+    }
+
+    /// Wells listed more than once, as labels such as `Plate1 A1`.
+    pub fn duplicates(&self) -> Vec<String> {
+        let mut duplicates: Vec<String> = Vec::new();
+        for (n, well) in self.wells.iter().enumerate() {
+            let label = well.label();
+            let repeated = self.wells[..n].iter().any(|earlier| earlier.label().eq_ignore_ascii_case(&label));
+            if repeated && !duplicates.contains(&label) {
+                duplicates.push(label);
+            }
+        }
+        duplicates
+    }
+
+    /// The well at `well` of `plate`. A map well on the same plate wins over
+    /// one without a plate, which matches on every plate; a sample without a
+    /// plate only matches map wells without one.
+    pub fn get(&self, plate: Option<&str>, well: &str) -> Option<&PlateWell> {
+        let well = normalize_well(well)?;
+        let plate = plate.map(str::trim).filter(|plate| !plate.is_empty());
+        let at_well = || self.wells.iter().filter(|known| known.well == well);
+        at_well()
+            .find(|known| match (&known.plate, plate) {
+                (Some(known), Some(plate)) => known.eq_ignore_ascii_case(plate),
+                _ => false,
+            })
+            .or_else(|| at_well().find(|known| known.plate.is_none()))
+    }
+}
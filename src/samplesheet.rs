@@ -0,0 +1,345 @@
+//! Illumina sample sheets: `[Section]` blocks of CSV lines such as `[Header]`,
+//! `[Reads]`, `[Settings]` and `[Data]` (v1), or `[BCLConvert_Settings]`,
+//! `[BCLConvert_Data]` and application sections like `[Cloud_Data]` (v2).
+//! Element AVITI run manifests (`RunManifest.csv`, with `[RUNVALUES]`,
+//! `[SETTINGS]` and `[SAMPLES]`) share the layout. Lines are kept verbatim;
+//! only table rows whose fields are changed are written back re-serialized.
+
+use std::fmt;
+
+use csv::{QuoteStyle, ReaderBuilder, WriterBuilder};
+
+const BOM: char = '\u{feff}';
+
+/// True when `text` looks like a sample sheet, i.e. its first non-blank line is a `[Section]` header.
+pub fn is_sample_sheet(text: &str) -> bool {
+    text.lines()
+        .map(|line| line.trim_start_matches(BOM))
+        .find(|line| !is_blank(line))
+        .is_some_and(|line| section_name(line).is_some())
+}
+
+/// A blank line, including the all-comma lines spreadsheet exports leave behind.
+fn is_blank(line: &str) -> bool {
+    line.trim().chars().all(|c| c == ',')
+}
+
+/// The name of a `[Section]` header line such as `[Data]` or `[Data],,,`.
+fn section_name(line: &str) -> Option<&str> {
+    let first = line.trim_start_matches(BOM).split(',').next()?.trim();
+    first.strip_prefix('[')?.strip_suffix(']').map(str::trim)
+}
+
+fn split_fields(line: &str) -> Vec<String> {
+    let mut reader = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(line.as_bytes());
+    match reader.records().next() {
+        Some(Ok(record)) => record.iter().map(str::to_string).collect(),
+        _ => vec![line.to_string()],
+    }
+}
+
+fn join_fields(fields: &[String]) -> String {
+    let mut writer = WriterBuilder::new()
+        .quote_style(QuoteStyle::Necessary)
+        .terminator(csv::Terminator::Any(b'\n'))
+        .from_writer(Vec::new());
+    // Writing to a Vec cannot fail
+    writer.write_record(fields).expect("write to memory");
+    let bytes = writer.into_inner().expect("flush to memory");
+    String::from_utf8_lossy(&bytes).trim_end_matches('\n').to_string()
+}
+
+/// One `[Name]` section and the lines that follow it, up to the next section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    name: String,
+    /// The header line exactly as read, e.g. `[Data],,,,`.
+    header: String,
+    /// 1-based line number of the header line in the sheet.
+    line_number: usize,
+    lines: Vec<String>,
+}
+
+impl Section {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    /// The value of a `Key,Value` line, as in `[Header]` or `[BCLConvert_Settings]`.
+    /// Keys are matched ignoring ASCII case.
+    pub fn value(&self, key: &str) -> Option<String> {
+        self.lines.iter().find_map(|line| {
+            let mut fields = split_fields(line).into_iter();
+            let found = fields.next()?;
+            if found.trim().eq_ignore_ascii_case(key) {
+                Some(fields.next().unwrap_or_default().trim().to_string())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Reads the section as a table whose first non-blank line holds the
+    /// column names, as in `[Data]`. Blank lines are skipped.
+    pub fn table(&self) -> Option<Table> {
+        let mut lines = self
+            .lines
+            .iter()
+            .enumerate()
+            .filter(|(_, line)| !is_blank(line));
+        let (_, header) = lines.next()?;
+        let columns = split_fields(header).into_iter().map(|c| c.trim().to_string()).collect();
+        let rows = lines
+            .map(|(index, line)| TableRow {
+                index,
+                line_number: self.line_number + index + 1,
+                fields: split_fields(line),
+            })
+            .collect();
+        Some(Table { columns, rows })
+    }
+
+    /// Writes `row`'s fields back, but only if they differ from the line as
+    /// read, so unchanged rows keep their original quoting and spacing.
+    pub fn update_row(&mut self, row: &TableRow) {
+        if let Some(line) = self.lines.get_mut(row.index)
+            && split_fields(line) != row.fields
+        {
+            *line = join_fields(&row.fields);
+        }
+    }
+
+    /// Replaces `row`'s line with one line per entry of `copies`, e.g. a sample
+    /// repeated once per oligo of a pooled index. The lines after it move, so
+    /// rows read before are replaced last to first.
+    pub fn replace_row(&mut self, row: &TableRow, copies: &[Vec<String>]) {
+        if row.index < self.lines.len() {
+            let lines: Vec<String> = copies.iter().map(|fields| join_fields(fields)).collect();
+            self.lines.splice(row.index..=row.index, lines);
+        }
+    }
+
+    /// Drops the table rows for which `keep` returns false, leaving the
+    /// column header, blank lines and every other line in place.
+    pub fn retain_rows(&mut self, mut keep: impl FnMut(&TableRow) -> bool) {
+        let Some(table) = self.table() else {
+            return;
+        };
+        let dropped: Vec<usize> = table.rows.iter().filter(|row| !keep(row)).map(|row| row.index).collect();
+        let mut index = 0;
+        self.lines.retain(|_| {
+            index += 1;
+            !dropped.contains(&(index - 1))
+        });
+    }
+}
+
+/// The column names and rows of a table section.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<TableRow>,
+}
+
+impl Table {
+    /// Position of the column named `name`, ignoring ASCII case.
+    pub fn column(&self, name: &str) -> Option<usize> {
+        self.columns.iter().position(|c| c.eq_ignore_ascii_case(name))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableRow {
+    /// Position of the row's line within its section.
+    index: usize,
+    line_number: usize,
+    pub fields: Vec<String>,
+}
+
+impl TableRow {
+    /// 1-based line number of the row in the sheet.
+    pub fn line_number(&self) -> usize {
+        self.line_number
+    }
+
+    pub fn get(&self, column: usize) -> Option<&str> {
+        self.fields.get(column).map(String::as_str)
+    }
+}
+
+/// Sample sheet layout generation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SheetVersion {
+    /// `[Data]` with `index` / `index2` columns (bcl2fastq, IEM).
+    V1,
+    /// `[BCLConvert_Data]` with `Index` / `Index2` columns.
+    V2,
+    /// Element AVITI run manifest: `[SAMPLES]` with `Index1` / `Index2` columns.
+    Aviti,
+}
+
+impl SheetVersion {
+    /// Name of the section holding the sample table.
+    pub fn data_section(self) -> &'static str {
+        match self {
+            SheetVersion::V1 => "Data",
+            SheetVersion::V2 => "BCLConvert_Data",
+            SheetVersion::Aviti => "SAMPLES",
+        }
+    }
+
+    /// Names of the i7 and i5 index columns in the sample table.
+    pub fn index_columns(self) -> (&'static str, &'static str) {
+        match self {
+            SheetVersion::V1 => ("index", "index2"),
+            SheetVersion::V2 => ("Index", "Index2"),
+            SheetVersion::Aviti => ("Index1", "Index2"),
+        }
+    }
+
+    /// Name of the sample name column in the sample table.
+    pub fn sample_column(self) -> &'static str {
+        match self {
+            SheetVersion::V1 | SheetVersion::V2 => "Sample_ID",
+            SheetVersion::Aviti => "SampleName",
+        }
+    }
+}
+
+impl fmt::Display for SheetVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SheetVersion::V1 => f.write_str("v1"),
+            SheetVersion::V2 => f.write_str("v2"),
+            SheetVersion::Aviti => f.write_str("AVITI run manifest"),
+        }
+    }
+}
+
+/// Index lengths in an `OverrideCycles` value such as `Y151;I8N2;N10;Y151`:
+/// the number of `I` cycles of each index read (a read without `Y` cycles),
+/// in read order. A masked index read counts as 0.
+pub fn override_index_cycles(override_cycles: &str) -> Result<Vec<usize>, String> {
+    let mut index_cycles = Vec::new();
+    for read in override_cycles.split(';').map(str::trim).filter(|r| !r.is_empty()) {
+        let invalid = || format!("invalid OverrideCycles read '{}'", read);
+        let mut is_template = false;
+        let mut indexed = 0;
+        let mut chars = read.chars().peekable();
+        while let Some(kind) = chars.next() {
+            let mut digits = String::new();
+            while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+                digits.push(digit);
+            }
+            // A letter without a count covers the rest of the read
+            let count: usize = if digits.is_empty() { 0 } else { digits.parse().map_err(|_| invalid())? };
+            match kind.to_ascii_uppercase() {
+                'Y' => is_template = true,
+                'I' => indexed += count,
+                'U' | 'N' => {}
+                _ => return Err(invalid()),
+            }
+        }
+        if !is_template {
+            index_cycles.push(indexed);
+        }
+    }
+    Ok(index_cycles)
+}
+
+/// A parsed sample sheet. Displaying it writes the sheet back out, with the
+/// original line endings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SampleSheet {
+    /// Lines before the first section header.
+    preamble: Vec<String>,
+    sections: Vec<Section>,
+    line_ending: &'static str,
+    trailing_newline: bool,
+}
+
+impl SampleSheet {
+    pub fn parse(text: &str) -> SampleSheet {
+        let line_ending = if text.contains("\r\n") { "\r\n" } else { "\n" };
+        let mut preamble = Vec::new();
+        let mut sections: Vec<Section> = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            if let Some(name) = section_name(line) {
+                sections.push(Section {
+                    name: name.to_string(),
+                    header: line.to_string(),
+                    line_number: n + 1,
+                    lines: Vec::new(),
+                });
+            } else {
+                match sections.last_mut() {
+                    Some(section) => section.lines.push(line.to_string()),
+                    None => preamble.push(line.to_string()),
+                }
+            }
+        }
+        SampleSheet {
+            preamble,
+            sections,
+            line_ending,
+            trailing_newline: text.ends_with('\n'),
+        }
+    }
+
+    /// An AVITI run manifest when there is a `[SAMPLES]` section; otherwise V2
+    /// when `[Header]` says `FileFormatVersion,2` or there is a `[BCLConvert_Data]` section.
+    pub fn version(&self) -> SheetVersion {
+        if self.section("SAMPLES").is_some() {
+            return SheetVersion::Aviti;
+        }
+        let declared_v2 = self
+            .section("Header")
+            .and_then(|header| header.value("FileFormatVersion"))
+            .is_some_and(|version| version == "2");
+        if declared_v2 || self.section("BCLConvert_Data").is_some() {
+            SheetVersion::V2
+        } else {
+            SheetVersion::V1
+        }
+    }
+
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// The first section called `name`, ignoring ASCII case.
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|s| s.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn section_mut(&mut self, name: &str) -> Option<&mut Section> {
+        self.sections.iter_mut().find(|s| s.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for SampleSheet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let lines = self.preamble.iter().chain(
+            self.sections
+                .iter()
+                .flat_map(|section| std::iter::once(&section.header).chain(&section.lines)),
+        );
+        for (n, line) in lines.enumerate() {
+            if n > 0 {
+                f.write_str(self.line_ending)?;
+            }
+            f.write_str(line)?;
+        }
+        if self.trailing_newline {
+            f.write_str(self.line_ending)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::{Alphabet, Operation, RcOptions, SequenceError, apply_operation, validate_sequence};
+
+/// Strand orientation of an index relative to the sequence it was read as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Orientation {
+    Forward,
+    ReverseComplement,
+}
+
+impl Orientation {
+    /// The orientation after reverse complementing once more.
+    pub fn flipped(self) -> Orientation {
+        match self {
+            Orientation::Forward => Orientation::ReverseComplement,
+            Orientation::ReverseComplement => Orientation::Forward,
+        }
+    }
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Orientation::Forward => f.write_str("forward"),
+            Orientation::ReverseComplement => f.write_str("reverse-complemented"),
+        }
+    }
+}
+
+impl FromStr for Orientation {
+    type Err = String;
+
+    /// Accepts `forward` or `reverse-complemented` (also `reverse-complement` and `rc`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "forward" => Ok(Orientation::Forward),
+            "reverse-complemented" | "reverse-complement" | "rc" => Ok(Orientation::ReverseComplement),
+            other => Err(format!(
+                "unknown orientation '{}' (expected forward or reverse-complemented)",
+                other
+            )),
+        }
+    }
+}
+
+/// A nucleotide sequence whose characters have been checked against the IUPAC alphabet.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DnaSequence {
+    bases: String,
+    alphabet: Alphabet,
+}
+
+impl DnaSequence {
+    /// Validates `bases`; the alphabet is detected from the presence of `U`.
+    pub fn new(bases: impl Into<String>) -> Result<Self, SequenceError> {
+        let bases = bases.into();
+        validate_sequence(&bases)?;
+        let alphabet = Alphabet::Auto.resolve(&bases);
+        Ok(DnaSequence { bases, alphabet })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.bases
+    }
+
+    pub fn len(&self) -> usize {
+        self.bases.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bases.is_empty()
+    }
+
+    /// Either [`Alphabet::Dna`] or [`Alphabet::Rna`], never `Auto`.
+    pub fn alphabet(&self) -> Alphabet {
+        self.alphabet
+    }
+
+    pub fn reverse_complement(&self) -> DnaSequence {
+        self.reverse_complement_with(&RcOptions {
+            alphabet: self.alphabet,
+            ..RcOptions::default()
+        })
+    }
+
+    pub fn reverse_complement_with(&self, options: &RcOptions) -> DnaSequence {
+        self.apply(Operation::ReverseComplement, options)
+    }
+
+    pub fn apply(&self, operation: Operation, options: &RcOptions) -> DnaSequence {
+        if operation == Operation::Passthrough {
+            return self.clone();
+        }
+        let bases = apply_operation(&self.bases, operation, options);
+        let alphabet = options.alphabet.resolve(&self.bases);
+        DnaSequence { bases, alphabet }
+    }
+}
+
+impl FromStr for DnaSequence {
+    type Err = SequenceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DnaSequence::new(s)
+    }
+}
+
+impl TryFrom<&str> for DnaSequence {
+    type Error = SequenceError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        DnaSequence::new(s)
+    }
+}
+
+impl AsRef<str> for DnaSequence {
+    fn as_ref(&self) -> &str {
+        &self.bases
+    }
+}
+
+impl fmt::Display for DnaSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.bases)
+    }
+}
+
+/// A non-empty index (barcode) sequence together with its orientation.
+///
+/// Indexes parsed from a sheet start out [`Orientation::Forward`]; each
+/// reverse complement flips the orientation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IndexSequence {
+    sequence: DnaSequence,
+    orientation: Orientation,
+}
+
+impl IndexSequence {
+    pub fn new(bases: impl Into<String>) -> Result<Self, SequenceError> {
+        Self::with_orientation(bases, Orientation::Forward)
+    }
+
+    pub fn with_orientation(
+        bases: impl Into<String>,
+        orientation: Orientation,
+    ) -> Result<Self, SequenceError> {
+        let sequence = DnaSequence::new(bases)?;
+        if sequence.is_empty() {
+            return Err(SequenceError::Empty);
+        }
+        Ok(IndexSequence {
+            sequence,
+            orientation,
+        })
+    }
+
+    pub fn sequence(&self) -> &DnaSequence {
+        &self.sequence
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.sequence.as_str()
+    }
+
+    pub fn len(&self) -> usize {
+        self.sequence.len()
+    }
+
+    /// Always false; kept alongside [`IndexSequence::len`] for API symmetry.
+    pub fn is_empty(&self) -> bool {
+        false
+    }
+
+    pub fn alphabet(&self) -> Alphabet {
+        self.sequence.alphabet()
+    }
+
+    pub fn orientation(&self) -> Orientation {
+        self.orientation
+    }
+
+    pub fn reverse_complement(&self) -> IndexSequence {
+        IndexSequence {
+            sequence: self.sequence.reverse_complement(),
+            orientation: self.orientation.flipped(),
+        }
+    }
+
+    pub fn reverse_complement_with(&self, options: &RcOptions) -> IndexSequence {
+        self.apply(Operation::ReverseComplement, options)
+    }
+
+    /// Applies `operation`. Only a reverse complement changes the strand, so
+    /// complement-only and reverse-only fixes keep the current orientation.
+    pub fn apply(&self, operation: Operation, options: &RcOptions) -> IndexSequence {
+        let orientation = match operation {
+            Operation::ReverseComplement => self.orientation.flipped(),
+            _ => self.orientation,
+        };
+        IndexSequence {
+            sequence: self.sequence.apply(operation, options),
+            orientation,
+        }
+    }
+}
+
+impl FromStr for IndexSequence {
+    type Err = SequenceError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        IndexSequence::new(s)
+    }
+}
+
+impl TryFrom<&str> for IndexSequence {
+    type Error = SequenceError;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        IndexSequence::new(s)
+    }
+}
+
+impl AsRef<str> for IndexSequence {
+    fn as_ref(&self) -> &str {
+        self.sequence.as_str()
+    }
+}
+
+impl fmt::Display for IndexSequence {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.sequence, f)
+    }
+}
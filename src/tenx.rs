@@ -0,0 +1,213 @@
+//! 10x Genomics sample index sets: names such as `SI-GA-A1` or `SI-TT-B3`
+//! that stand for a set of index sequences.
+//!
+//! Single-index sets (`SI-GA`, `SI-NA`) are four i7 oligos pooled in one
+//! well; dual-index sets (`SI-TT`, `SI-TN`, `SI-NN`) are one i7 and one i5.
+//! Tables are read in the CSV layouts 10x publishes: `name,oligo,oligo,...`
+//! lines without a header for single-index sets, and a header starting with
+//! `index_name` followed by `index(i7),index2_workflow_a(i5),index2_workflow_b(i5)`
+//! for dual-index sets. `#` lines are comments. i5 is stored as listed for
+//! the forward strand workflow (workflow A). The bundled sets are read from
+//! `data/tenx_single_index_sets.csv` and `data/tenx_dual_index_sets.csv`.
+
+use std::path::Path;
+
+use csv::ReaderBuilder;
+
+use crate::instrument::Workflow;
+use crate::kits::KitError;
+use crate::{reverse_complement, validate_sequence};
+
+const BUILTIN_SINGLE_INDEX_SETS: &str = include_str!("../data/tenx_single_index_sets.csv");
+const BUILTIN_DUAL_INDEX_SETS: &str = include_str!("../data/tenx_dual_index_sets.csv");
+
+/// The 10x set types. Each is a plate of [`SETS_PER_TYPE`] sets.
+pub const SET_TYPES: [&str; 5] = ["SI-GA", "SI-NA", "SI-TT", "SI-TN", "SI-NN"];
+pub const SETS_PER_TYPE: usize = 96;
+
+/// True when `value` is shaped like a 10x index set name: `SI-`, a two-letter
+/// set type, `-` and a plate well, e.g. `SI-GA-A1` or `si-tt-h12`.
+pub fn is_set_name(value: &str) -> bool {
+    let parts: Vec<&str> = value.trim().split('-').collect();
+    let [prefix, kind, well] = parts.as_slice() else {
+        return false;
+    };
+    let mut well = well.chars();
+    let row = well.next().map(|c| c.to_ascii_uppercase());
+    let column: String = well.collect();
+    prefix.eq_ignore_ascii_case("SI")
+        && kind.len() == 2
+        && kind.chars().all(|c| c.is_ascii_alphabetic())
+        && matches!(row, Some('A'..='H'))
+        && column.parse::<u8>().is_ok_and(|n| (1..=12).contains(&n))
+        && !column.starts_with('0')
+}
+
+/// The set type of a set name, e.g. `SI-TT` for `si-tt-b3`.
+pub fn set_type(name: &str) -> Option<String> {
+    if !is_set_name(name) {
+        return None;
+    }
+    name.trim().rsplit_once('-').map(|(kind, _)| kind.to_ascii_uppercase())
+}
+
+/// One 10x index set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TenxIndexSet {
+    /// Four i7 oligos that together make up one sample index.
+    Single { name: String, oligos: Vec<String> },
+    /// One i7 and one i5, with i5 as read on the forward strand workflow.
+    Dual { name: String, i7: String, i5: String },
+}
+
+impl TenxIndexSet {
+    pub fn name(&self) -> &str {
+        match self {
+            TenxIndexSet::Single { name, .. } | TenxIndexSet::Dual { name, .. } => name,
+        }
+    }
+
+    /// The set's i5 as read on `workflow`; `None` for single-index sets.
+    pub fn i5(&self, workflow: Workflow) -> Option<String> {
+        match self {
+            TenxIndexSet::Single { .. } => None,
+            TenxIndexSet::Dual { i5, .. } => Some(match workflow {
+                Workflow::Forward => i5.clone(),
+                Workflow::ReverseComplement => reverse_complement(i5),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TenxIndexSets {
+    sets: Vec<TenxIndexSet>,
+}
+
+impl TenxIndexSets {
+    /// The sets bundled with this build.
+    pub fn builtin() -> TenxIndexSets {
+        let mut sets = TenxIndexSets::from_csv(BUILTIN_SINGLE_INDEX_SETS).expect("bundled 10x single-index table is valid");
+        sets.extend(TenxIndexSets::from_csv(BUILTIN_DUAL_INDEX_SETS).expect("bundled 10x dual-index table is valid"));
+        sets
+    }
+
+    /// Parses a single- or dual-index table. Sequences are validated and stored uppercase.
+    pub fn from_csv(text: &str) -> Result<TenxIndexSets, KitError> {
+        let error = |line: Option<usize>, message: String| KitError { line, message };
+        let mut reader = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .comment(Some(b'#'))
+            .trim(csv::Trim::All)
+            .from_reader(text.as_bytes());
+        let mut records = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|e| error(e.position().map(|p| p.line() as usize), e.to_string()))?;
+            if record.iter().any(|field| !field.is_empty()) {
+                records.push(record);
+            }
+        }
+        let sequence = |line: Option<usize>, value: &str, what: &str| {
+            let value = value.to_ascii_uppercase();
+            validate_sequence(&value).map_err(|e| error(line, format!("{} {}", what, e)))?;
+            Ok(value)
+        };
+
+        let mut sets = Vec::new();
+        let dual_header = records.first().filter(|r| r.get(0).is_some_and(|f| f.eq_ignore_ascii_case("index_name")));
+        if let Some(header) = dual_header {
+            let column = |prefix: &str| header.iter().position(|h| h.to_ascii_lowercase().starts_with(prefix));
+            let i7_col = header
+                .iter()
+                .position(|h| matches!(h.to_ascii_lowercase().as_str(), "index" | "index(i7)"))
+                .ok_or_else(|| error(None, "missing column 'index(i7)'".to_string()))?;
+            let (a_col, b_col) = (column("index2_workflow_a"), column("index2_workflow_b"));
+            if a_col.is_none() && b_col.is_none() {
+                return Err(error(None, "missing column 'index2_workflow_a(i5)'".to_string()));
+            }
+            for record in &records[1..] {
+                let line = record.position().map(|p| p.line() as usize);
+                let field = |col: Option<usize>| col.and_then(|col| record.get(col)).filter(|f| !f.is_empty());
+                let name = record.get(0).unwrap_or("").to_string();
+                let i7 = field(Some(i7_col)).ok_or_else(|| error(line, format!("set '{}' has no i7", name)))?;
+                let i7 = sequence(line, i7, "i7")?;
+                let forward = field(a_col).map(|a| sequence(line, a, "i5")).transpose()?;
+                let reverse = field(b_col).map(|b| sequence(line, b, "i5")).transpose()?;
+                let i5 = match (forward, reverse) {
+                    (Some(a), Some(b)) if reverse_complement(&b) != a => {
+                        return Err(error(line, format!("set '{}': the workflow A and B i5 are not reverse complements", name)));
+                    }
+                    (Some(a), _) => a,
+                    (None, Some(b)) => reverse_complement(&b),
+                    (None, None) => return Err(error(line, format!("set '{}' has no i5", name))),
+                };
+                sets.push(TenxIndexSet::Dual { name, i7, i5 });
+            }
+        } else {
+            for record in &records {
+                let line = record.position().map(|p| p.line() as usize);
+                let name = record.get(0).unwrap_or("").to_string();
+                let oligos = record
+                    .iter()
+                    .skip(1)
+                    .filter(|field| !field.is_empty())
+                    .map(|oligo| sequence(line, oligo, "oligo"))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if oligos.is_empty() {
+                    return Err(error(line, format!("set '{}' has no sequences", name)));
+                }
+                sets.push(TenxIndexSet::Single { name, oligos });
+            }
+        }
+        for set in &sets {
+            if !is_set_name(set.name()) {
+                return Err(error(None, format!("'{}' is not a 10x index set name", set.name())));
+            }
+        }
+        Ok(TenxIndexSets { sets })
+    }
+
+    /// Reads an index set file; errors name the file.
+    pub fn from_path(path: &Path) -> Result<TenxIndexSets, KitError> {
+        let text = std::fs::read_to_string(path).map_err(|e| KitError {
+            line: None,
+            message: format!("{}: {}", path.display(), e),
+        })?;
+        TenxIndexSets::from_csv(&text).map_err(|e| KitError {
+            line: None,
+            message: format!("{}: {}", path.display(), e),
+        })
+    }
+
+    /// Adds the sets of `other`; a set already known is replaced.
+    pub fn extend(&mut self, other: TenxIndexSets) {
+        for set in other.sets {
+            self.sets.retain(|known| !known.name().eq_ignore_ascii_case(set.name()));
+            self.sets.push(set);
+        }
+    }
+
+    pub fn sets(&self) -> &[TenxIndexSet] {
+        &self.sets
+    }
+
+    /// The number of known sets of each type, in [`SET_TYPES`] order followed
+    /// by any other type a loaded table has.
+    pub fn coverage(&self) -> Vec<(String, usize)> {
+        let mut coverage: Vec<(String, usize)> = SET_TYPES.iter().map(|kind| (kind.to_string(), 0)).collect();
+        for kind in self.sets.iter().filter_map(|set| set_type(set.name())) {
+            match coverage.iter_mut().find(|(known, _)| *known == kind) {
+                Some((_, count)) => *count += 1,
+                None => coverage.push((kind, 1)),
+            }
+        }
+        coverage
+    }
+
+    /// The set called `name`, ignoring ASCII case and surrounding whitespace.
+    pub fn get(&self, name: &str) -> Option<&TenxIndexSet> {
+        let name = name.trim();
+        self.sets.iter().find(|set| set.name().eq_ignore_ascii_case(name))
+    }
+}
@@ -0,0 +1,35 @@
+use tracseq_rc::balance::{CycleBalance, at_risk_cycles, channels, cycle_balance};
+
+#[test]
+fn test_channels() {
+    assert_eq!(channels(b'A'), (true, true));
+    assert_eq!(channels(b'c'), (true, false));
+    assert_eq!(channels(b'T'), (false, true));
+    assert_eq!(channels(b'G'), (false, false));
+    assert_eq!(channels(b'N'), (false, false));
+}
+
+#[test]
+fn test_cycle_balance() {
+    let cycles = cycle_balance(["ACGT", "GGA"]);
+    assert_eq!(cycles.len(), 4);
+    assert_eq!(cycles[0], CycleBalance { cycle: 1, bases: 2, red: 1, green: 1 });
+    assert_eq!(cycles[3], CycleBalance { cycle: 4, bases: 1, red: 0, green: 1 });
+}
+
+#[test]
+fn test_at_risk_cycles() {
+    // Cycle 1 all G, cycle 2 only C and G, cycle 3 only T, cycle 4 balanced
+    let cycles = at_risk_cycles(["GCTA", "GGTC", "GCTT"]);
+    let problems: Vec<(usize, Option<&str>)> = cycles.iter().map(|c| (c.cycle, c.problem())).collect();
+    assert_eq!(
+        problems,
+        [
+            (1, Some("all G, no signal")),
+            (2, Some("no green signal, only G/C")),
+            (3, Some("no red signal, only G/T")),
+        ]
+    );
+    assert!(at_risk_cycles(["ACTA", "CAAT"]).is_empty());
+    assert!(at_risk_cycles([]).is_empty());
+}
@@ -0,0 +1,101 @@
+use tracseq_rc::cell::{CellFormat, CellParser};
+use tracseq_rc::{Operation, RcOptions, SequenceError};
+
+fn rc_cell(format: &str, cell: &str) -> String {
+    let parser: CellParser = format.parse().unwrap();
+    parser.parse(cell).unwrap().reverse_complement(&RcOptions::default()).to_string()
+}
+
+#[test]
+fn test_labeled_cells_keep_their_label() {
+    assert_eq!(rc_cell("label:-", "D701-ATTACTCG"), "D701-CGAGTAAT");
+    assert_eq!(rc_cell("label:_", "D701_ATTACTCG"), "D701_CGAGTAAT");
+    assert_eq!(rc_cell("label::", "D701:ATTACTCG"), "D701:CGAGTAAT");
+    assert_eq!(rc_cell("label: ", "D701 ATTACTCG"), "D701 CGAGTAAT");
+    // Only the first delimiter separates the label
+    assert_eq!(rc_cell("label:-", "Prefix-ATGC"), "Prefix-GCAT");
+    // No label present: the whole cell is the sequence
+    assert_eq!(rc_cell("label:-", "GCAT"), "ATGC");
+}
+
+#[test]
+fn test_parenthesized_cells() {
+    assert_eq!(rc_cell("paren", "D701 (ATTACTCG)"), "D701 (CGAGTAAT)");
+    assert_eq!(rc_cell("paren", "D701 ( ATTACTCG ) "), "D701 ( CGAGTAAT ) ");
+}
+
+#[test]
+fn test_combined_index_cells_flip_each_half() {
+    assert_eq!(rc_cell("sequence", "ACGT+TTGG"), "ACGT+CCAA");
+    assert_eq!(rc_cell("sequence", "ATTACTCG+TATAGCCT"), "CGAGTAAT+AGGCTATA");
+
+    let parser = CellParser::default();
+    let cell = parser.parse("ATTACTCG+TATAGCCT").unwrap();
+    let halves: Vec<&str> = cell.sequences().map(|index| index.as_str()).collect();
+    assert_eq!(halves, ["ATTACTCG", "TATAGCCT"]);
+
+    // Only flip the i5 half
+    let i5_only = cell.map_sequences(|n, index| if n == 1 { index.reverse_complement() } else { index.clone() });
+    assert_eq!(i5_only.to_string(), "ATTACTCG+AGGCTATA");
+    let each = cell.apply_each(&[Operation::Passthrough, Operation::ReverseComplement], &RcOptions::default());
+    assert_eq!(each, i5_only);
+
+    let no_split = CellParser::default().with_pair_separator(None);
+    assert!(no_split.parse("ACGT+TTGG").is_err());
+}
+
+#[test]
+fn test_regex_capture_groups() {
+    let format = r"regex:^(?:\w+) i7=([ACGTN]+) i5=([ACGTN]+)$";
+    assert_eq!(rc_cell(format, "UDI0001 i7=CCGCGGTT i5=AGCGCTAG"), "UDI0001 i7=AACCGCGG i5=CTAGCGCT");
+
+    // No match falls back to the whole cell
+    assert_eq!(rc_cell(format, "ACGT"), "ACGT");
+}
+
+#[test]
+fn test_cells_are_rebuilt_exactly() {
+    let parser = CellParser::new(CellFormat::Parenthesized);
+    for cell in ["D701 (ATTACTCG)", "  D702 (TCCGGAGA)", "x (ACGT+TTGG)"] {
+        assert_eq!(parser.parse(cell).unwrap().to_string(), cell);
+    }
+    let parsed = parser.parse("D701 (ATTACTCG)").unwrap();
+    assert_eq!(parsed.label(), Some("D701 ("));
+}
+
+#[test]
+fn test_error_offsets_are_relative_to_the_cell() {
+    let parser: CellParser = "label:-".parse().unwrap();
+    assert_eq!(
+        parser.parse("D701-ATTAXTCG"),
+        Err(SequenceError::InvalidBase { base: 'X', offset: 9 })
+    );
+    assert_eq!(
+        CellParser::default().parse("ACGT+TTXG"),
+        Err(SequenceError::InvalidBase { base: 'X', offset: 7 })
+    );
+    assert_eq!(CellParser::default().parse("ACGT+"), Err(SequenceError::Empty));
+}
+
+#[test]
+fn test_detect_cell_format() {
+    let detected = |samples: &[&str]| CellParser::detect(samples.iter().copied()).map(|p| p.to_string());
+    assert_eq!(detected(&["ATTACTCG", "", "TCCGGAGA"]).as_deref(), Some("sequence"));
+    assert_eq!(detected(&["ATTACTCG+TATAGCCT"]).as_deref(), Some("sequence"));
+    assert_eq!(detected(&["D701-ATTACTCG", "TCCGGAGA"]).as_deref(), Some("label:-"));
+    assert_eq!(detected(&["D701_ATTACTCG"]).as_deref(), Some("label:_"));
+    assert_eq!(detected(&["D701 (ATTACTCG)"]).as_deref(), Some("paren"));
+    assert_eq!(detected(&["D701 ATTACTCG"]).as_deref(), Some("label: "));
+    assert_eq!(detected(&["Sample_1", "Sample_2"]), None);
+    assert_eq!(detected(&["ACG"]), None);
+    assert_eq!(detected(&[]), None);
+}
+
+#[test]
+fn test_cell_format_parsing() {
+    assert_eq!("sequence".parse::<CellFormat>(), Ok(CellFormat::Sequence));
+    assert_eq!("label:_".parse::<CellFormat>(), Ok(CellFormat::Labeled { delimiter: '_' }));
+    assert!("label:--".parse::<CellFormat>().is_err());
+    assert!("regex:(".parse::<CellFormat>().is_err());
+    assert!("csv".parse::<CellFormat>().is_err());
+}
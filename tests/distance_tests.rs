@@ -0,0 +1,58 @@
+use tracseq_rc::distance::{
+    IndexedSample, edit_distance, find_collisions, hamming_distance, index_distance,
+};
+
+fn sample(name: &str, indexes: &[&str]) -> IndexedSample {
+    IndexedSample::new(name, indexes.iter().map(|index| index.to_string()).collect())
+}
+
+#[test]
+fn test_hamming_distance() {
+    assert_eq!(hamming_distance("ATTACTCG", "ATTACTCG"), Some(0));
+    assert_eq!(hamming_distance("ATTACTCG", "ATTACTCA"), Some(1));
+    assert_eq!(hamming_distance("acgt", "ACGA"), Some(1));
+    assert_eq!(hamming_distance("ACGT", "ACG"), None);
+    assert_eq!(hamming_distance("", ""), Some(0));
+}
+
+#[test]
+fn test_edit_distance() {
+    assert_eq!(edit_distance("ACGT", "ACGT"), 0);
+    assert_eq!(edit_distance("ACGT", "ACG"), 1);
+    assert_eq!(edit_distance("ACGT", "CGT"), 1);
+    assert_eq!(edit_distance("ACGT", "AGGT"), 1);
+    assert_eq!(edit_distance("ATTACTCG", "TTACTCGA"), 2);
+    assert_eq!(edit_distance("", "ACG"), 3);
+    // Equal lengths are compared position by position, so a one-base shift costs many mismatches
+    assert_eq!(index_distance("ATTACTCG", "TTACTCGA"), 7);
+    assert_eq!(index_distance("ATTACTCG", "ATTACTC"), 1);
+}
+
+#[test]
+fn test_find_collisions() {
+    let samples = [
+        sample("S1", &["ATTACTCG", "TATAGCCT"]),
+        sample("S2", &["ATTACTCG", "TATAGCCA"]),
+        sample("S3", &["TCCGGAGA", "ATAGAGGC"]),
+        sample("S4", &["", ""]),
+        sample("S5", &["ATTACTCG", "TATAGCCT"]),
+    ];
+    let collisions = find_collisions(&samples, 1);
+    let pairs: Vec<(&str, &str, usize)> = collisions
+        .iter()
+        .map(|c| (c.first.as_str(), c.second.as_str(), c.distance))
+        .collect();
+    assert_eq!(pairs, [("S1", "S2", 1), ("S1", "S5", 0), ("S2", "S5", 1)]);
+    assert!(collisions.iter().all(|c| !c.collides_after_rc));
+    assert!(find_collisions(&samples, 0).len() == 1);
+}
+
+#[test]
+fn test_palindromic_indexes_collide_after_rc() {
+    // ACGT is its own reverse complement, so flipping one sample does not help
+    let samples = [sample("A", &["ACGTACGT"]), sample("B", &["ACGTACGA"])];
+    let collisions = find_collisions(&samples, 1);
+    assert_eq!(collisions.len(), 1);
+    assert_eq!(collisions[0].rc_distance, 1);
+    assert!(collisions[0].collides_after_rc);
+}
@@ -0,0 +1,101 @@
+use tracseq_rc::export::{ExportRow, ExportTarget, export_sample_sheet, sample_id};
+use tracseq_rc::instrument::Workflow;
+use tracseq_rc::samplesheet::{SampleSheet, SheetVersion};
+
+fn row(sample: &str, lane: Option<&str>, i7: &[&str], i5: &str) -> ExportRow {
+    ExportRow {
+        sample: sample.to_string(),
+        lane: lane.map(str::to_string),
+        i7: i7.iter().map(|s| s.to_string()).collect(),
+        i5: i5.to_string(),
+        tenx_set: None,
+    }
+}
+
+#[test]
+fn test_export_target() {
+    assert_eq!("bcl-convert".parse::<ExportTarget>(), Ok(ExportTarget::BclConvert));
+    assert_eq!("cellranger".parse::<ExportTarget>(), Ok(ExportTarget::Mkfastq));
+    assert!("iem".parse::<ExportTarget>().is_err());
+    assert_eq!(sample_id(" Liver rep/1 "), "Liver_rep_1");
+}
+
+#[test]
+fn test_i5_orientation_per_target() {
+    let rows = [row("S1", None, &["ATTACTCG"], "TATAGCCT")];
+
+    // bcl2fastq wants index2 as read, so it needs the workflow
+    assert!(export_sample_sheet(ExportTarget::Bcl2fastq, &rows, None).is_err());
+    let v1 = export_sample_sheet(ExportTarget::Bcl2fastq, &rows, Some(Workflow::ReverseComplement)).unwrap();
+    assert!(v1.text.ends_with("[Data]\nSample_ID,index,index2\nS1,ATTACTCG,AGGCTATA\n"));
+    let v1 = export_sample_sheet(ExportTarget::Bcl2fastq, &rows, Some(Workflow::Forward)).unwrap();
+    assert!(v1.text.ends_with("S1,ATTACTCG,TATAGCCT\n"));
+
+    // BCL Convert follows the workflow too, and takes the forward strand without one
+    let v2 = export_sample_sheet(ExportTarget::BclConvert, &rows, Some(Workflow::ReverseComplement)).unwrap();
+    assert!(v2.text.starts_with("[Header]\nFileFormatVersion,2\n"));
+    assert!(v2.text.ends_with("[BCLConvert_Data]\nSample_ID,Index,Index2\nS1,ATTACTCG,AGGCTATA\n"));
+    let v2 = export_sample_sheet(ExportTarget::BclConvert, &rows, None).unwrap();
+    assert!(v2.text.ends_with("S1,ATTACTCG,TATAGCCT\n"));
+}
+
+#[test]
+fn test_lanes_and_pooled_oligos() {
+    let rows = [
+        row("S1", Some("1"), &["AAAA", "CCCC"], ""),
+        row("S2", None, &["GGGG"], ""),
+    ];
+    let v2 = export_sample_sheet(ExportTarget::BclConvert, &rows, None).unwrap();
+    assert!(v2.text.ends_with("Lane,Sample_ID,Index\n1,S1,AAAA\n1,S1,CCCC\n1,S2,GGGG\n"));
+}
+
+#[test]
+fn test_mkfastq_simple_csv() {
+    let mut tenx = row("S1", None, &["GTAACATGCG"], "AGTGTTACCT");
+    tenx.tenx_set = Some("SI-TT-A1".to_string());
+    let rows = [tenx, row("S2", Some("2"), &["ATTACTCG"], "TATAGCCT")];
+    let export = export_sample_sheet(ExportTarget::Mkfastq, &rows, None).unwrap();
+    assert_eq!(export.text, "Lane,Sample,Index\n2,S1,SI-TT-A1\n2,S2,ATTACTCG\n");
+    assert_eq!(export.warnings.len(), 1);
+}
+
+#[test]
+fn test_aviti_run_manifest() {
+    let rows = [row("S1", Some("1"), &["ATTACTCG"], "TATAGCCT"), row("S2", None, &["TCCGGAGA"], "ATAGAGGC")];
+    // The AVITI reads i5 as its reverse complement unless a workflow says otherwise
+    let export = export_sample_sheet(ExportTarget::Aviti, &rows, None).unwrap();
+    assert!(export.text.starts_with("[RUNVALUES]\nKeyName,Value\n\n[SETTINGS]\n"));
+    assert!(export.text.ends_with("[SAMPLES]\nSampleName,Index1,Index2,Lane\nS1,ATTACTCG,AGGCTATA,1\nS2,TCCGGAGA,GCCTCTAT,1\n"));
+    assert_eq!("runmanifest".parse::<ExportTarget>(), Ok(ExportTarget::Aviti));
+
+    // The manifest reads back as a run manifest for either workflow
+    for (workflow, index2) in [
+        (Workflow::ReverseComplement, ["AGGCTATA", "GCCTCTAT"]),
+        (Workflow::Forward, ["TATAGCCT", "ATAGAGGC"]),
+    ] {
+        let export = export_sample_sheet(ExportTarget::Aviti, &rows, Some(workflow)).unwrap();
+        let sheet = SampleSheet::parse(&export.text);
+        assert_eq!(sheet.version(), SheetVersion::Aviti);
+        assert_eq!(sheet.to_string(), export.text);
+        let table = sheet.section("SAMPLES").unwrap().table().unwrap();
+        let index2_col = table.column("Index2").unwrap();
+        let values: Vec<&str> = table.rows.iter().filter_map(|row| row.get(index2_col)).collect();
+        assert_eq!(values, index2, "{}", workflow);
+        let samples: Vec<&str> = table.rows.iter().filter_map(|row| row.get(table.column("SampleName").unwrap())).collect();
+        assert_eq!(samples, ["S1", "S2"]);
+    }
+}
+
+#[test]
+fn test_mgi_barcode_list() {
+    // Barcode 1 is reverse complemented for the DNBSEQ, barcode 2 is not
+    let rows = [row("S1", Some("1"), &["ATTACTCG"], "TATAGCCT"), row("S2", Some("2"), &["TCCGGAGA"], "")];
+    let export = export_sample_sheet(ExportTarget::Mgi, &rows, None).unwrap();
+    assert_eq!(export.text, "S1\tCGAGTAAT\tTATAGCCT\n");
+    assert_eq!(export.warnings.len(), 2);
+    assert_eq!(ExportTarget::Mgi.extension(), "txt");
+
+    let single = export_sample_sheet(ExportTarget::Mgi, &[row("S1", None, &["AAAA", "CCCC"], "")], None).unwrap();
+    assert_eq!(single.text, "S1\tTTTT\nS1\tGGGG\n");
+    assert!(single.warnings.is_empty());
+}
@@ -0,0 +1,86 @@
+use std::io::{Read, Write};
+
+use flate2::Compression;
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use tracseq_rc::fastx::{
+    FastxFormat, FastxOptions, FastxReader, reverse_complement_fastx, reverse_complement_records,
+};
+
+const FASTQ: &str = "@read1 1:N:0:ATTACTCG+TATAGCCT\nAACCGGTN\n+\nABCDEFGH\n@read2\nGATTACA\n+\nIIIII##\n";
+
+fn rc_records(input: &str, options: &FastxOptions) -> String {
+    let mut out = Vec::new();
+    reverse_complement_records(input.as_bytes(), &mut out, options).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn test_fastq_reverse_complement_reverses_qualities() {
+    let output = rc_records(FASTQ, &FastxOptions::default());
+    assert_eq!(
+        output,
+        "@read1 1:N:0:ATTACTCG+TATAGCCT\nNACCGGTT\n+\nHGFEDCBA\n@read2\nTGTAATC\n+\n##IIIII\n"
+    );
+}
+
+#[test]
+fn test_fastq_tag_is_appended_to_record_id() {
+    let options = FastxOptions {
+        tag: Some("/rc".to_string()),
+    };
+    let output = rc_records(FASTQ, &options);
+    assert!(output.starts_with("@read1/rc 1:N:0:ATTACTCG+TATAGCCT\n"));
+    assert!(output.contains("\n@read2/rc\n"));
+}
+
+#[test]
+fn test_fasta_keeps_line_wrapping() {
+    let input = ">chr1 test\nAAAACCCC\nGGGGTT\n>short\nACG\n";
+    let output = rc_records(input, &FastxOptions::default());
+    assert_eq!(output, ">chr1 test\nAACCCCGG\nGGTTTT\n>short\nCGT\n");
+}
+
+#[test]
+fn test_reader_detects_format_and_counts() {
+    let mut reader = FastxReader::new(FASTQ.as_bytes());
+    let records: Vec<_> = reader.by_ref().collect::<Result<_, _>>().unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(records[0].id(), "read1");
+    assert_eq!(reader.format(), Some(FastxFormat::Fastq));
+
+    let mut empty = FastxReader::new("".as_bytes());
+    assert!(empty.next().is_none());
+    assert_eq!(empty.format(), None);
+}
+
+#[test]
+fn test_malformed_fastq_is_an_error() {
+    let truncated = "@read1\nACGT\n+\nII\n";
+    let err = FastxReader::new(truncated.as_bytes()).next().unwrap().unwrap_err();
+    assert!(err.to_string().contains("quality length"));
+
+    let not_fastx = "ID,Index\n1,ACGT\n";
+    assert!(FastxReader::new(not_fastx.as_bytes()).next().unwrap().is_err());
+}
+
+#[test]
+fn test_gzip_input_and_output() -> Result<(), Box<dyn std::error::Error>> {
+    let dir = tempfile::tempdir()?;
+    let input = dir.path().join("reads.fastq.gz");
+    let output = dir.path().join("reads_rc.fastq.gz");
+
+    let mut encoder = GzEncoder::new(std::fs::File::create(&input)?, Compression::default());
+    encoder.write_all(FASTQ.as_bytes())?;
+    encoder.finish()?;
+
+    let summary = reverse_complement_fastx(Some(&input), Some(&output), &FastxOptions::default())?;
+    assert_eq!(summary.records, 2);
+    assert_eq!(summary.bases, 15);
+    assert_eq!(summary.format, Some(FastxFormat::Fastq));
+
+    let mut decoded = String::new();
+    MultiGzDecoder::new(std::fs::File::open(&output)?).read_to_string(&mut decoded)?;
+    assert_eq!(decoded, rc_records(FASTQ, &FastxOptions::default()));
+    Ok(())
+}
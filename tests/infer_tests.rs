@@ -0,0 +1,77 @@
+use std::io::Cursor;
+
+use tracseq_rc::Orientation;
+use tracseq_rc::fastx::FastxReader;
+use tracseq_rc::infer::{BarcodeCounts, I5Evidence, header_barcode};
+
+fn fastq(records: &[(&str, &str)]) -> FastxReader<Cursor<Vec<u8>>> {
+    let mut text = String::new();
+    for (header, seq) in records {
+        text.push_str(&format!("@{}\n{}\n+\n{}\n", header, seq, "I".repeat(seq.len())));
+    }
+    FastxReader::new(Cursor::new(text.into_bytes()))
+}
+
+#[test]
+fn test_header_barcode() {
+    assert_eq!(
+        header_barcode("M001:1:FC:1:1101:1:1 1:N:0:ATTACTCG+TATAGCCT"),
+        Some(("ATTACTCG".to_string(), "TATAGCCT".to_string()))
+    );
+    assert_eq!(
+        header_barcode("M001:1:FC:1:1101:1:1 1:N:0:attactcg"),
+        Some(("ATTACTCG".to_string(), String::new()))
+    );
+    // Sample numbers and missing comments are not barcodes
+    assert_eq!(header_barcode("M001:1:FC:1:1101:1:1 1:N:0:2"), None);
+    assert_eq!(header_barcode("M001:1:FC:1:1101:1:1"), None);
+}
+
+#[test]
+fn test_count_headers() {
+    let mut counts = BarcodeCounts::new();
+    let reader = fastq(&[
+        ("r1 1:N:0:ATTACTCG+AGGCTATA", "ACGT"),
+        ("r2 1:N:0:ATTACTCG+AGGCTATA", "ACGT"),
+        ("r3 1:N:0:TCCGGAGA+AGGCTATA", "ACGT"),
+        ("r4 1:N:0:1", "ACGT"),
+    ]);
+    assert_eq!(counts.add_headers(reader, 100).unwrap(), 4);
+    assert_eq!(counts.total(), 3);
+    assert_eq!(counts.count(Some("attactcg"), "AGGCTATA"), 2);
+    assert_eq!(counts.count(None, "AGGCTATA"), 3);
+    assert_eq!(counts.top(1), [("ATTACTCG", "AGGCTATA", 2)]);
+
+    let mut limited = BarcodeCounts::new();
+    let reader = fastq(&[("r1 1:N:0:ATTACTCG+AGGCTATA", "ACGT"), ("r2 1:N:0:ATTACTCG+AGGCTATA", "ACGT")]);
+    assert_eq!(limited.add_headers(reader, 1).unwrap(), 1);
+    assert_eq!(limited.total(), 1);
+}
+
+#[test]
+fn test_count_index_reads() {
+    let mut counts = BarcodeCounts::new();
+    let i1 = fastq(&[("r1", "ATTACTCG"), ("r2", "ATTACTCG")]);
+    let i2 = fastq(&[("r1", "AGGCTATA"), ("r2", "TATAGCCT")]);
+    assert_eq!(counts.add_index_reads(Some(i1), Some(i2), 100).unwrap(), 2);
+    assert_eq!(counts.count(Some("ATTACTCG"), "AGGCTATA"), 1);
+
+    // Without I1, only i5 is compared
+    let mut i5_only = BarcodeCounts::new();
+    let i2 = fastq(&[("r1", "AGGCTATA")]);
+    i5_only.add_index_reads(None::<FastxReader<Cursor<Vec<u8>>>>, Some(i2), 100).unwrap();
+    assert_eq!(i5_only.count(Some("ATTACTCG"), "AGGCTATA"), 1);
+}
+
+#[test]
+fn test_i5_evidence() {
+    let mut counts = BarcodeCounts::new();
+    counts.add("ATTACTCG", "AGGCTATA");
+    counts.add("ATTACTCG", "AGGCTATA");
+    counts.add("ATTACTCG", "TATAGCCT");
+    let evidence = counts.i5_evidence(Some("ATTACTCG"), "TATAGCCT");
+    assert_eq!(evidence, I5Evidence { as_written: 1, reverse_complement: 2 });
+    assert_eq!(evidence.verdict(), Some(Orientation::ReverseComplement));
+    assert_eq!(counts.i5_evidence(Some("ATTACTCG"), "AGGCTATA").verdict(), Some(Orientation::Forward));
+    assert_eq!(counts.i5_evidence(Some("TCCGGAGA"), "AGGCTATA").verdict(), None);
+}
@@ -0,0 +1,48 @@
+use tracseq_rc::Operation;
+use tracseq_rc::instrument::{IndexRole, Instrument, Workflow};
+
+#[test]
+fn test_orientation_table() {
+    for instrument in ["miseq", "hiseq2500", "novaseq6000-v1.0"] {
+        let instrument: Instrument = instrument.parse().unwrap();
+        assert_eq!(instrument.workflow(), Workflow::Forward, "{}", instrument);
+    }
+    for instrument in ["nextseq500", "NextSeq 2000", "miniseq", "novaseq6000-v1.5", "NovaSeq_X_Plus", "iseq", "Element AVITI"] {
+        let instrument: Instrument = instrument.parse().unwrap();
+        assert_eq!(instrument.workflow(), Workflow::ReverseComplement, "{}", instrument);
+    }
+    assert_eq!(Workflow::ReverseComplement.i5_operation(), Operation::ReverseComplement);
+    assert_eq!(Workflow::Forward.i5_operation(), Operation::Passthrough);
+}
+
+#[test]
+fn test_two_colour_instruments() {
+    assert!(Instrument::NextSeq500.is_two_colour());
+    assert!(Instrument::NovaSeqX.is_two_colour());
+    assert!(!Instrument::MiSeq.is_two_colour());
+    assert!(!Instrument::HiSeq4000.is_two_colour());
+}
+
+#[test]
+fn test_instrument_parsing() {
+    for instrument in Instrument::ALL {
+        assert_eq!(instrument.id().parse::<Instrument>(), Ok(instrument));
+    }
+    let err = "novaseq6000".parse::<Instrument>().unwrap_err();
+    assert!(err.contains("reagent kit"));
+    assert!("sanger".parse::<Instrument>().is_err());
+    assert_eq!("b".parse::<Workflow>(), Ok(Workflow::ReverseComplement));
+    assert_eq!("forward".parse::<Workflow>(), Ok(Workflow::Forward));
+}
+
+#[test]
+fn test_index_role_from_column() {
+    assert_eq!(IndexRole::from_column("Index 2"), Some(IndexRole::I5));
+    assert_eq!(IndexRole::from_column("Barcode2"), Some(IndexRole::I5));
+    assert_eq!(IndexRole::from_column("index2"), Some(IndexRole::I5));
+    assert_eq!(IndexRole::from_column("IndexNtSequence2"), Some(IndexRole::I5));
+    assert_eq!(IndexRole::from_column("i5"), Some(IndexRole::I5));
+    assert_eq!(IndexRole::from_column("Index"), Some(IndexRole::I7));
+    assert_eq!(IndexRole::from_column("IndexNtSequence"), Some(IndexRole::I7));
+    assert_eq!(IndexRole::from_column("Sample_ID"), None);
+}
@@ -1,7 +1,12 @@
 use calamine::{Reader, Xlsx};
 use rust_xlsxwriter::Workbook;
 use tempfile::NamedTempFile;
-use tracseq_rc::reverse_complement;
+use tracseq_rc::{
+    Alphabet, CaseMode, Operation, RcOptions, SequenceError, apply_operation, complement,
+    complement_byte, reverse_complement, reverse_complement_bytes, reverse_complement_in_place,
+    reverse_complement_into, reverse_complement_with, try_reverse_complement,
+    try_reverse_complement_with,
+};
 
 fn assert_reverse_complement(input: &str, expected: &str) {
     let result = reverse_complement(input);
@@ -21,6 +26,245 @@ fn test_reverse_complement() {
     assert_reverse_complement("ATGCATGC", "GCATGCAT");
 }
 
+#[test]
+fn test_iupac_complement() {
+    let pairs = [
+        ('A', 'T'),
+        ('G', 'C'),
+        ('R', 'Y'),
+        ('K', 'M'),
+        ('B', 'V'),
+        ('D', 'H'),
+        ('S', 'S'),
+        ('W', 'W'),
+        ('N', 'N'),
+    ];
+    for (base, expected) in pairs {
+        assert_eq!(complement(base), expected, "complement of '{}'", base);
+        assert_eq!(complement(expected), base, "complement of '{}'", expected);
+    }
+
+    assert_reverse_complement("ACGTRYKMSWBDHVN", "NBDHVWSKMRYACGT");
+    assert_reverse_complement("NNRYACGT", "ACGTRYNN");
+}
+
+#[test]
+fn test_iupac_reverse_complement_round_trip() {
+    for code in "ACGTRYKMSWBDHVN".chars() {
+        let single = code.to_string();
+        assert_eq!(reverse_complement(&reverse_complement(&single)), single);
+    }
+
+    let degenerate = "ACGTRYKMSWBDHVN";
+    assert_eq!(
+        reverse_complement(&reverse_complement(degenerate)),
+        degenerate
+    );
+}
+
+#[test]
+fn test_lowercase_reverse_complement_preserves_case() {
+    assert_reverse_complement("acgt", "acgt");
+    assert_reverse_complement("aaccg", "cggtt");
+    assert_reverse_complement("ACGTacgtNNnn", "nnNNacgtACGT");
+    assert_reverse_complement("ryKM", "KMry");
+    assert_eq!(complement('r'), 'y');
+    assert_eq!(complement('n'), 'n');
+}
+
+#[test]
+fn test_reverse_complement_uppercase_mode() {
+    let upper = RcOptions {
+        case: CaseMode::Upper,
+        ..RcOptions::default()
+    };
+    assert_eq!(reverse_complement_with("acgtTTgg", &upper), "CCAAACGT");
+    assert_eq!(
+        reverse_complement_with("acgtTTgg", &RcOptions::default()),
+        "ccAAacgt"
+    );
+}
+
+#[test]
+fn test_rna_reverse_complement() {
+    let rna = RcOptions {
+        alphabet: Alphabet::Rna,
+        ..RcOptions::default()
+    };
+    // Small-RNA 3' adapter
+    assert_eq!(
+        reverse_complement_with("UGGAAUUCUCGGGUGCCAAGG", &rna),
+        "CCUUGGCACCCGAGAAUUCCA"
+    );
+    assert_eq!(reverse_complement_with("acgu", &rna), "acgu");
+    // DNA input is written out as RNA
+    assert_eq!(reverse_complement_with("AACT", &rna), "AGUU");
+
+    // U in DNA mode pairs with A
+    assert_eq!(
+        reverse_complement_with("AACU", &RcOptions::default()),
+        "AGTT"
+    );
+}
+
+#[test]
+fn test_auto_alphabet_detection() {
+    let auto = RcOptions {
+        alphabet: Alphabet::Auto,
+        ..RcOptions::default()
+    };
+    assert_eq!(Alphabet::Auto.resolve("ACGU"), Alphabet::Rna);
+    assert_eq!(Alphabet::Auto.resolve("ACGT"), Alphabet::Dna);
+    assert_eq!(Alphabet::Auto.resolve("ACGN"), Alphabet::Dna);
+    assert_eq!(Alphabet::Dna.resolve("ACGU"), Alphabet::Dna);
+    assert_eq!(reverse_complement_with("GGAU", &auto), "AUCC");
+    assert_eq!(reverse_complement_with("GGAT", &auto), "ATCC");
+
+    assert_eq!("RNA".parse::<Alphabet>(), Ok(Alphabet::Rna));
+    assert!("protein".parse::<Alphabet>().is_err());
+}
+
+#[test]
+fn test_try_reverse_complement() {
+    assert_eq!(try_reverse_complement("ATGC"), Ok("GCAT".to_string()));
+    assert_eq!(try_reverse_complement(""), Ok(String::new()));
+    assert_eq!(try_reverse_complement("acgtRYN"), Ok("NRYacgt".to_string()));
+
+    // The lenient function passes '-' through; the validating one rejects it
+    assert_eq!(
+        try_reverse_complement("ATGC-N"),
+        Err(SequenceError::InvalidBase {
+            base: '-',
+            offset: 4
+        })
+    );
+    assert_eq!(
+        try_reverse_complement("AT GC"),
+        Err(SequenceError::InvalidBase {
+            base: ' ',
+            offset: 2
+        })
+    );
+    assert_eq!(
+        try_reverse_complement("1ACG"),
+        Err(SequenceError::InvalidBase {
+            base: '1',
+            offset: 0
+        })
+    );
+
+    let rna = RcOptions {
+        alphabet: Alphabet::Rna,
+        ..RcOptions::default()
+    };
+    assert_eq!(
+        try_reverse_complement_with("AACU", &rna),
+        Ok("AGUU".to_string())
+    );
+    assert!(try_reverse_complement_with("AAXU", &rna).is_err());
+}
+
+#[test]
+fn test_sequence_error_message() {
+    let err = try_reverse_complement("ACGT\t").unwrap_err();
+    assert_eq!(err.to_string(), "invalid character '\\t' at offset 4");
+}
+
+#[test]
+fn test_byte_complement_matches_char_complement() {
+    for byte in 0..=127u8 {
+        assert_eq!(
+            complement_byte(byte) as char,
+            complement(byte as char),
+            "complement of {:?}",
+            byte as char
+        );
+    }
+}
+
+#[test]
+fn test_byte_reverse_complement_variants() {
+    let input = "ACGTRYKMSWBDHVNacgtnU-";
+    let expected = reverse_complement(input);
+
+    let mut in_place = input.as_bytes().to_vec();
+    reverse_complement_in_place(&mut in_place);
+    assert_eq!(in_place, expected.as_bytes());
+
+    let mut out = vec![0u8; input.len()];
+    reverse_complement_into(input.as_bytes(), &mut out);
+    assert_eq!(out, expected.as_bytes());
+
+    assert_eq!(reverse_complement_bytes(b"AAACCG"), b"CGGTTT");
+    assert_eq!(reverse_complement_bytes(b""), b"");
+
+    // Odd length leaves the middle base complemented, not skipped
+    let mut odd = b"AGC".to_vec();
+    reverse_complement_in_place(&mut odd);
+    assert_eq!(odd, b"GCT");
+}
+
+#[test]
+#[should_panic(expected = "output buffer length")]
+fn test_reverse_complement_into_rejects_short_buffer() {
+    let mut out = [0u8; 3];
+    reverse_complement_into(b"ACGT", &mut out);
+}
+
+#[test]
+fn test_apply_operation() {
+    let options = RcOptions::default();
+    assert_eq!(
+        apply_operation("ACGTtn", Operation::ReverseComplement, &options),
+        "naACGT"
+    );
+    assert_eq!(
+        apply_operation("ACGTtn", Operation::Complement, &options),
+        "TGCAan"
+    );
+    assert_eq!(
+        apply_operation("ACGTtn", Operation::Reverse, &options),
+        "ntTGCA"
+    );
+    assert_eq!(
+        apply_operation("ACGTtn", Operation::Passthrough, &options),
+        "ACGTtn"
+    );
+
+    let upper_rna = RcOptions {
+        case: CaseMode::Upper,
+        alphabet: Alphabet::Rna,
+    };
+    assert_eq!(
+        apply_operation("acgt", Operation::Complement, &upper_rna),
+        "UGCA"
+    );
+    // Passthrough ignores case and alphabet options
+    assert_eq!(
+        apply_operation("acgt", Operation::Passthrough, &upper_rna),
+        "acgt"
+    );
+}
+
+#[test]
+fn test_operation_parsing() {
+    assert_eq!("rc".parse::<Operation>(), Ok(Operation::ReverseComplement));
+    assert_eq!(
+        "Reverse-Complement".parse::<Operation>(),
+        Ok(Operation::ReverseComplement)
+    );
+    assert_eq!("complement".parse::<Operation>(), Ok(Operation::Complement));
+    assert_eq!("reverse".parse::<Operation>(), Ok(Operation::Reverse));
+    assert_eq!("none".parse::<Operation>(), Ok(Operation::Passthrough));
+    assert_eq!(
+        "passthrough".parse::<Operation>(),
+        Ok(Operation::Passthrough)
+    );
+    assert!("flip".parse::<Operation>().is_err());
+    assert_eq!(Operation::default(), Operation::ReverseComplement);
+    assert_eq!(Operation::Passthrough.to_string(), "none");
+}
+
 #[test]
 fn test_excel_processing() -> Result<(), Box<dyn std::error::Error>> {
     // Create a temporary Excel file with test data
@@ -56,7 +300,7 @@ fn test_excel_processing() -> Result<(), Box<dyn std::error::Error>> {
 
         // Write header row
         for (col, cell) in header_row.iter().enumerate() {
-            output_sheet.write_string(0, col as u16, &cell.to_string())?;
+            output_sheet.write_string(0, col as u16, cell.to_string())?;
         }
 
         // Process rows
@@ -82,7 +326,7 @@ fn test_excel_processing() -> Result<(), Box<dyn std::error::Error>> {
                     output_sheet.write_string(
                         (row_idx + 1) as u32,
                         col_idx as u16,
-                        &cell.to_string(),
+                        cell.to_string(),
                     )?;
                 }
             }
@@ -0,0 +1,129 @@
+use tracseq_rc::Orientation;
+use tracseq_rc::instrument::IndexRole;
+use tracseq_rc::kits::KitDatabase;
+
+#[test]
+fn test_builtin_kits() {
+    let kits = KitDatabase::builtin();
+    assert_eq!(kits.version(), Some("2"));
+    assert_eq!(
+        kits.kits(),
+        ["IDT for Illumina UD Indexes", "TruSeq CD Indexes", "Nextera XT Index Kit v2"]
+    );
+    let udi = &kits.indexes()[..2];
+    assert_eq!(udi[0].name, "UDI0001");
+    assert_eq!(udi[0].well.as_deref(), Some("A1"));
+    assert_eq!((udi[0].read, udi[0].sequence.as_str()), (IndexRole::I7, "CCGCGGTT"));
+    assert_eq!((udi[1].read, udi[1].sequence.as_str()), (IndexRole::I5, "AGCGCTAG"));
+}
+
+#[test]
+fn test_partial_kits() {
+    let mut kits = KitDatabase::builtin();
+    assert_eq!(kits.plate_size("idt for illumina ud indexes"), Some(96));
+    assert_eq!(kits.partial_kits(), [("IDT for Illumina UD Indexes", 12, 96)]);
+
+    // A complete plate in a kit file is not partial, and fills in a partial one
+    let plate = KitDatabase::from_csv("# wells: Core=2\nkit,well,index_name,i7,i5\nCore,A1,C1,ACGTACGT,\nCore,B1,C2,TTGGCCAA,\n").unwrap();
+    assert!(plate.partial_kits().is_empty());
+    let rest = KitDatabase::from_csv("kit,well,index_name,i7,i5\nIDT for Illumina UD Indexes,E2,X1,ACGTACGT,\n").unwrap();
+    kits.extend(plate);
+    kits.extend(rest);
+    assert_eq!(kits.partial_kits(), [("IDT for Illumina UD Indexes", 13, 96)]);
+
+    let err = KitDatabase::from_csv("# wells: Core\nkit,well,index_name,i7,i5\n").unwrap_err();
+    assert_eq!(err.to_string(), "line 1: expected '# wells: KIT=N', found '# wells: Core'");
+}
+
+#[test]
+fn test_lookup_and_classify() {
+    let kits = KitDatabase::builtin();
+    let found = kits.lookup("tatagcct");
+    assert_eq!(found.len(), 1);
+    assert_eq!(found[0].index.name, "D501");
+    assert_eq!(found[0].orientation, Orientation::Forward);
+    assert_eq!(found[0].to_string(), "TruSeq CD Indexes D501 i5");
+
+    assert_eq!(kits.classify("TATAGCCT"), Some(Orientation::Forward));
+    assert_eq!(kits.classify("AGGCTATA"), Some(Orientation::ReverseComplement));
+    assert_eq!(kits.classify("ACGTACGT"), None);
+}
+
+#[test]
+fn test_kit_file_errors() {
+    let kits = KitDatabase::from_csv("kit,well,index_name,i7,i5\nMy kit,,X1,acgtacgt,\n").unwrap();
+    assert_eq!(kits.version(), None);
+    assert_eq!(kits.indexes().len(), 1);
+    assert_eq!(kits.indexes()[0].sequence, "ACGTACGT");
+
+    let err = KitDatabase::from_csv("kit,well,index_name,i7,i5\nMy kit,,X1,,\n").unwrap_err();
+    assert_eq!(err.to_string(), "line 2: index 'X1' has neither an i7 nor an i5 sequence");
+    let err = KitDatabase::from_csv("kit,well,index_name,i7,i5\nMy kit,,X1,ACXT,\n").unwrap_err();
+    assert_eq!(err.line, Some(2));
+    assert!(KitDatabase::from_csv("# orientation: sideways\nkit,well,index_name,i7,i5\n").is_err());
+    assert!(KitDatabase::from_csv("kit,name,sequence\n").is_err());
+}
+
+#[test]
+fn test_kit_file_orientation() {
+    // i5 listed for the reverse complement workflow is stored forward
+    let text = "# version: 3\n# orientation: reverse-complemented\nkit,well,index_name,i7,i5,orientation\nCore,A1,C1,ACGTACGT,AACCGGTA,\nCore,B1,C2,TTGGCCAA,CCTTGGAA,forward\n";
+    let kits = KitDatabase::from_csv(text).unwrap();
+    assert_eq!(kits.version(), Some("3"));
+    let sequences: Vec<&str> = kits.indexes().iter().map(|index| index.sequence.as_str()).collect();
+    assert_eq!(sequences, ["ACGTACGT", "TACCGGTT", "TTGGCCAA", "CCTTGGAA"]);
+    assert_eq!(kits.classify("AACCGGTA"), Some(Orientation::ReverseComplement));
+}
+
+#[test]
+fn test_validate_kits() {
+    assert!(KitDatabase::builtin().validate().is_empty());
+    let kits = KitDatabase::from_csv(
+        "kit,well,index_name,i7,i5\nCore,A1,C1,ACGTACGT,\nCore,B1,C1,TTGGCCAA,\nCore,C1,C3,ACGTACGT,\nCore,D1,C4,ACGTAC,\n",
+    )
+    .unwrap();
+    assert_eq!(
+        kits.validate(),
+        [
+            "Core: C1 i7 is listed twice",
+            "Core: C1 and C3 share the i7 sequence ACGTACGT",
+            "Core: i7 sequences have mixed lengths (6, 8)",
+        ]
+    );
+}
+
+#[test]
+fn test_diff_kits() {
+    let old = KitDatabase::from_csv("kit,well,index_name,i7,i5\nCore,A1,C1,ACGTACGT,\nCore,B1,C2,TTGGCCAA,\n").unwrap();
+    let new = KitDatabase::from_csv("kit,well,index_name,i7,i5\nCore,A1,C1,ACGTACGA,\nCore,C1,C3,GGGGCCCC,\n").unwrap();
+    let changes: Vec<String> = old.diff(&new).iter().map(|change| change.to_string()).collect();
+    assert_eq!(
+        changes,
+        [
+            "~ Core C1 i7 (A1): ACGTACGT -> ACGTACGA",
+            "- Core C2 i7 (B1): TTGGCCAA",
+            "+ Core C3 i7 (C1): GGGGCCCC",
+        ]
+    );
+    assert!(old.diff(&old).is_empty());
+}
+
+#[test]
+fn test_adapter_read_through() {
+    use tracseq_rc::instrument::Workflow;
+    use tracseq_rc::kits::{Adapter, read_through};
+
+    let kits = KitDatabase::builtin();
+    let d501 = kits.lookup("TATAGCCT");
+    assert_eq!(d501[0].index.adapter, Some(Adapter::TruSeq));
+    assert_eq!(kits.lookup("CCGCGGTT")[0].index.adapter, None);
+
+    assert_eq!(&read_through(None, IndexRole::I7, Workflow::Forward).unwrap()[..2], "AT");
+    assert_eq!(&read_through(None, IndexRole::I5, Workflow::ReverseComplement).unwrap()[..2], "GT");
+    assert_eq!(read_through(None, IndexRole::I5, Workflow::Forward), None);
+    assert_eq!(&read_through(Some(Adapter::TruSeq), IndexRole::I5, Workflow::Forward).unwrap()[..2], "AC");
+    assert_eq!(&read_through(Some(Adapter::Nextera), IndexRole::I5, Workflow::Forward).unwrap()[..2], "TC");
+
+    let error = KitDatabase::from_csv("kit,well,index_name,i7,i5,adapter\nK,,X1,ACGTACGT,,other\n").unwrap_err();
+    assert_eq!(error.line, Some(2));
+}
@@ -0,0 +1,71 @@
+use tracseq_rc::kits::KitDatabase;
+use tracseq_rc::layout::{PlateLayout, PlateSize, check_layout};
+use tracseq_rc::platemap::PlateMap;
+
+#[test]
+fn test_index_well() {
+    let size = PlateSize::WELLS_96;
+    assert_eq!(PlateLayout::Recorded.index_well("a01", size).as_deref(), Some("A1"));
+    assert_eq!(PlateLayout::Rotated.index_well("A1", size).as_deref(), Some("H12"));
+    assert_eq!(PlateLayout::Rotated.index_well("C5", size).as_deref(), Some("F8"));
+    assert_eq!(PlateLayout::ShiftedColumns(1).index_well("B3", size).as_deref(), Some("B4"));
+    assert_eq!(PlateLayout::ShiftedColumns(1).index_well("B12", size), None);
+    assert_eq!(PlateLayout::ShiftedRows(-1).index_well("A1", size), None);
+    assert_eq!(PlateLayout::Rotated.index_well("A1", PlateSize::WELLS_384).as_deref(), Some("P24"));
+}
+
+const MAP: &str = "Well,i7,i5\nA1,AAAAAAAA,CCCCCCCC\nA2,ACACACAC,GTGTGTGT\nA3,AGAGAGAG,TCTCTCTC\nA4,ATATATAT,TGTGTGTG\nH12,CACACACA,GAGAGAGA\nH11,CTCTCTCT,GAAGAAGA\n";
+
+#[test]
+fn test_check_layout_finds_a_shift() {
+    let map = PlateMap::from_csv(MAP).unwrap();
+    assert_eq!(PlateSize::of(&map), PlateSize::WELLS_96);
+    let wells = [(None, "A1"), (None, "A2"), (None, "A3")];
+    // The reads carry the indexes of A2-A4, plus a little of A1
+    let reads = |i7: &str| match i7 {
+        "ACACACAC" | "AGAGAGAG" | "ATATATAT" => 100,
+        "AAAAAAAA" => 2,
+        _ => 0,
+    };
+    let check = check_layout(&wells, &map, |well| reads(&well.i7));
+    assert_eq!(check.likely_error, Some(PlateLayout::ShiftedColumns(1)));
+    assert_eq!((check.scores[0].score, check.observed), (202, 302));
+    assert_eq!(check.scores[2].matched, 3);
+}
+
+#[test]
+fn test_check_layout_keeps_the_recorded_wells() {
+    let map = PlateMap::from_csv(MAP).unwrap();
+    let wells = [(None, "A1"), (None, "A2")];
+    let check = check_layout(&wells, &map, |well| if well.well == "A1" || well.well == "A2" { 50 } else { 0 });
+    assert_eq!(check.likely_error, None);
+    // Nothing observed, nothing to report
+    assert_eq!(check_layout(&wells, &map, |_| 0).likely_error, None);
+}
+
+#[test]
+fn test_check_layout_with_the_builtin_kit() {
+    let kits = KitDatabase::builtin();
+    let map = PlateMap::from_kit(&kits, "IDT for Illumina UD Indexes").unwrap();
+    let kit_wells = ["A1", "B1", "C1", "D1", "E1", "F1", "G1", "H1", "A2", "B2", "C2", "D2"];
+    assert_eq!(map.well_count(), kit_wells.len());
+    let rotated: Vec<String> = kit_wells
+        .iter()
+        .map(|well| PlateLayout::Rotated.index_well(well, PlateSize::WELLS_96).unwrap())
+        .collect();
+
+    // Samples in the bundled wells of a rotated plate got indexes the kit
+    // does not list: nothing can be found, and the check says why
+    let wells: Vec<(Option<&str>, &str)> = kit_wells.iter().map(|well| (None, *well)).collect();
+    let check = check_layout(&wells, &map, |_| 0);
+    assert_eq!((check.likely_error, check.observed), (None, 0));
+    let unchecked: Vec<(PlateLayout, usize)> = check.unchecked().iter().map(|score| (score.layout, score.unmapped)).collect();
+    assert!(unchecked.contains(&(PlateLayout::Rotated, 12)));
+    assert!(!unchecked.iter().any(|(layout, _)| *layout == PlateLayout::Recorded));
+
+    // Samples recorded in the opposite corner got the bundled wells' indexes
+    let wells: Vec<(Option<&str>, &str)> = rotated.iter().map(|well| (None, well.as_str())).collect();
+    let check = check_layout(&wells, &map, |_| 100);
+    assert_eq!(check.likely_error, Some(PlateLayout::Rotated));
+    assert_eq!((check.scores[1].matched, check.scores[0].unmapped), (12, 12));
+}
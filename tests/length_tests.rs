@@ -0,0 +1,31 @@
+use tracseq_rc::length::{LengthMode, mixed_lengths, resize_index};
+
+#[test]
+fn test_length_mode() {
+    assert_eq!("truncate".parse::<LengthMode>(), Ok(LengthMode::Truncate));
+    assert_eq!("PAD".parse::<LengthMode>(), Ok(LengthMode::Pad));
+    assert_eq!("pad-adapter".parse::<LengthMode>(), Ok(LengthMode::PadAdapter));
+    assert!("trim".parse::<LengthMode>().is_err());
+    assert_eq!(LengthMode::Truncate.target(8, 10), 8);
+    assert_eq!(LengthMode::PadAdapter.target(8, 10), 10);
+    assert_eq!(LengthMode::PadAdapter.to_string(), "pad-adapter");
+}
+
+#[test]
+fn test_mixed_lengths() {
+    assert_eq!(mixed_lengths([8, 10, 8]), Some((8, 10)));
+    assert_eq!(mixed_lengths([8, 8]), None);
+    // A sample without the index counts as length 0
+    assert_eq!(mixed_lengths([8, 0]), Some((0, 8)));
+    assert_eq!(mixed_lengths([]), None);
+}
+
+#[test]
+fn test_resize_index() {
+    assert_eq!(resize_index("ATTACTCGAA", 8, ""), "ATTACTCG");
+    assert_eq!(resize_index("ATTACTCG", 10, ""), "ATTACTCGNN");
+    assert_eq!(resize_index("ATTACTCG", 10, "AT"), "ATTACTCGAT");
+    assert_eq!(resize_index("ATTACTCG", 11, "AT"), "ATTACTCGATN");
+    assert_eq!(resize_index("", 4, ""), "NNNN");
+    assert_eq!(resize_index("ATTACTCG", 8, "AT"), "ATTACTCG");
+}
@@ -0,0 +1,49 @@
+use tracseq_rc::Operation;
+use tracseq_rc::instrument::IndexRole;
+use tracseq_rc::mgi::{BarcodeMode, MgiBarcode, barcode_mode, parse_barcodes, read_barcodes, write_barcodes};
+
+#[test]
+fn test_orientation_table() {
+    assert_eq!(BarcodeMode::Single.operation(IndexRole::I7), Some(Operation::ReverseComplement));
+    assert_eq!(BarcodeMode::Single.operation(IndexRole::I5), None);
+    assert_eq!(BarcodeMode::Dual.operation(IndexRole::I7), Some(Operation::ReverseComplement));
+    assert_eq!(BarcodeMode::Dual.operation(IndexRole::I5), Some(Operation::Passthrough));
+    assert_eq!(BarcodeMode::Dual.barcode(IndexRole::I7, "ATTACTCG").as_deref(), Some("CGAGTAAT"));
+    assert_eq!(BarcodeMode::Dual.barcode(IndexRole::I5, "TATAGCCT").as_deref(), Some("TATAGCCT"));
+}
+
+#[test]
+fn test_parse_barcodes() {
+    let single = parse_barcodes("# barcode list\n1\tcgagtaat\n2  TCTCCGGA\n\n").unwrap();
+    assert_eq!(barcode_mode(&single), BarcodeMode::Single);
+    assert_eq!(single[0].barcode, "CGAGTAAT");
+    assert_eq!(single[1].name, "2");
+
+    let dual = parse_barcodes("S1\tCGAGTAAT\tTATAGCCT\n").unwrap();
+    assert_eq!(barcode_mode(&dual), BarcodeMode::Dual);
+    assert_eq!(dual[0].barcode2.as_deref(), Some("TATAGCCT"));
+
+    let err = parse_barcodes("1\tCGAGTAAT\n2\tTCTCCGGA\tATAGAGGC\n").unwrap_err();
+    assert_eq!(err.line, Some(2));
+    assert!(parse_barcodes("1\tCGAGTAXT\n").is_err());
+    assert!(parse_barcodes("1\n").is_err());
+
+    // Reading keeps invalid bases, with the line they are on
+    let read = read_barcodes("# barcode list\n1\tcgagtaxt\n").unwrap();
+    assert_eq!(read[0].0, 2);
+    assert_eq!(read[0].1.barcode, "CGAGTAXT");
+}
+
+#[test]
+fn test_write_barcodes() {
+    let barcodes = [
+        MgiBarcode {
+            name: "S1".to_string(),
+            barcode: "CGAGTAAT".to_string(),
+            barcode2: Some("TATAGCCT".to_string()),
+        },
+    ];
+    let text = write_barcodes(&barcodes);
+    assert_eq!(text, "S1\tCGAGTAAT\tTATAGCCT\n");
+    assert_eq!(parse_barcodes(&text).unwrap(), barcodes);
+}
@@ -0,0 +1,43 @@
+use tracseq_rc::kits::KitDatabase;
+use tracseq_rc::platemap::{PlateMap, normalize_well};
+
+#[test]
+fn test_normalize_well() {
+    assert_eq!(normalize_well("a01").as_deref(), Some("A1"));
+    assert_eq!(normalize_well(" H 12 ").as_deref(), Some("H12"));
+    assert_eq!(normalize_well("P24").as_deref(), Some("P24"));
+    assert_eq!(normalize_well("Q1"), None);
+    assert_eq!(normalize_well("A25"), None);
+    assert_eq!(normalize_well("A"), None);
+}
+
+#[test]
+fn test_plate_map_from_csv() {
+    let map = PlateMap::from_csv(
+        "# orientation: reverse-complemented\nPlate,Well,index,index2\nP1,A01,attactcg,AGGCTATA\nP2,A1,TCCGGAGA,GCCTCTAT\nP2,A1,CGCTCATT,AGGATAGG\n",
+    )
+    .unwrap();
+    // i5 is stored forward
+    assert_eq!(map.wells()[0].i5, "TATAGCCT");
+    assert_eq!(map.get(Some("p1"), "A1").unwrap().i7, "ATTACTCG");
+    assert_eq!(map.get(Some("P2"), "a01").unwrap().i7, "TCCGGAGA");
+    assert_eq!(map.get(Some("P3"), "A1"), None);
+    assert_eq!(map.get(None, "A1"), None);
+    assert_eq!(map.duplicates(), ["P2 A1"]);
+
+    assert!(PlateMap::from_csv("Plate,index\nP1,ATTACTCG\n").is_err());
+    let err = PlateMap::from_csv("Well,i7\nZ9,ATTACTCG\n").unwrap_err();
+    assert_eq!(err.line, Some(2));
+}
+
+#[test]
+fn test_plate_map_from_kit() {
+    let kits = KitDatabase::builtin();
+    let map = PlateMap::from_kit(&kits, "IDT for Illumina UD Indexes").unwrap();
+    // Kit wells match on every plate
+    let well = map.get(Some("Plate 7"), "B1").unwrap();
+    assert_eq!((well.i7.as_str(), well.i5.as_str()), ("TTATAACC", "GATATCGA"));
+    assert_eq!(well.name.as_deref(), Some("UDI0002"));
+    assert!(map.duplicates().is_empty());
+    assert_eq!(PlateMap::from_kit(&kits, "TruSeq CD Indexes"), None);
+}
@@ -0,0 +1,120 @@
+use tracseq_rc::samplesheet::{SampleSheet, SheetVersion, is_sample_sheet, override_index_cycles};
+
+const SHEET: &str = "[Header],,,,\r\nIEMFileVersion,4,,,\r\nExperiment Name,Run 42,,,\r\n\r\n[Reads],,,,\r\n151,,,,\r\n151,,,,\r\n\r\n[Settings],,,,\r\nAdapter,CTGTCTCTTATACACATCT,,,\r\n\r\n[Data],,,,\r\nSample_ID,Sample_Name,index,index2,Description\r\nS1,\"Liver, rep 1\",ATTACTCG,TATAGCCT,\r\nS2,Kidney,TCCGGAGA,ATAGAGGC,\r\n";
+
+#[test]
+fn test_sample_sheet_round_trips_verbatim() {
+    let sheet = SampleSheet::parse(SHEET);
+    assert_eq!(sheet.to_string(), SHEET);
+    let names: Vec<&str> = sheet.sections().iter().map(|s| s.name()).collect();
+    assert_eq!(names, ["Header", "Reads", "Settings", "Data"]);
+    assert_eq!(sheet.section("reads").unwrap().lines(), ["151,,,,", "151,,,,", ""]);
+
+    let unix = "[Data]\nSample_ID,index\nS1,ACGT";
+    assert_eq!(SampleSheet::parse(unix).to_string(), unix);
+}
+
+#[test]
+fn test_data_table() {
+    let sheet = SampleSheet::parse(SHEET);
+    let table = sheet.section("Data").unwrap().table().unwrap();
+    assert_eq!(table.columns, ["Sample_ID", "Sample_Name", "index", "index2", "Description"]);
+    assert_eq!(table.column("INDEX2"), Some(3));
+    assert_eq!(table.rows.len(), 2);
+    assert_eq!(table.rows[0].get(1), Some("Liver, rep 1"));
+    assert_eq!(table.rows[0].line_number(), 14);
+    assert_eq!(table.rows[1].line_number(), 15);
+}
+
+#[test]
+fn test_only_changed_rows_are_rewritten() {
+    let mut sheet = SampleSheet::parse(SHEET);
+    let data = sheet.section_mut("Data").unwrap();
+    let mut table = data.table().unwrap();
+    table.rows[0].fields[3] = "AGGCTATA".to_string();
+    for row in &table.rows {
+        data.update_row(row);
+    }
+    let expected = SHEET.replace(
+        "S1,\"Liver, rep 1\",ATTACTCG,TATAGCCT,",
+        "S1,\"Liver, rep 1\",ATTACTCG,AGGCTATA,",
+    );
+    assert_eq!(sheet.to_string(), expected);
+}
+
+#[test]
+fn test_is_sample_sheet() {
+    assert!(is_sample_sheet(SHEET));
+    assert!(is_sample_sheet("\u{feff}\n,,,\n[Header]\n"));
+    assert!(!is_sample_sheet("Id,Index\n1,ACGT\n"));
+    assert!(!is_sample_sheet(""));
+}
+
+const V2_SHEET: &str = "[Header]\nFileFormatVersion,2\nRunName,Run 43\n\n[Reads]\nRead1Cycles,151\nIndex1Cycles,10\nIndex2Cycles,10\n\n[BCLConvert_Settings]\nOverrideCycles,Y151;I8N2;I8N2;Y151\n\n[BCLConvert_Data]\nSample_ID,Index,Index2\nS1,ATTACTCG,TATAGCCT\n\n[Cloud_Data]\nSample_ID,ProjectName,LibraryName\nS1,Liver,S1_lib\n";
+
+#[test]
+fn test_v2_sample_sheet() {
+    let sheet = SampleSheet::parse(V2_SHEET);
+    assert_eq!(sheet.version(), SheetVersion::V2);
+    assert_eq!(sheet.to_string(), V2_SHEET);
+    assert_eq!(SampleSheet::parse(SHEET).version(), SheetVersion::V1);
+    assert_eq!(SheetVersion::V2.data_section(), "BCLConvert_Data");
+    assert_eq!(SheetVersion::V2.index_columns(), ("Index", "Index2"));
+    assert_eq!(SheetVersion::V2.sample_column(), "Sample_ID");
+
+    let settings = sheet.section("BCLConvert_Settings").unwrap();
+    assert_eq!(settings.value("overridecycles").as_deref(), Some("Y151;I8N2;I8N2;Y151"));
+    assert_eq!(settings.value("AdapterRead1"), None);
+
+    let table = sheet.section("BCLConvert_Data").unwrap().table().unwrap();
+    assert_eq!(table.column("Index2"), Some(2));
+    assert_eq!(sheet.section("Cloud_Data").unwrap().lines()[1], "S1,Liver,S1_lib");
+}
+
+#[test]
+fn test_override_index_cycles() {
+    assert_eq!(override_index_cycles("Y151;I8N2;I8N2;Y151"), Ok(vec![8, 8]));
+    assert_eq!(override_index_cycles("Y151;I10;I10;Y151"), Ok(vec![10, 10]));
+    assert_eq!(override_index_cycles("Y151;I8;N10;Y151"), Ok(vec![8, 0]));
+    assert_eq!(override_index_cycles("U8Y143;I8U9;Y151"), Ok(vec![8]));
+    assert!(override_index_cycles("Y151;X8").is_err());
+}
+
+#[test]
+fn test_retain_rows() {
+    let mut sheet = SampleSheet::parse(SHEET);
+    let data = sheet.section_mut("Data").unwrap();
+    data.retain_rows(|row| row.get(0) != Some("S1"));
+    let expected = SHEET.replace("S1,\"Liver, rep 1\",ATTACTCG,TATAGCCT,\r\n", "");
+    assert_eq!(sheet.to_string(), expected);
+    assert_eq!(sheet.section("Data").unwrap().table().unwrap().rows.len(), 1);
+}
+
+#[test]
+fn test_replace_row() {
+    let mut sheet = SampleSheet::parse(SHEET);
+    let data = sheet.section_mut("Data").unwrap();
+    let table = data.table().unwrap();
+    let copies: Vec<Vec<String>> = ["AAAA", "CCCC"]
+        .iter()
+        .map(|index| ["S2", "Kidney", index, "", ""].map(String::from).to_vec())
+        .collect();
+    data.replace_row(&table.rows[1], &copies);
+    let expected = SHEET.replace("S2,Kidney,TCCGGAGA,ATAGAGGC,\r\n", "S2,Kidney,AAAA,,\r\nS2,Kidney,CCCC,,\r\n");
+    assert_eq!(sheet.to_string(), expected);
+}
+
+#[test]
+fn test_aviti_run_manifest() {
+    let text = "[RUNVALUES]\nKeyName,Value\n\n[SETTINGS]\nSettingName,Value\nI1MaskManifest,I1:Y*\n\n[SAMPLES]\nSampleName,Index1,Index2,Lane\nS1,ATTACTCG,TATAGCCT,1\n";
+    let sheet = SampleSheet::parse(text);
+    assert_eq!(sheet.version(), SheetVersion::Aviti);
+    assert_eq!(sheet.to_string(), text);
+    assert_eq!(SheetVersion::Aviti.data_section(), "SAMPLES");
+    assert_eq!(SheetVersion::Aviti.index_columns(), ("Index1", "Index2"));
+    assert_eq!(SheetVersion::Aviti.sample_column(), "SampleName");
+
+    let table = sheet.section("SAMPLES").unwrap().table().unwrap();
+    assert_eq!(table.column("SampleName"), Some(0));
+    assert_eq!(table.rows[0].get(2), Some("TATAGCCT"));
+}
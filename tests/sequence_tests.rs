@@ -0,0 +1,70 @@
+use tracseq_rc::{Alphabet, DnaSequence, IndexSequence, Operation, Orientation, RcOptions, SequenceError};
+
+#[test]
+fn test_dna_sequence_validation() {
+    let seq = DnaSequence::new("ACGTN").unwrap();
+    assert_eq!(seq.as_str(), "ACGTN");
+    assert_eq!(seq.len(), 5);
+    assert_eq!(seq.alphabet(), Alphabet::Dna);
+
+    assert!(DnaSequence::new("").unwrap().is_empty());
+    assert_eq!(
+        DnaSequence::new("Sample-1"),
+        Err(SequenceError::InvalidBase { base: 'p', offset: 3 })
+    );
+    assert_eq!(
+        "ACG T".parse::<DnaSequence>(),
+        Err(SequenceError::InvalidBase { base: ' ', offset: 3 })
+    );
+}
+
+#[test]
+fn test_dna_sequence_detects_rna() {
+    let seq = DnaSequence::new("GGAUCC").unwrap();
+    assert_eq!(seq.alphabet(), Alphabet::Rna);
+    // Reverse complementing keeps the alphabet it was read with
+    assert_eq!(seq.reverse_complement().as_str(), "GGAUCC");
+    assert_eq!(DnaSequence::new("AACU").unwrap().reverse_complement().as_str(), "AGUU");
+}
+
+#[test]
+fn test_index_sequence_orientation() {
+    let index = IndexSequence::new("ATTACTCG").unwrap();
+    assert_eq!(index.orientation(), Orientation::Forward);
+    assert_eq!(index.len(), 8);
+
+    let rc = index.reverse_complement();
+    assert_eq!(rc.as_str(), "CGAGTAAT");
+    assert_eq!(rc.orientation(), Orientation::ReverseComplement);
+    assert_eq!(rc.reverse_complement(), index);
+
+    assert_eq!(Orientation::Forward.to_string(), "forward");
+    assert_eq!(Orientation::ReverseComplement.to_string(), "reverse-complemented");
+}
+
+#[test]
+fn test_index_sequence_rejects_empty_and_invalid() {
+    assert_eq!(IndexSequence::new(""), Err(SequenceError::Empty));
+    assert!(IndexSequence::try_from("D701").is_err());
+    assert_eq!(
+        IndexSequence::with_orientation("CGAGTAAT", Orientation::ReverseComplement)
+            .unwrap()
+            .orientation(),
+        Orientation::ReverseComplement
+    );
+}
+
+#[test]
+fn test_only_reverse_complement_flips_orientation() {
+    let index = IndexSequence::new("ATTACTCG").unwrap();
+    let options = RcOptions::default();
+    let complemented = index.apply(Operation::Complement, &options);
+    assert_eq!(complemented.as_str(), "TAATGAGC");
+    assert_eq!(complemented.orientation(), Orientation::Forward);
+    assert_eq!(index.apply(Operation::Reverse, &options).as_str(), "GCTCATTA");
+    assert_eq!(index.apply(Operation::Passthrough, &options), index);
+    assert_eq!(
+        index.apply(Operation::ReverseComplement, &options).orientation(),
+        Orientation::ReverseComplement
+    );
+}
@@ -0,0 +1,93 @@
+use tracseq_rc::instrument::Workflow;
+use tracseq_rc::tenx::{TenxIndexSet, TenxIndexSets, is_set_name, set_type};
+
+#[test]
+fn test_is_set_name() {
+    assert!(is_set_name("SI-GA-A1"));
+    assert!(is_set_name(" si-tt-h12 "));
+    assert!(is_set_name("SI-NN-B3"));
+    assert!(!is_set_name("SI-GA-I1"));
+    assert!(!is_set_name("SI-GA-A13"));
+    assert!(!is_set_name("SI-GA-A01"));
+    assert!(!is_set_name("D701-ATTACTCG"));
+    assert!(!is_set_name("ATTACTCG"));
+}
+
+#[test]
+fn test_builtin_sets() {
+    let sets = TenxIndexSets::builtin();
+    assert_eq!(
+        sets.get("si-ga-a1"),
+        Some(&TenxIndexSet::Single {
+            name: "SI-GA-A1".to_string(),
+            oligos: ["GGTTTACT", "CTAAACGG", "TCGGCGTC", "AACCGTAA"].map(String::from).to_vec(),
+        })
+    );
+    let dual = sets.get("SI-TT-A1").unwrap();
+    assert_eq!(dual.i5(Workflow::Forward).as_deref(), Some("AGTGTTACCT"));
+    assert_eq!(dual.i5(Workflow::ReverseComplement).as_deref(), Some("AGGTAACACT"));
+    assert_eq!(sets.get("SI-TT-H12"), None);
+
+    // The four oligos of a single-index set cover every base at every cycle
+    for set in sets.sets() {
+        if let TenxIndexSet::Single { name, oligos } = set {
+            for cycle in 0..oligos[0].len() {
+                let mut bases: Vec<u8> = oligos.iter().map(|oligo| oligo.as_bytes()[cycle]).collect();
+                bases.sort();
+                assert_eq!(bases, b"ACGT", "{} cycle {}", name, cycle + 1);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_set_coverage() {
+    let mut sets = TenxIndexSets::builtin();
+    let expected = |counts: [usize; 5]| -> Vec<(String, usize)> {
+        ["SI-GA", "SI-NA", "SI-TT", "SI-TN", "SI-NN"].iter().map(|kind| kind.to_string()).zip(counts).collect()
+    };
+    assert_eq!(sets.coverage(), expected([12, 0, 1, 0, 0]));
+    // Later rows and the other dual-index types are only known from loaded tables
+    for name in ["SI-TT-B3", "SI-TN-A1", "SI-NN-A1"] {
+        assert_eq!(sets.get(name), None, "{}", name);
+    }
+    assert_eq!(set_type(" si-tt-b3 ").as_deref(), Some("SI-TT"));
+    assert_eq!(set_type("D701"), None);
+
+    let text = "index_name,index(i7),index2_workflow_a(i5),index2_workflow_b(i5)\nSI-TN-A1,ACGTACGTAC,GGTTTTCCCC,GGGGAAAACC\n";
+    sets.extend(TenxIndexSets::from_csv(text).unwrap());
+    assert_eq!(sets.coverage(), expected([12, 0, 1, 1, 0]));
+    assert_eq!(sets.get("si-tn-a1").unwrap().i5(Workflow::Forward).as_deref(), Some("GGTTTTCCCC"));
+}
+
+#[test]
+fn test_dual_index_table() {
+    let text = "index_name,index(i7),index2_workflow_a(i5),index2_workflow_b(i5)\nSI-TT-B1,acgtacgtac,,GGGGAAAACC\n";
+    let sets = TenxIndexSets::from_csv(text).unwrap();
+    assert_eq!(
+        sets.sets(),
+        [TenxIndexSet::Dual {
+            name: "SI-TT-B1".to_string(),
+            i7: "ACGTACGTAC".to_string(),
+            i5: "GGTTTTCCCC".to_string(),
+        }]
+    );
+
+    let mismatched = "index_name,index(i7),index2_workflow_a(i5),index2_workflow_b(i5)\nSI-TT-B1,ACGTACGTAC,AAAACCCCGG,AAAACCCCGG\n";
+    let error = TenxIndexSets::from_csv(mismatched).unwrap_err();
+    assert_eq!(error.line, Some(2));
+    assert!(TenxIndexSets::from_csv("SI-GA-A1,GGTTTACT,XX\n").is_err());
+    assert!(TenxIndexSets::from_csv("D701,ATTACTCG\n").is_err());
+}
+
+#[test]
+fn test_extend_replaces_known_sets() {
+    let mut sets = TenxIndexSets::builtin();
+    let count = sets.sets().len();
+    sets.extend(TenxIndexSets::from_csv("# corrected\nSI-GA-A1,AAAAAAAA,CCCCCCCC,GGGGGGGG,TTTTTTTT\nSI-GA-B1,ACGTACGT\n").unwrap());
+    assert_eq!(sets.sets().len(), count + 1);
+    match sets.get("SI-GA-A1") {
+        Some(TenxIndexSet::Single { oligos, .. }) => assert_eq!(oligos[0], "AAAAAAAA"),
+        other => panic!("unexpected {:?}", other),
+    }
+}